@@ -1,21 +1,31 @@
 use tokio::sync::Mutex;
 use tracing::{error, info};
 use tauri_plugin_opener::OpenerExt;
-use std::sync::{Arc, OnceLock};
-use tokio::sync::mpsc::{Sender, channel};
+use std::{
+    collections::HashMap,
+    sync::{Arc, OnceLock, atomic::{AtomicU64, Ordering}},
+};
+use tokio::{
+    sync::mpsc::{Sender, channel},
+    time::{sleep, timeout, Duration},
+};
 use tauri::{
-    Manager, WindowEvent, RunEvent,
+    Manager, WindowEvent, RunEvent, WebviewWindow,
     AppHandle, menu::{Menu, MenuItem}, 
     tray::{MouseButton, MouseButtonState,
-        TrayIconBuilder, TrayIconEvent
+        TrayIcon, TrayIconBuilder, TrayIconEvent
     }
 };
 use tracing_appender::non_blocking::WorkerGuard;
 
 use crate::{
-    log, utils, events, overlay,
-    overlay::Overlay,
-    monitors::MonitorDeviceImpl
+    log, utils, events, overlay, tray, hotkeys, autostart,
+    history::BrightnessHistory,
+    selftest::SelfTestReport,
+    overlay::{Overlay, OverlayControl},
+    events::{BroadcastFreeze, MonitorBroadcaster, ReadyGate, WsStatus, PendingConfirm},
+    settings::{Settings, TrayAction},
+    monitors::{MonitorDeviceImpl, DimState}
 };
 
 /// keep it non blocking
@@ -24,8 +34,28 @@ pub struct AppState {
     pub log_guard: Arc<WorkerGuard>, 
     pub monitor_device: Arc<Mutex<Vec<MonitorDeviceImpl>>>,
     pub overlay_tx: Arc<Mutex<Option<Sender<Overlay>>>>,
+    pub overlay_ctl_tx: Arc<Mutex<Option<Sender<OverlayControl>>>>,
+    /// per device dim backend & level, keyed by `device_name`
+    pub dim_state: Arc<Mutex<HashMap<String, DimState>>>,
+    pub settings: Arc<Mutex<Settings>>,
+    pub broadcast_freeze: Arc<BroadcastFreeze>,
+    pub broadcaster: MonitorBroadcaster,
+    /// automatic features await this after startup
+    pub ready_gate: Arc<ReadyGate>,
+    /// empty unless `Settings::record_history` is on
+    pub history: Arc<Mutex<BrightnessHistory>>,
+    /// last `self_test` run, empty until one finished
+    pub self_test: Arc<Mutex<Vec<SelfTestReport>>>,
+    pub ws_status: Arc<Mutex<WsStatus>>,
+    /// reverts waiting on `confirm_brightness`, keyed by `device_name`
+    pub pending_confirms: Arc<Mutex<HashMap<String, PendingConfirm>>>,
+    /// set once the tray is built, its tooltip & icon show the brightness
+    pub tray: Arc<Mutex<Option<TrayIcon>>>,
 }
 
+/// how long quitting waits on the overlay windows to go away
+const OVERLAY_SHUTDOWN_TIMEOUT: Duration = Duration::from_millis(500);
+
 /// global app handle
 pub static APP_HANDLE: OnceLock<AppHandle> = OnceLock::new();
 
@@ -33,28 +63,133 @@ pub fn app_handle<'a>() -> &'a AppHandle {
     APP_HANDLE.get().expect("app handle could not initialized")
 }
 
+/// keeps the monitor hosting the window undimmed while it's visible, so the sliders stay readable
+pub fn reveal_window_monitor(window: &WebviewWindow, visible: bool) {
+    let device_name = if visible { utils::window_device_name(window) } else { None };
+    let state = app_handle().state::<AppState>().inner().clone();
+    tauri::async_runtime::spawn(async move {
+        if visible && !state.settings.lock().await.reveal_window_monitor {
+            return;
+        }
+        if let Some(tx) = state.overlay_ctl_tx.lock().await.as_ref() {
+            let _ = tx.send(OverlayControl::Reveal(device_name)).await;
+        }
+    });
+}
+
 pub fn run() {
     let builder = tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .invoke_handler(tauri::generate_handler![
             events::set_brightness,
+            events::get_brightness,
+            events::adjust_brightness,
+            events::reset_monitor,
+            events::set_monitor_label,
+            events::clear_monitor_label,
+            events::set_monitor_alias,
+            events::clear_monitor_alias,
+            events::set_min_brightness,
+            events::gradient_dim,
+            events::cinema_fade,
+            events::dim_monitors,
+            events::clear_gradient_dim,
+            events::freeze_broadcasts,
+            events::set_autostart,
+            events::get_autostart,
+            events::get_dim_backend,
+            events::set_dim_backend,
+            events::set_fullscreen_gamma,
+            events::check_conflicts,
+            events::cursor_monitor,
+            events::set_cursor_brightness,
+            events::adjust_cursor_brightness,
+            events::set_color_temperature,
+            events::get_color_temperature,
+            events::set_warmth,
+            events::internal_supported_levels,
+            events::get_supported_levels,
+            events::set_overlay_color,
+            events::set_overlay_click_through,
+            events::list_all_monitors,
+            events::set_brightness_nits,
+            events::set_nits,
+            events::reading_mode,
+            events::privacy_screen,
+            events::lock_monitor,
+            events::unlock_monitor,
+            events::export_settings,
+            events::import_settings,
+            events::simulate_brightness_change,
+            events::brightness_history,
+            events::set_brightness_map,
+            events::set_brightness_all,
+            events::can_control,
+            events::self_test,
+            events::ws_status,
+            events::ws_url,
+            events::overlay_stats,
+            events::factory_reset,
+            events::save_profile,
+            events::apply_profile,
+            events::list_profiles,
+            events::set_contrast,
+            events::get_vcp,
+            events::set_vcp,
+            events::get_capabilities,
+            events::protocol_schema,
+            events::set_input_source,
+            events::set_brightness_confirm,
+            events::confirm_brightness,
+            events::preview_schedule,
         ])
         .setup(|app| {
             APP_HANDLE.set(app.handle().clone())
                 .map_err(|e| anyhow::anyhow!("failed to set global `AppHandle`: {:#?}", e))?;
 
             let log_guard = log::init_logging(app)?;
+            let settings = Settings::load().unwrap_or_else(|e| {
+                error!("failed to load settings, using defaults: {:?}", e);
+                Settings::default()
+            });
+
+            #[cfg(feature = "hid")]
+            crate::hid::init(settings.hid_devices.clone());
+            let tray_actions = settings.tray_actions;
+            let hotkey_config = settings.hotkeys.clone();
+            crate::mock::init(settings.mock_monitors);
+            crate::wmi::init(settings.wmi_fallback);
+            crate::brightness::set_verify_writes(settings.verify_ddcci_writes);
+            crate::brightness::set_max_writes_per_sec(settings.ddcci_max_writes_per_sec);
+            crate::monitors::set_dim_split(settings.dim_split);
+            crate::monitors::set_min_brightness(settings.min_brightness.clone());
+            // turning it off has to remove the entry, even one left by an older install
+            if let Err(e) = autostart::reconcile(settings.autostart) {
+                error!("failed to reconcile autostart: {:?}", e);
+            }
+
             let state = AppState {
                 log_guard: Arc::new(log_guard),
                 monitor_device: Arc::new(Mutex::new(Vec::new())),
                 overlay_tx: Arc::new(Mutex::new(None)),
+                overlay_ctl_tx: Arc::new(Mutex::new(None)),
+                dim_state: Arc::new(Mutex::new(HashMap::new())),
+                settings: Arc::new(Mutex::new(settings)),
+                broadcast_freeze: Arc::new(BroadcastFreeze::default()),
+                broadcaster: MonitorBroadcaster::default(),
+                ready_gate: Arc::new(ReadyGate::default()),
+                history: Arc::new(Mutex::new(BrightnessHistory::default())),
+                self_test: Arc::new(Mutex::new(Vec::new())),
+                ws_status: Arc::new(Mutex::new(WsStatus::default())),
+                pending_confirms: Arc::new(Mutex::new(HashMap::new())),
+                tray: Arc::new(Mutex::new(None)),
             };
             app.manage(state.clone());
 
             tauri::async_runtime::spawn({
                 let state = state.clone();
                 async move {
-                    if let Err(e) = events::start_ws_server(state).await {
+                    if let Err(e) = events::start_ws_server(state, None).await {
                         error!("WebSocket server failed: {:?}", e);
                     }
                 }
@@ -65,46 +200,72 @@ pub fn run() {
                 move || {
                     tauri::async_runtime::block_on(async move {
                         let (tx, rx) = channel::<Overlay>(32);
+                        let (ctl_tx, ctl_rx) = channel::<OverlayControl>(8);
                         *state.overlay_tx.lock().await = Some(tx.clone());
-                        if let Err(e) = overlay::init_overlay(rx).await {
+                        *state.overlay_ctl_tx.lock().await = Some(ctl_tx);
+                        if let Err(e) = overlay::init_overlay(rx, ctl_rx).await {
                             error!("overlay thread crashed: {:?}", e);
                         }
                     });
                 }
             });
 
+            // the device scan may have beaten the overlay thread, its check was skipped then
+            tauri::async_runtime::spawn({
+                let state = state.clone();
+                async move {
+                    overlay::CREATED.notified().await;
+                    events::check_overlay_devices(&state.monitor_device.lock().await);
+                }
+            });
+
             let reset_i = MenuItem::with_id(app, "reset", "Reset", true, None::<&str>)?;
+            let release_i = MenuItem::with_id(app, "release_input", "Release input", true, None::<&str>)?;
             let about_i = MenuItem::with_id(app, "about", "About", true, None::<&str>)?;
             let quit_i = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
 
-            let menu = Menu::with_items(app, &[&reset_i, &about_i, &quit_i])?;
+            let menu = Menu::with_items(app, &[&reset_i, &release_i, &about_i, &quit_i])?;
+            let _ = tray::TRAY_MENU.set(menu.clone());
 
-            let _ = TrayIconBuilder::new()
-                .menu(&menu)
+            tauri::async_runtime::spawn(tray::scroll_brightness(state.clone(), tray::init_scroll_hook()));
+            tauri::async_runtime::spawn(hotkeys::handle_hotkeys(state.clone(), hotkeys::init(&hotkey_config)));
+
+            let mut tray_builder = TrayIconBuilder::with_id(tray::TRAY_ID)
                 .icon(app.default_window_icon().unwrap().clone())
-                .tooltip("fade & brightness")
+                .tooltip(tray::TOOLTIP)
                 .on_tray_icon_event(|tray, event|  {
-                    if let TrayIconEvent::Click {
-                        position,
-                        button: MouseButton::Left,
-                        button_state: MouseButtonState::Up,
-                        ..
-                    } = event {
-                        let app = tray.app_handle();
-                        if let Some(window) = app.get_webview_window("main") {
-                            let is_visible = window.is_visible().unwrap_or(false);
-                            if is_visible {
-                                if let Err(e) = window.hide() {
-                                    error!("failed to hide window: {}", e);
-                                }
-                            } else {
-                                utils::show_tray_window(&window, &position);
-                            }
-                        }
+                    match event {
+                        TrayIconEvent::Click {
+                            position,
+                            button,
+                            button_state: MouseButtonState::Up,
+                            ..
+                        } => tray::on_click(tray.app_handle(), button.into(), position),
+                        TrayIconEvent::DoubleClick {
+                            position,
+                            button: MouseButton::Left,
+                            ..
+                        } => tray::on_click(tray.app_handle(), tray::TrayClick::Double, position),
+                        // wheel over the icon adjusts brightness, see `tray`
+                        TrayIconEvent::Enter { .. } => tray::set_hovering(true),
+                        TrayIconEvent::Leave { .. } => tray::set_hovering(false),
+                        _ => {}
                     }
                 })
-                .show_menu_on_left_click(false)
-                .build(app)?;
+                .show_menu_on_left_click(tray_actions.left == TrayAction::ShowMenu);
+
+            // the native menu pops up on its own, only attach it when a left/right click wants it
+            if tray_actions.left == TrayAction::ShowMenu || tray_actions.right == TrayAction::ShowMenu {
+                tray_builder = tray_builder.menu(&menu);
+            }
+            *state.tray.blocking_lock() = Some(tray_builder.build(app)?);
+
+            // a login launch stays in the tray until it's clicked
+            if autostart::launched_at_login() {
+                if let Some(window) = app.get_webview_window("main") {
+                    window.hide()?;
+                }
+            }
 
             info!("initializing fade & brightness");
             Ok(())
@@ -113,6 +274,19 @@ pub fn run() {
             match event.id().as_ref() {
                 "reset" => {
                     info!("`Reset` menu item clicked");
+                    let state = app.state::<AppState>().inner().clone();
+                    tauri::async_runtime::spawn(async move { events::reset_all(&state).await });
+                }
+                "release_input" => {
+                    info!("`Release input` menu item clicked");
+                    let app = app.clone();
+                    tauri::async_runtime::spawn(async move {
+                        // the way out of an opaque, input blocking overlay
+                        let ctl_tx = app.state::<AppState>().overlay_ctl_tx.lock().await.clone();
+                        if let Some(tx) = ctl_tx {
+                            let _ = tx.send(OverlayControl::ClickThrough { device_name: None, enabled: true }).await;
+                        }
+                    });
                 }
                 "about" => {
                     info!("`About` menu item clicked");
@@ -122,28 +296,56 @@ pub fn run() {
                 }
                 "quit" => {
                     info!("`Quit` menu item clicked, exiting");
-                    app.exit(0);
+                    let app = app.clone();
+                    tauri::async_runtime::spawn(async move {
+                        // take the overlays down first, layered windows otherwise linger until the process is gone
+                        let ctl_tx = app.state::<AppState>().overlay_ctl_tx.lock().await.clone();
+                        if let Some(tx) = ctl_tx {
+                            if tx.send(OverlayControl::Shutdown).await.is_ok() {
+                                let _ = timeout(OVERLAY_SHUTDOWN_TIMEOUT, overlay::STOPPED.notified()).await;
+                            }
+                        }
+                        app.exit(0);
+                    });
                 }
                 _ => {}
             }
         });
 
+    // bumped on every focus change, a pending hide only goes through if nothing happened since
+    let focus_generation = Arc::new(AtomicU64::new(0));
+
     builder
         .build(tauri::generate_context!())
         .expect("error while building tauri application")
-        .run(|app_handle, event| {
-            if let RunEvent::WindowEvent {
-                label,
-                event: WindowEvent::Focused(false),
-                ..
-            } = event {
-                if label == "main" {
-                    if let Some(window) = app_handle.get_webview_window("main") {
-                        if let Err(e) = window.hide() {
-                            error!("failed to hide window on focus lose: {}", e);
-                        }
-                    }
-                }
+        .run(move |app_handle, event| {
+            let RunEvent::WindowEvent { label, event: WindowEvent::Focused(focused), .. } = event else {
+                return;
+            };
+            if label != "main" {
+                return;
+            }
+            let generation = focus_generation.fetch_add(1, Ordering::SeqCst) + 1;
+            if focused {
+                return;
             }
+
+            let Some(window) = app_handle.get_webview_window("main") else {
+                return;
+            };
+            let focus_generation = focus_generation.clone();
+            let state = app_handle.state::<AppState>().inner().clone();
+            tauri::async_runtime::spawn(async move {
+                // dragging across monitors or a popup briefly takes the focus
+                let grace = Duration::from_millis(state.settings.lock().await.hide_grace_ms);
+                sleep(grace).await;
+                if focus_generation.load(Ordering::SeqCst) != generation {
+                    return;
+                }
+                if let Err(e) = window.hide() {
+                    error!("failed to hide window on focus lose: {}", e);
+                }
+                reveal_window_monitor(&window, false);
+            });
         });
 }
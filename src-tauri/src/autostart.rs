@@ -0,0 +1,94 @@
+/*
+ * launch at login through `HKCU\Software\Microsoft\Windows\CurrentVersion\Run`,
+ * the entry passes `AUTOSTART_ARG` so that launch stays in the tray
+*/
+use anyhow::anyhow;
+use std::{env, iter, ffi::{c_void, OsStr}, os::windows::ffi::OsStrExt};
+use windows::{
+    core::{w, PCWSTR},
+    Win32::{
+        Foundation::{ERROR_SUCCESS, ERROR_FILE_NOT_FOUND},
+        System::Registry::{
+            RegGetValueW, RegSetKeyValueW, RegDeleteKeyValueW, HKEY_CURRENT_USER, RRF_RT_REG_SZ, REG_SZ,
+        },
+    }
+};
+
+const RUN_KEY: PCWSTR = w!(r"Software\Microsoft\Windows\CurrentVersion\Run");
+const VALUE_NAME: PCWSTR = w!("fade");
+const AUTOSTART_ARG: &str = "--autostart";
+
+/// started by the `Run` entry rather than by the user
+pub fn launched_at_login() -> bool {
+    env::args().any(|arg| arg == AUTOSTART_ARG)
+}
+
+/// what the `Run` entry should hold for this executable
+fn command_line() -> anyhow::Result<String> {
+    let exe = env::current_exe()?;
+    Ok(format!("\"{}\" {}", exe.display(), AUTOSTART_ARG))
+}
+
+/// the command line in the `Run` entry, `None` without one
+fn registered() -> anyhow::Result<Option<String>> {
+    let mut data = [0u16; 1024];
+    let mut size = (data.len() * 2) as u32;
+    let status = unsafe {
+        RegGetValueW(
+            HKEY_CURRENT_USER,
+            RUN_KEY,
+            VALUE_NAME,
+            RRF_RT_REG_SZ,
+            None,
+            Some(data.as_mut_ptr() as *mut _),
+            Some(&mut size as *mut u32),
+        )
+    };
+    match status {
+        ERROR_SUCCESS => {
+            let len = (size as usize / 2).min(data.len());
+            Ok(Some(String::from_utf16_lossy(&data[..len]).trim_end_matches('\0').to_string()))
+        }
+        ERROR_FILE_NOT_FOUND => Ok(None),
+        status => Err(anyhow!("failed to read the autostart entry, err: {:?}", status)),
+    }
+}
+
+pub fn is_enabled() -> anyhow::Result<bool> {
+    Ok(registered()?.is_some())
+}
+
+pub fn set_enabled(enabled: bool) -> anyhow::Result<()> {
+    let status = if enabled {
+        let wide: Vec<u16> = OsStr::new(&command_line()?).encode_wide().chain(iter::once(0)).collect();
+        unsafe {
+            RegSetKeyValueW(
+                HKEY_CURRENT_USER,
+                RUN_KEY,
+                VALUE_NAME,
+                REG_SZ.0,
+                Some(wide.as_ptr() as *const c_void),
+                (wide.len() * 2) as u32,
+            )
+        }
+    } else {
+        match unsafe { RegDeleteKeyValueW(HKEY_CURRENT_USER, RUN_KEY, VALUE_NAME) } {
+            ERROR_FILE_NOT_FOUND => ERROR_SUCCESS,
+            status => status,
+        }
+    };
+    if status != ERROR_SUCCESS {
+        return Err(anyhow!("failed to {} autostart, err: {:?}", if enabled { "enable" } else { "disable" }, status));
+    }
+    tracing::info!("autostart {}", if enabled { "enabled" } else { "disabled" });
+    Ok(())
+}
+
+/// makes the `Run` entry match the setting, an entry of a moved install gets rewritten
+pub fn reconcile(enabled: bool) -> anyhow::Result<()> {
+    let wanted = if enabled { Some(command_line()?) } else { None };
+    if registered()? != wanted {
+        set_enabled(enabled)?;
+    }
+    Ok(())
+}
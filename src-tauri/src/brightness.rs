@@ -6,18 +6,25 @@
 //! credit: @jacob-pro, @stephaneyfx
 //!
 use std::{
+    thread,
     ffi::c_void,
     mem::size_of,
+    time::{Duration, Instant},
+    collections::HashMap,
+    sync::{LazyLock, Mutex, atomic::{AtomicBool, AtomicU32, Ordering}},
 };
-use anyhow::anyhow;
+use serde::Serialize;
 use windows::{
     core::BOOL,
     Win32::{
+        Foundation::GetLastError,
         System::IO::DeviceIoControl,
         Devices::Display::{
             DISPLAY_BRIGHTNESS, 
             DISPLAYPOLICY_AC, DISPLAYPOLICY_DC,
-            GetMonitorBrightness, SetMonitorBrightness,
+            GetMonitorBrightness, SetMonitorBrightness, SetVCPFeature, GetVCPFeatureAndVCPFeatureReply,
+            GetMonitorContrast, SetMonitorContrast,
+            GetCapabilitiesStringLength, CapabilitiesRequestAndCapabilitiesReply,
             IOCTL_VIDEO_QUERY_DISPLAY_BRIGHTNESS,
             IOCTL_VIDEO_QUERY_SUPPORTED_BRIGHTNESS,
             IOCTL_VIDEO_SET_DISPLAY_BRIGHTNESS,
@@ -25,13 +32,54 @@ use windows::{
     },
 };
 
-use crate::monitors::MonitorDeviceImpl;
+use crate::{monitors::MonitorDeviceImpl, error::MonitorError};
 
 
-#[derive(Debug)]
+/// a way of reading & writing a monitor's brightness percentage.
+/// `anyhow` here, the hid & wmi backends fail in their own ways
+pub trait BrightnessBackend: Send + Sync {
+    fn get(&self, device: &MonitorDeviceImpl) -> anyhow::Result<u32>;
+    fn set(&self, device: &MonitorDeviceImpl, percentage: u32) -> anyhow::Result<()>;
+}
+
+/// internal displays, through `DeviceIoControl`
+pub struct Ioctl;
+
+/// external monitors, through ddc/ci
+pub struct Ddcci;
+
+impl BrightnessBackend for Ioctl {
+    fn get(&self, device: &MonitorDeviceImpl) -> anyhow::Result<u32> {
+        Ok(ioctl_query_display_brightness(device)?)
+    }
+
+    fn set(&self, device: &MonitorDeviceImpl, percentage: u32) -> anyhow::Result<()> {
+        // the nearest supported level, an empty list fails instead of writing 0 & blanking the panel
+        let new_value = ioctl_query_supported_brightness(device)?
+            .get_nearest(percentage)
+            .ok_or(MonitorError::UnsupportedLevel { level: percentage.min(100) as u8, supported: Vec::new() })?;
+        Ok(ioctl_set_display_brightness(device, new_value)?)
+    }
+}
+
+impl BrightnessBackend for Ddcci {
+    fn get(&self, device: &MonitorDeviceImpl) -> anyhow::Result<u32> {
+        Ok(ddcci_get_monitor_brightness(device)?.get_current_percentage())
+    }
+
+    fn set(&self, device: &MonitorDeviceImpl, percentage: u32) -> anyhow::Result<()> {
+        let current = ddcci_get_monitor_brightness(device)?;
+        tracing::debug!("current ddcci monitor brightness: {:?}", current);
+        let new_value = current.percentage_to_current(percentage);
+        Ok(ddcci_set_monitor_brightness(device, new_value)?)
+    }
+}
+
+/// serialized as a plain array of percentages
+#[derive(Debug, Serialize)]
 pub struct IoctlSupportedBrightnessLevels(Vec<u8>);
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub struct DdcciBrightnessValues {
     pub min: u32,
     pub max: u32,
@@ -39,12 +87,31 @@ pub struct DdcciBrightnessValues {
 }
 
 impl IoctlSupportedBrightnessLevels {
-    pub fn get_nearest(&self, percentage: u32) -> u8 {
+    /// percentages the display can actually show, in the order the driver reports them
+    pub fn levels(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// `None` when the driver reports no levels at all
+    pub fn get_nearest(&self, percentage: u32) -> Option<u8> {
         self.0
             .iter()
             .copied()
             .min_by_key(|&num| (num as i64 - percentage as i64).abs())
-            .unwrap_or(0)
+    }
+
+    pub fn contains(&self, level: u8) -> bool {
+        self.0.contains(&level)
+    }
+
+    /// some drivers silently ignore unsupported levels,
+    /// so `ioctl_set_display_brightness` refuses them before they reach the driver
+    pub fn validate(&self, level: u8) -> Result<(), MonitorError> {
+        if self.contains(level) {
+            Ok(())
+        } else {
+            Err(MonitorError::UnsupportedLevel { level, supported: self.0.clone() })
+        }
     }
 }
 
@@ -63,15 +130,53 @@ impl DdcciBrightnessValues {
     }
 }
 
+/// last raw values read per monitor `id`, reported next to the percentage without another read
+static DDCCI_RAW: LazyLock<Mutex<HashMap<String, DdcciBrightnessValues>>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// raw range & value of the last ddc/ci brightness read, `None` until one succeeded
+pub fn ddcci_last_read(id: &str) -> Option<DdcciBrightnessValues> {
+    DDCCI_RAW.lock().ok()?.get(id).copied()
+}
+
+/// attempts per ddc/ci brightness call, some monitors fail one now & then with a generic error
+const DDCCI_ATTEMPTS: u32 = 3;
+/// pause before the second attempt, doubled for each one after
+const DDCCI_RETRY_BACKOFF: Duration = Duration::from_millis(40);
+
+/// runs `f` up to `DDCCI_ATTEMPTS` times, the last error is returned if none succeeds.
+/// blocks for the backoff, like the ddc/ci calls themselves. only transient errors are
+/// retried, a monitor without ddc/ci fails the same every time
+fn with_retries<T>(
+    device: &MonitorDeviceImpl,
+    mut f: impl FnMut() -> Result<T, MonitorError>,
+) -> Result<T, MonitorError> {
+    if device.physical_monitor.0.is_invalid() {
+        return Err(MonitorError::HandleInvalid);
+    }
+    let mut backoff = DDCCI_RETRY_BACKOFF;
+    let mut attempt = 1;
+    loop {
+        match f() {
+            Err(e) if attempt < DDCCI_ATTEMPTS && e.is_transient() => {
+                tracing::debug!(
+                    "ddc/ci attempt {}/{} failed for '{}', retrying in {:?}: {:?}",
+                    attempt, DDCCI_ATTEMPTS, device.friendly_name, backoff, e
+                );
+                thread::sleep(backoff);
+                backoff *= 2;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
 /// returns the brightness percentage of ddc/ci display
 pub fn ddcci_get_monitor_brightness(
     device: &MonitorDeviceImpl,
-) -> anyhow::Result<DdcciBrightnessValues> {
-    unsafe {
+) -> Result<DdcciBrightnessValues, MonitorError> {
+    with_retries(device, || unsafe {
         let mut v = DdcciBrightnessValues::default();
-        if device.physical_monitor.0.is_invalid() {
-            tracing::error!("failed to set monitor brightness, invalid handler");
-        }
         BOOL(GetMonitorBrightness(
             device.physical_monitor.0,
             &mut v.min,
@@ -79,38 +184,287 @@ pub fn ddcci_get_monitor_brightness(
             &mut v.max,
         ))
         .ok()
-        .map(|_| v)
-        .map_err(|e| 
-            anyhow!(
-                "failed to get monitor brightness (ddcci), device: {:#?}, err {:#?}", 
-                device.friendly_name.clone(), e
-            ))
+        .map(|_| {
+            if let Ok(mut raw) = DDCCI_RAW.lock() {
+                raw.insert(device.id.clone(), v);
+            }
+            v
+        })
+        .map_err(MonitorError::from)
+    })
+}
+
+/// `Settings::verify_ddcci_writes`, set from the settings like `wmi::init`
+static VERIFY_WRITES: AtomicBool = AtomicBool::new(false);
+/// ddc/ci wants this long between a write & the next command
+const DDCCI_VERIFY_SETTLE: Duration = Duration::from_millis(50);
+
+pub fn set_verify_writes(enabled: bool) {
+    VERIFY_WRITES.store(enabled, Ordering::Relaxed);
+}
+
+/// `Settings::ddcci_max_writes_per_sec`, 0 doesn't limit
+static MAX_WRITES_PER_SEC: AtomicU32 = AtomicU32::new(10);
+/// when the next brightness write may go out, per physical monitor handle
+static NEXT_WRITE: LazyLock<Mutex<HashMap<isize, Instant>>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+
+pub fn set_max_writes_per_sec(limit: u32) {
+    MAX_WRITES_PER_SEC.store(limit, Ordering::Relaxed);
+}
+
+/// blocks until the monitor may take another write, every caller gets its own slot.
+/// protects monitor controllers that wear or heat up on a flood of writes, even from scripts
+fn wait_for_write_slot(device: &MonitorDeviceImpl) {
+    let limit = MAX_WRITES_PER_SEC.load(Ordering::Relaxed);
+    if limit == 0 {
+        return;
+    }
+    let spacing = Duration::from_secs(1) / limit;
+    let handle = device.physical_monitor.0.0 as isize;
+    let now = Instant::now();
+    let slot = {
+        let Ok(mut next) = NEXT_WRITE.lock() else {
+            return;
+        };
+        let slot = next.get(&handle).map_or(now, |&t| t.max(now));
+        next.insert(handle, slot + spacing);
+        slot
+    };
+    if slot > now {
+        tracing::debug!("rate limiting the write to '{}' for {:?}", device.friendly_name, slot - now);
+        thread::sleep(slot - now);
     }
 }
 
-/// set brightness to ddc/ci monitors
+/// set brightness to ddc/ci monitors, at most `set_max_writes_per_sec` a second per monitor.
+/// with `set_verify_writes` on it's read back,
+/// a monitor that clamped or ignored it (e.g. a picture mode) fails with `BrightnessLocked`
 pub fn ddcci_set_monitor_brightness(
     device: &MonitorDeviceImpl,
     value: u32
-) -> anyhow::Result<()> {
-    unsafe {
-        if device.physical_monitor.0.is_invalid() {
-            tracing::error!("failed to set monitor brightness, invalid handler");
-        }
+) -> Result<(), MonitorError> {
+    wait_for_write_slot(device);
+    with_retries(device, || unsafe {
         BOOL(SetMonitorBrightness(device.physical_monitor.0, value))
             .ok()
-            .map_err(|e| 
-            anyhow!(
-                "failed to set monitor brightness (ddcci), device: {:#?}, err {:#?}", 
-                device.friendly_name.clone(), e
-            ))
+            .map_err(MonitorError::from)
+    })?;
+    if !VERIFY_WRITES.load(Ordering::Relaxed) {
+        return Ok(());
     }
+
+    thread::sleep(DDCCI_VERIFY_SETTLE);
+    let read = ddcci_get_monitor_brightness(device)?;
+    // a percent of the range, rounding in the monitor's firmware isn't drift
+    let tolerance = ((read.max - read.min) / 100).max(1);
+    if read.current.abs_diff(value) > tolerance {
+        tracing::warn!(
+            "'{}' reads {} after writing {} (range {}..={})",
+            device.friendly_name, read.current, value, read.min, read.max
+        );
+        return Err(MonitorError::BrightnessLocked { requested: value, actual: read.current });
+    }
+    Ok(())
+}
+
+/// contrast percentage of a ddc/ci monitor, same scaling as brightness
+pub fn ddcci_get_monitor_contrast(
+    device: &MonitorDeviceImpl,
+) -> Result<u32, MonitorError> {
+    unsafe {
+        let mut v = DdcciBrightnessValues::default();
+        BOOL(GetMonitorContrast(
+            device.physical_monitor.0,
+            &mut v.min,
+            &mut v.current,
+            &mut v.max,
+        ))
+        .ok()
+        .map(|_| v.get_current_percentage())
+        .map_err(MonitorError::from)
+    }
+}
+
+pub fn ddcci_set_monitor_contrast(
+    device: &MonitorDeviceImpl,
+    percentage: u32,
+) -> Result<(), MonitorError> {
+    unsafe {
+        let mut v = DdcciBrightnessValues::default();
+        BOOL(GetMonitorContrast(device.physical_monitor.0, &mut v.min, &mut v.current, &mut v.max)).ok()?;
+
+        BOOL(SetMonitorContrast(device.physical_monitor.0, v.percentage_to_current(percentage)))
+            .ok()
+            .map_err(MonitorError::from)
+    }
+}
+
+/// raw vcp read, `(current, max)`
+pub fn ddcci_get_vcp(device: &MonitorDeviceImpl, code: u8) -> Result<(u32, u32), MonitorError> {
+    let (mut current, mut max) = (0, 0);
+    unsafe {
+        if GetVCPFeatureAndVCPFeatureReply(device.physical_monitor.0, code, None, &mut current, Some(&mut max)) == 0 {
+            return Err(GetLastError().into());
+        }
+    }
+    Ok((current, max))
+}
+
+pub fn ddcci_set_vcp(device: &MonitorDeviceImpl, code: u8, value: u32) -> Result<(), MonitorError> {
+    unsafe {
+        if SetVCPFeature(device.physical_monitor.0, code, value) == 0 {
+            return Err(GetLastError().into());
+        }
+    }
+    Ok(())
+}
+
+/// the monitor's mccs capabilities string, e.g. `(prot(monitor)type(lcd)vcp(02 10 12 14(05 08 0b) 60(0f 11))mccs_ver(2.2))`.
+/// slow, the monitor takes up to a few seconds to answer
+pub fn ddcci_get_capabilities(device: &MonitorDeviceImpl) -> Result<String, MonitorError> {
+    with_retries(device, || unsafe {
+        let mut len = 0;
+        if GetCapabilitiesStringLength(device.physical_monitor.0, &mut len) == 0 {
+            return Err(GetLastError().into());
+        }
+        let mut buffer = vec![0u8; len as usize];
+        if CapabilitiesRequestAndCapabilitiesReply(device.physical_monitor.0, &mut buffer) == 0 {
+            return Err(GetLastError().into());
+        }
+        let end = buffer.iter().position(|&b| b == 0).unwrap_or(buffer.len());
+        Ok(String::from_utf8_lossy(&buffer[..end]).into_owned())
+    })
+}
+
+/// body of the top level `name(...)` section of a capabilities string, nested parens included
+fn capabilities_section<'a>(caps: &'a str, name: &str) -> Option<&'a str> {
+    // the whole string is usually wrapped in one more pair of parens
+    let caps = caps.trim();
+    let caps = caps.strip_prefix('(').and_then(|c| c.strip_suffix(')')).unwrap_or(caps);
+
+    let mut depth = 0;
+    let mut key_start = 0;
+    let mut body_start = None;
+    for (i, c) in caps.char_indices() {
+        match c {
+            '(' => {
+                if depth == 0 && caps[key_start..i].trim().eq_ignore_ascii_case(name) {
+                    body_start = Some(i + 1);
+                }
+                depth += 1;
+            }
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    if let Some(start) = body_start {
+                        return Some(&caps[start..i]);
+                    }
+                    key_start = i + 1;
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// a vcp code a monitor advertises
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct VcpFeature {
+    pub code: u8,
+    /// discrete values it takes, e.g. the inputs of `0x60`. empty for continuous ones like brightness
+    pub values: Vec<u8>,
+}
+
+/// what a monitor's ddc/ci capabilities string advertises, so the ui only shows what it supports
+#[derive(Debug, Default, Clone, Serialize, PartialEq, Eq)]
+pub struct Capabilities {
+    /// `type(...)`, e.g. `lcd`
+    pub display_type: Option<String>,
+    pub model: Option<String>,
+    /// `mccs_ver(...)`, e.g. `2.2`
+    pub mccs_version: Option<String>,
+    /// in the order advertised
+    pub vcp: Vec<VcpFeature>,
+}
+
+impl Capabilities {
+    /// lenient, anything malformed is left out rather than failing the whole string
+    pub fn parse(caps: &str) -> Self {
+        let text = |name| capabilities_section(caps, name).map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+        Self {
+            display_type: text("type"),
+            model: text("model"),
+            mccs_version: text("mccs_ver"),
+            vcp: capabilities_section(caps, "vcp").map(parse_vcp).unwrap_or_default(),
+        }
+    }
+
+    pub fn supports(&self, code: u8) -> bool {
+        self.vcp.iter().any(|f| f.code == code)
+    }
+}
+
+/// `02 10 12 14(05 08 0b) 60(0f 11)`, values nested deeper (mccs 3 sub-features) are skipped
+fn parse_vcp(vcp: &str) -> Vec<VcpFeature> {
+    let mut features: Vec<VcpFeature> = Vec::new();
+    let mut depth = 0;
+    for token in vcp.split_inclusive(['(', ')', ' ']) {
+        if let Ok(value) = u8::from_str_radix(token.trim_end_matches(['(', ')', ' ']), 16) {
+            match depth {
+                0 => features.push(VcpFeature { code: value, values: Vec::new() }),
+                1 => if let Some(feature) = features.last_mut() {
+                    feature.values.push(value);
+                },
+                _ => {}
+            }
+        }
+        match token.chars().last() {
+            Some('(') => depth += 1,
+            Some(')') => depth = (depth - 1).max(0),
+            _ => {}
+        }
+    }
+    features
+}
+
+/// mccs `select color preset` vcp code
+const VCP_SELECT_COLOR_PRESET: u8 = 0x14;
+
+/// mccs color temperature presets, `(kelvin, vcp value)`
+const DDCCI_COLOR_PRESETS: &[(u32, u32)] = &[
+    (4000, 0x03),
+    (5000, 0x04),
+    (6500, 0x05),
+    (7500, 0x06),
+    (8200, 0x07),
+    (9300, 0x08),
+    (10000, 0x09),
+    (11500, 0x0a),
+];
+
+/// selects the monitor's color preset nearest to `kelvin`, returns the kelvin applied
+pub fn ddcci_set_color_preset(
+    device: &MonitorDeviceImpl,
+    kelvin: u32,
+) -> Result<u32, MonitorError> {
+    let &(applied, preset) = DDCCI_COLOR_PRESETS
+        .iter()
+        .min_by_key(|(k, _)| (*k as i64 - kelvin as i64).abs())
+        .expect("color presets aren't empty");
+
+    unsafe {
+        if SetVCPFeature(device.physical_monitor.0, VCP_SELECT_COLOR_PRESET, preset) == 0 {
+            return Err(GetLastError().into());
+        }
+    }
+    Ok(applied)
 }
 
 /// query ioctl brightness (internal display)
 pub fn ioctl_query_supported_brightness(
     device: &MonitorDeviceImpl,
-) -> anyhow::Result<IoctlSupportedBrightnessLevels> {
+) -> Result<IoctlSupportedBrightnessLevels, MonitorError> {
     unsafe {
         let mut bytes_returned = 0;
         let mut out_buffer = Vec::<u8>::with_capacity(256);
@@ -128,18 +482,14 @@ pub fn ioctl_query_supported_brightness(
             out_buffer.set_len(bytes_returned as usize);
             IoctlSupportedBrightnessLevels(out_buffer)
         })
-        .map_err(|e| 
-            anyhow!(
-                "failed to query supported monitor brightness (ioctl), device: {:#?}, err {:#?}", 
-                device.friendly_name.clone(), e
-            ))
+        .map_err(MonitorError::from)
     }
 }
 
 /// returns the brightness percentage of ioctl display
 pub fn ioctl_query_display_brightness(
     device: &MonitorDeviceImpl
-) -> anyhow::Result<u32> {
+) -> Result<u32, MonitorError> {
     unsafe {
         let mut bytes_returned = 0;
         let mut display_brightness = DISPLAY_BRIGHTNESS::default();
@@ -153,11 +503,7 @@ pub fn ioctl_query_display_brightness(
             Some(&mut bytes_returned),
             None,
         )
-        .map_err(|e|
-                anyhow!(
-                    "failed to query monitor brightness (ioctl), device: {:#?}, err {:#?}", 
-                    device.friendly_name.clone(), e
-                ))
+        .map_err(MonitorError::from)
         .and_then(|_| match display_brightness.ucDisplayPolicy as u32 {
             DISPLAYPOLICY_AC => {
                 // this is a value between 0 and 100.
@@ -167,19 +513,25 @@ pub fn ioctl_query_display_brightness(
                 // this is a value between 0 and 100.
                 Ok(display_brightness.ucDCBrightness as u32)
             }
-            _ => Err(anyhow!(
-                "unexpected response when querying display brightness (ioctl), device: {:#?}",
-                device.friendly_name.clone()
-            )),
+            _ => Err(MonitorError::UnexpectedResponse),
         })
     }
 }
 
-/// set brightness for ioctl display
+/// set brightness for ioctl display, a level the driver doesn't list fails with `UnsupportedLevel`
 pub fn ioctl_set_display_brightness(
     device: &MonitorDeviceImpl,
     value: u8
-) -> anyhow::Result<()> {
+) -> Result<(), MonitorError> {
+    ioctl_query_supported_brightness(device)?.validate(value)?;
+    write_display_brightness(device, value)
+}
+
+/// the raw ioctl, without checking `value` against the supported levels
+fn write_display_brightness(
+    device: &MonitorDeviceImpl,
+    value: u8
+) -> Result<(), MonitorError> {
     // bit 0: controls ac brightness
     // bit 1: controls dc brightness
     // bit 2: combines both ac & dc
@@ -207,10 +559,108 @@ pub fn ioctl_set_display_brightness(
             // doing a very tiny sleep seems to mitigate this
             std::thread::sleep(std::time::Duration::from_nanos(1));
         })
-        .map_err(|e| 
-            anyhow!(
-                "failed to set monitor brightness (ioctl), device: {:#?}, err: {:#?}", 
-                device.friendly_name.clone(), e
-            ))
+        .map_err(MonitorError::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// a dell u2720q, trimmed
+    const DELL_CAPS: &str = "(prot(monitor)type(LCD)model(U2720Q)cmds(01 02 03 07 0C E3 F3)\
+        vcp(02 04 05 08 10 12 14(01 04 05 06 08 09 0B 0C) 16 18 1A 52 60(0F 11 1B) AA(01 02 04) \
+        DC(00 02 03 05) DF F0(00 08) FD)mswhql(1)asset_eep(40)mccs_ver(2.1))";
+
+    fn codes(caps: &Capabilities) -> Vec<u8> {
+        caps.vcp.iter().map(|f| f.code).collect()
+    }
+
+    #[test]
+    fn parses_a_real_capabilities_string() {
+        let caps = Capabilities::parse(DELL_CAPS);
+        assert_eq!(caps.display_type.as_deref(), Some("LCD"));
+        assert_eq!(caps.model.as_deref(), Some("U2720Q"));
+        assert_eq!(caps.mccs_version.as_deref(), Some("2.1"));
+        assert_eq!(
+            codes(&caps),
+            [0x02, 0x04, 0x05, 0x08, 0x10, 0x12, 0x14, 0x16, 0x18, 0x1a, 0x52, 0x60, 0xaa, 0xdc, 0xdf, 0xf0, 0xfd],
+        );
+        let inputs = caps.vcp.iter().find(|f| f.code == 0x60).unwrap();
+        assert_eq!(inputs.values, [0x0f, 0x11, 0x1b]);
+        assert!(caps.vcp.iter().find(|f| f.code == 0x10).unwrap().values.is_empty());
+        assert!(caps.supports(0x10));
+        assert!(!caps.supports(0x62));
+    }
+
+    #[test]
+    fn values_nested_deeper_are_skipped() {
+        let caps = Capabilities::parse("vcp(10 DC(00 02(05 06) 03) 12)");
+        assert_eq!(codes(&caps), [0x10, 0xdc, 0x12]);
+        assert_eq!(caps.vcp[1].values, [0x00, 0x02, 0x03]);
+    }
+
+    #[test]
+    fn malformed_tokens_are_left_out() {
+        let caps = Capabilities::parse("(vcp(10 zz 1G0 100 12(xx 05)) vcpname(14(Brightness)))");
+        assert_eq!(codes(&caps), [0x10, 0x12]);
+        assert_eq!(caps.vcp[1].values, [0x05]);
+        // a stray closing paren doesn't go below the top level
+        assert_eq!(codes(&Capabilities::parse("vcp(10) 12 14)")), [0x10]);
+        assert_eq!(parse_vcp("10) 12 14"), parse_vcp("10 12 14"));
+    }
+
+    #[test]
+    fn unbalanced_parens() {
+        // the vcp section never closes, what came before it still parses
+        let caps = Capabilities::parse("(type(lcd)model(X)vcp(10 12(01)");
+        assert_eq!(caps.display_type.as_deref(), Some("lcd"));
+        assert_eq!(caps.model.as_deref(), Some("X"));
+        assert!(caps.vcp.is_empty());
+        assert_eq!(Capabilities::parse("((("), Capabilities::default());
+    }
+
+    #[test]
+    fn missing_vcp_section() {
+        let caps = Capabilities::parse("(prot(monitor)type(lcd)model()mccs_ver(2.2))");
+        assert!(caps.vcp.is_empty());
+        assert!(!caps.supports(0x10));
+        assert_eq!(caps.model, None);
+        assert_eq!(caps.mccs_version.as_deref(), Some("2.2"));
+        assert_eq!(Capabilities::parse(""), Capabilities::default());
+    }
+
+    fn levels() -> IoctlSupportedBrightnessLevels {
+        IoctlSupportedBrightnessLevels(vec![0, 10, 25, 50, 75, 100])
+    }
+
+    #[test]
+    fn validate_accepts_supported_levels() {
+        assert!(levels().validate(0).is_ok());
+        assert!(levels().validate(50).is_ok());
+        assert!(levels().validate(100).is_ok());
+    }
+
+    #[test]
+    fn validate_refuses_levels_outside_the_supported_set() {
+        match levels().validate(40) {
+            Err(MonitorError::UnsupportedLevel { level, supported }) => {
+                assert_eq!(level, 40);
+                assert_eq!(supported, vec![0, 10, 25, 50, 75, 100]);
+            }
+            other => panic!("expected UnsupportedLevel, got {:?}", other),
+        }
+        assert!(IoctlSupportedBrightnessLevels(Vec::new()).validate(0).is_err());
+    }
+
+    #[test]
+    fn nearest_level_is_always_supported() {
+        for percentage in 0..=100 {
+            let nearest = levels().get_nearest(percentage).unwrap();
+            assert!(levels().validate(nearest).is_ok(), "{}% snapped to {}", percentage, nearest);
+        }
+        assert_eq!(levels().get_nearest(40), Some(50));
+        assert_eq!(levels().get_nearest(12), Some(10));
+        assert_eq!(IoctlSupportedBrightnessLevels(Vec::new()).get_nearest(50), None);
     }
 }
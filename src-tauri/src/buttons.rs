@@ -0,0 +1,98 @@
+/*
+ * the monitor's own osd buttons mapped to fade actions, opt-in per pnp model.
+ * mccs has no button events, but monitors supporting it raise `new control value` (vcp 0x02)
+ * when a control changes from the osd, & queue the changed codes in `active control` (vcp 0x52).
+ * so pressing e.g. the input button shows up as a change of 0x60. monitors that don't
+ * implement it are skipped after the first failed read, without a warning
+*/
+use std::collections::HashSet;
+use tracing::{debug, info};
+use tokio::time::{sleep, Duration};
+use crate::{
+    app::AppState,
+    events, monitors, tray,
+    settings::ButtonAction,
+};
+
+const POLL: Duration = Duration::from_millis(500);
+const VCP_NEW_CONTROL_VALUE: u8 = 0x02;
+const VCP_ACTIVE_CONTROL: u8 = 0x52;
+/// `new control value`: changes are pending
+const NEW_CONTROL_PENDING: u32 = 0x02;
+/// `new control value`: written back once the queue is read
+const NEW_CONTROL_NONE: u32 = 0x01;
+/// upper bound on the `active control` queue, a monitor never ending it can't hang the poll
+const MAX_QUEUED: usize = 16;
+
+/// polls the monitors whose model has mappings in `monitor_buttons`
+pub async fn button_changes(state: AppState) {
+    // monitor `id`s which failed to answer, a monitor doesn't gain the feature later
+    let mut unsupported: HashSet<String> = HashSet::new();
+
+    loop {
+        sleep(POLL).await;
+
+        let config = state.settings.lock().await.monitor_buttons.clone();
+        if config.is_empty() {
+            continue;
+        }
+
+        let mut actions = Vec::new();
+        {
+            let devices = state.monitor_device.lock().await;
+            for dev in devices.iter().filter(|d| !d.is_internal()) {
+                if unsupported.contains(&dev.id) {
+                    continue;
+                }
+                let Some(mappings) = monitors::pnp_model(&dev.id).and_then(|m| config.get(m)) else {
+                    continue;
+                };
+
+                let pending = match dev.get_vcp(VCP_NEW_CONTROL_VALUE) {
+                    Ok((value, _)) => value == NEW_CONTROL_PENDING,
+                    Err(e) => {
+                        debug!("'{}' doesn't report control changes, not polling it: {:?}", dev.friendly_name, e);
+                        unsupported.insert(dev.id.clone());
+                        continue;
+                    }
+                };
+                if !pending {
+                    continue;
+                }
+
+                for _ in 0..MAX_QUEUED {
+                    let code = match dev.get_vcp(VCP_ACTIVE_CONTROL) {
+                        Ok((0, _)) => break,
+                        Ok((code, _)) => code,
+                        Err(e) => {
+                            debug!("failed to read active control of '{}': {:?}", dev.friendly_name, e);
+                            break;
+                        }
+                    };
+                    debug!("'{}' reported a change of vcp {:#04x}", dev.friendly_name, code);
+                    actions.extend(mappings.iter().filter(|m| m.vcp as u32 == code).map(|m| m.action));
+                }
+                if let Err(e) = dev.set_vcp(VCP_NEW_CONTROL_VALUE, NEW_CONTROL_NONE) {
+                    debug!("failed to reset new control value of '{}': {:?}", dev.friendly_name, e);
+                }
+            }
+        }
+
+        // the actions lock the devices themselves
+        for action in actions {
+            info!("monitor button: {:?}", action);
+            run(&state, action).await;
+        }
+    }
+}
+
+async fn run(state: &AppState, action: ButtonAction) {
+    match action {
+        ButtonAction::ToggleDim => tray::toggle_dim(state).await,
+        ButtonAction::CycleBrightness => tray::cycle_brightness(state).await,
+        ButtonAction::ToggleReadingMode => {
+            let on = !state.settings.lock().await.reading_mode_enabled;
+            events::set_reading_mode(state, on).await;
+        }
+    }
+}
@@ -0,0 +1,241 @@
+/*
+ * scripting without the gui: `fade.exe --set 50 --monitor "Dell U2720Q"`, `--get` & `--list`.
+ * a thin client of the running instance's http api on the websocket port, one is started
+ * in the background when none answers. `--monitor` takes a device name, id, alias, label or name
+*/
+use anyhow::{anyhow, bail, Context};
+use std::{
+    env, thread,
+    io::{self, Read, Write},
+    net::{SocketAddr, TcpStream},
+    process::Command,
+    time::{Duration, Instant},
+};
+use windows::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+use crate::{events, monitors::MonitorInfo};
+
+const SET_ARG: &str = "--set";
+const GET_ARG: &str = "--get";
+const LIST_ARG: &str = "--list";
+const MONITOR_ARG: &str = "--monitor";
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+const IO_TIMEOUT: Duration = Duration::from_secs(10);
+/// how long a freshly started instance gets to bind the port
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(15);
+const STARTUP_POLL: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    List,
+    Get,
+    Set(u32),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Invocation {
+    pub action: Action,
+    /// every monitor when `None`
+    pub monitor: Option<String>,
+}
+
+/// `None` without any cli action, the tray app is launched then
+pub fn parse() -> Option<anyhow::Result<Invocation>> {
+    let mut action = None;
+    let mut monitor = None;
+    let mut invalid = None;
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let next = match arg.as_str() {
+            LIST_ARG => Some(Action::List),
+            GET_ARG => Some(Action::Get),
+            SET_ARG => match args.next().map(|v| v.trim().trim_end_matches('%').parse::<u32>()) {
+                Some(Ok(value)) if value <= 100 => Some(Action::Set(value)),
+                Some(_) => {
+                    invalid.get_or_insert_with(|| anyhow!("`{}` expects a brightness within 0..=100", SET_ARG));
+                    Some(Action::Set(0))
+                }
+                None => {
+                    invalid.get_or_insert_with(|| anyhow!("`{}` expects a brightness", SET_ARG));
+                    Some(Action::Set(0))
+                }
+            },
+            MONITOR_ARG => {
+                match args.next() {
+                    Some(name) => monitor = Some(name),
+                    None => { invalid.get_or_insert_with(|| anyhow!("`{}` expects a monitor", MONITOR_ARG)); }
+                }
+                None
+            }
+            // app arguments like `--mock-monitors` are left to the app
+            _ => None,
+        };
+        if let Some(next) = next {
+            if action.replace(next).is_some() {
+                invalid.get_or_insert_with(|| anyhow!("only one of `{}`, `{}` & `{}` at a time", LIST_ARG, GET_ARG, SET_ARG));
+            }
+        }
+    }
+
+    // `--monitor` on its own still means the cli was meant
+    if action.is_none() && monitor.is_none() && invalid.is_none() {
+        return None;
+    }
+    Some(match (invalid, action) {
+        (Some(e), _) => Err(e),
+        (None, None) => Err(anyhow!("`{}` needs `{}` or `{}`", MONITOR_ARG, GET_ARG, SET_ARG)),
+        (None, Some(action)) => Ok(Invocation { action, monitor }),
+    })
+}
+
+/// runs `invocation` against the running instance, returns the process exit code
+pub fn run(invocation: anyhow::Result<Invocation>) -> i32 {
+    // release builds are a gui app without a console, print into the one we were started from
+    unsafe {
+        let _ = AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+    match invocation.and_then(|invocation| execute(&invocation)) {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("fade: {:#}", e);
+            1
+        }
+    }
+}
+
+fn execute(invocation: &Invocation) -> anyhow::Result<()> {
+    let addr = events::ws_addr(None)?;
+    let monitors = fetch_monitors(addr)?;
+    let targets = select(&monitors, invocation.monitor.as_deref())?;
+
+    match invocation.action {
+        Action::List => {
+            for info in targets {
+                let alias = info.alias.as_deref().map(|a| format!(" [{}]", a)).unwrap_or_default();
+                println!("{}\t{}{}\t{}%", info.device_name, display_name(info), alias, info.brightness);
+            }
+        }
+        Action::Get => {
+            if let [info] = targets.as_slice() {
+                println!("{}", info.brightness);
+            } else {
+                for info in targets {
+                    println!("{}\t{}", display_name(info), info.brightness);
+                }
+            }
+        }
+        Action::Set(value) => {
+            let mut failed = 0;
+            // clones share a device name, one write reaches all of them
+            let mut written: Vec<&str> = Vec::new();
+            for info in targets {
+                if written.contains(&info.device_name.as_str()) {
+                    continue;
+                }
+                written.push(&info.device_name);
+                let body = serde_json::json!({
+                    "command": "set_brightness",
+                    "device_name": info.device_name,
+                    "brightness": value,
+                });
+                if let Err(e) = request(addr, "POST", "/brightness", Some(&body.to_string())) {
+                    eprintln!("fade: {}: {:#}", display_name(info), e);
+                    failed += 1;
+                }
+            }
+            if failed > 0 {
+                bail!("{} monitors failed", failed);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn display_name(info: &MonitorInfo) -> &str {
+    info.label.as_deref().unwrap_or(&info.name)
+}
+
+/// exact selectors first, then the (case insensitive) label or name
+fn select<'a>(monitors: &'a [MonitorInfo], selector: Option<&str>) -> anyhow::Result<Vec<&'a MonitorInfo>> {
+    let Some(selector) = selector.map(str::trim) else {
+        return Ok(monitors.iter().collect());
+    };
+    let exact: Vec<_> = monitors.iter()
+        .filter(|m| m.device_name == selector || m.id == selector || m.alias.as_deref() == Some(selector))
+        .collect();
+    if !exact.is_empty() {
+        return Ok(exact);
+    }
+    let by_name: Vec<_> = monitors.iter()
+        .filter(|m| {
+            m.label.as_deref().is_some_and(|l| l.eq_ignore_ascii_case(selector))
+                || m.name.eq_ignore_ascii_case(selector)
+        })
+        .collect();
+    if by_name.is_empty() {
+        let known: Vec<&str> = monitors.iter().map(display_name).collect();
+        bail!("no monitor matches '{}', connected: {:?}", selector, known);
+    }
+    Ok(by_name)
+}
+
+/// `GET /monitors`, starting an instance first when nothing listens on `addr`
+fn fetch_monitors(addr: SocketAddr) -> anyhow::Result<Vec<MonitorInfo>> {
+    let body = match request(addr, "GET", "/monitors", None) {
+        Ok(body) => body,
+        Err(e) if e.downcast_ref::<io::Error>().is_some_and(|e| e.kind() == io::ErrorKind::ConnectionRefused) => {
+            start_instance()?;
+            let deadline = Instant::now() + STARTUP_TIMEOUT;
+            loop {
+                thread::sleep(STARTUP_POLL);
+                let expired = Instant::now() >= deadline;
+                // monitors are enumerated after the server is up, an empty list may just be early
+                match request(addr, "GET", "/monitors", None) {
+                    Ok(body) if expired || body.trim() != "[]" => break body,
+                    Err(e) if expired => return Err(e.context(format!("fade didn't come up on {}", addr))),
+                    _ => continue,
+                }
+            }
+        }
+        Err(e) => return Err(e),
+    };
+    serde_json::from_str(&body).context("unexpected `/monitors` reply")
+}
+
+/// the tray app, without arguments so it doesn't end up here again
+fn start_instance() -> anyhow::Result<()> {
+    let exe = env::current_exe()?;
+    Command::new(exe).spawn().context("failed to start fade")?;
+    Ok(())
+}
+
+/// minimal http/1.1 over a fresh connection, the body of a 2xx reply.
+/// a refused connection stays an `io::Error` so the caller can tell nothing is listening
+pub fn request(addr: SocketAddr, method: &str, path: &str, body: Option<&str>) -> anyhow::Result<String> {
+    let mut stream = TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT)?;
+    stream.set_read_timeout(Some(IO_TIMEOUT))?;
+    stream.set_write_timeout(Some(IO_TIMEOUT))?;
+
+    let body = body.unwrap_or_default();
+    write!(
+        stream,
+        "{} {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        method, path, addr, body.len(), body
+    ).context("failed to send the request")?;
+
+    let mut reply = String::new();
+    stream.read_to_string(&mut reply).context("failed to read the reply")?;
+    let (head, body) = reply.split_once("\r\n\r\n").ok_or_else(|| anyhow!("malformed http reply"))?;
+    let status: u16 = head.split_whitespace().nth(1)
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(|| anyhow!("malformed http status line"))?;
+    if !(200..300).contains(&status) {
+        // `/brightness` replies with a `CommandReply` carrying the reason
+        let message = serde_json::from_str::<serde_json::Value>(body).ok()
+            .and_then(|v| v.pointer("/error/message").and_then(|m| m.as_str()).map(str::to_string))
+            .unwrap_or_else(|| body.trim().to_string());
+        bail!("{} {} failed with {}: {}", method, path, status, message);
+    }
+    Ok(body.to_string())
+}
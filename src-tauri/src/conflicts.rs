@@ -0,0 +1,141 @@
+/*
+ * detection of software known to fight fade over gamma/brightness,
+ * running processes are matched by executable name & night light is read from the registry
+*/
+use anyhow::anyhow;
+use std::collections::HashSet;
+use serde::{
+    Serialize,
+    Deserialize
+};
+use windows::{
+    core::w,
+    Win32::{
+        Foundation::ERROR_SUCCESS,
+        System::{
+            Diagnostics::ToolHelp::{
+                CreateToolhelp32Snapshot, Process32FirstW, Process32NextW, PROCESSENTRY32W,
+                TH32CS_SNAPPROCESS,
+            },
+            Registry::{RegGetValueW, HKEY_CURRENT_USER, RRF_RT_REG_BINARY},
+        },
+    }
+};
+use crate::monitors::SafeDisplayHandle;
+
+/// a program which is likely to overwrite fade's changes
+#[derive(Debug, Serialize, Deserialize, Clone, Eq, PartialEq)]
+pub struct ConflictRule {
+    /// executable name, compared case insensitively, e.g. `flux.exe`
+    pub executable: String,
+    pub name: String,
+    pub guidance: String,
+}
+
+#[derive(Debug, Serialize, Clone, Eq, PartialEq)]
+pub struct Conflict {
+    pub name: String,
+    /// matched executable, `None` for os features like night light
+    pub executable: Option<String>,
+    pub guidance: String,
+}
+
+/// `(executable, name, guidance)`, extended by `Settings::conflict_rules`
+const KNOWN_CONFLICTS: &[(&str, &str, &str)] = &[
+    ("flux.exe", "f.lux", "f.lux rewrites the gamma ramp periodically, disable it or use the overlay dim backend"),
+    ("iris.exe", "Iris", "Iris controls gamma & brightness, close it while using fade"),
+    ("lightbulb.exe", "LightBulb", "LightBulb rewrites the gamma ramp, disable it or use the overlay dim backend"),
+    ("monitorian.exe", "Monitorian", "Monitorian also sets ddc/ci brightness, only keep one of them running"),
+    ("twinkletray.exe", "Twinkle Tray", "Twinkle Tray also sets ddc/ci brightness, only keep one of them running"),
+    ("clickmonitorddc.exe", "ClickMonitorDDC", "ClickMonitorDDC also sets ddc/ci brightness, only keep one of them running"),
+    ("radeonsoftware.exe", "AMD Software", "disable custom color/brightness in the display tab of AMD Software"),
+    ("nvcplui.exe", "NVIDIA Control Panel", "set \"use NVIDIA settings\" back to \"other applications control color settings\""),
+    ("igfxem.exe", "Intel Graphics", "reset color/brightness in the Intel Graphics Command Center"),
+];
+
+/// cloud store entry written by the settings app when night light is toggled
+const NIGHT_LIGHT_KEY: windows::core::PCWSTR = w!(
+    "Software\\Microsoft\\Windows\\CurrentVersion\\CloudStore\\Store\\DefaultAccount\\Current\\default$windows.data.bluelightreduction.bluelightreductionstate\\windows.data.bluelightreduction.bluelightreductionstate"
+);
+/// byte holding the on/off flag in the undocumented blob, `0x15` when on
+const NIGHT_LIGHT_FLAG_OFFSET: usize = 18;
+const NIGHT_LIGHT_ON: u8 = 0x15;
+
+/// lowercased executable names of all running processes
+fn running_executables() -> anyhow::Result<HashSet<String>> {
+    unsafe {
+        let snapshot = SafeDisplayHandle(
+            CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0)
+                .map_err(|e| anyhow!("failed to snapshot processes, err: {:#?}", e))?
+        );
+
+        let mut entry = PROCESSENTRY32W {
+            dwSize: size_of::<PROCESSENTRY32W>() as u32,
+            ..Default::default()
+        };
+        let mut executables = HashSet::new();
+        let mut next = Process32FirstW(snapshot.0, &mut entry);
+        while next.is_ok() {
+            let len = entry.szExeFile.iter().position(|&c| c == 0).unwrap_or(entry.szExeFile.len());
+            executables.insert(String::from_utf16_lossy(&entry.szExeFile[..len]).to_lowercase());
+            next = Process32NextW(snapshot.0, &mut entry);
+        }
+        Ok(executables)
+    }
+}
+
+/// `false` when the key is missing, night light was never used
+pub fn night_light_enabled() -> bool {
+    let mut data = [0u8; 256];
+    let mut size = data.len() as u32;
+    let status = unsafe {
+        RegGetValueW(
+            HKEY_CURRENT_USER,
+            NIGHT_LIGHT_KEY,
+            w!("Data"),
+            RRF_RT_REG_BINARY,
+            None,
+            Some(data.as_mut_ptr() as *mut _),
+            Some(&mut size as *mut u32),
+        )
+    };
+    status == ERROR_SUCCESS
+        && size as usize > NIGHT_LIGHT_FLAG_OFFSET
+        && data[NIGHT_LIGHT_FLAG_OFFSET] == NIGHT_LIGHT_ON
+}
+
+/// built-in rules plus `extra`, an extra rule overrides a built-in one for the same executable
+pub fn detect(extra: &[ConflictRule]) -> anyhow::Result<Vec<Conflict>> {
+    let running = running_executables()?;
+
+    let mut rules: Vec<ConflictRule> = KNOWN_CONFLICTS
+        .iter()
+        .filter(|(exe, ..)| !extra.iter().any(|r| r.executable.eq_ignore_ascii_case(exe)))
+        .map(|&(executable, name, guidance)| ConflictRule {
+            executable: executable.to_string(),
+            name: name.to_string(),
+            guidance: guidance.to_string(),
+        })
+        .collect();
+    rules.extend(extra.iter().cloned());
+
+    let mut conflicts: Vec<Conflict> = rules
+        .into_iter()
+        .filter(|rule| running.contains(&rule.executable.to_lowercase()))
+        .map(|rule| Conflict {
+            name: rule.name,
+            executable: Some(rule.executable),
+            guidance: rule.guidance,
+        })
+        .collect();
+
+    if night_light_enabled() {
+        conflicts.push(Conflict {
+            name: "Windows Night Light".to_string(),
+            executable: None,
+            guidance: "night light tints the gamma ramp, turn it off in Settings > System > Display".to_string(),
+        });
+    }
+
+    Ok(conflicts)
+}
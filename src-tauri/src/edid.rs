@@ -0,0 +1,256 @@
+/*
+ * edid access, read from the registry copy windows keeps for every display
+ * `HKLM\SYSTEM\CurrentControlSet\Enum\DISPLAY\<model>\<instance>\Device Parameters\EDID`
+*/
+use anyhow::anyhow;
+use std::{iter, ffi::OsStr, os::windows::ffi::OsStrExt};
+use windows::{
+    core::{w, PCWSTR},
+    Win32::{
+        Foundation::ERROR_SUCCESS,
+        System::Registry::{RegGetValueW, HKEY_LOCAL_MACHINE, RRF_RT_REG_BINARY},
+    }
+};
+
+const EDID_BLOCK_LEN: usize = 128;
+/// cta-861 extension block tag
+const CTA_EXTENSION_TAG: u8 = 0x02;
+/// "use extended tag" data block type
+const CTA_EXTENDED_BLOCK: u8 = 7;
+/// hdr static metadata extended tag
+const CTA_HDR_STATIC_METADATA: u8 = 0x06;
+/// display descriptor tag of the serial number string
+const DESCRIPTOR_SERIAL: u8 = 0xff;
+
+/// registry key of a display from its `monitorDevicePath`,
+/// e.g. `\\?\DISPLAY#GSM5B7F#5&2c0c9ed3&0&UID4352#{e6f07b5f-...}`
+fn registry_key(device_path: &str) -> Option<String> {
+    let mut parts = device_path.trim_start_matches(r"\\?\").split('#');
+    let (class, model, instance) = (parts.next()?, parts.next()?, parts.next()?);
+    Some(format!(
+        r"SYSTEM\CurrentControlSet\Enum\{}\{}\{}\Device Parameters",
+        class, model, instance
+    ))
+}
+
+/// raw edid of a display, base block followed by its extensions
+pub fn read_edid(device_path: &str) -> anyhow::Result<Vec<u8>> {
+    let key = registry_key(device_path)
+        .ok_or_else(|| anyhow!("unexpected monitor device path: {}", device_path))?;
+    let wide: Vec<u16> = OsStr::new(&key).encode_wide().chain(iter::once(0)).collect();
+
+    unsafe {
+        let mut size = 0u32;
+        let status = RegGetValueW(
+            HKEY_LOCAL_MACHINE, PCWSTR(wide.as_ptr()), w!("EDID"), RRF_RT_REG_BINARY,
+            None, None, Some(&mut size as *mut u32),
+        );
+        if status != ERROR_SUCCESS {
+            return Err(anyhow!("failed to read edid size, key: {}, err: {:?}", key, status));
+        }
+
+        let mut edid = vec![0u8; size as usize];
+        let status = RegGetValueW(
+            HKEY_LOCAL_MACHINE, PCWSTR(wide.as_ptr()), w!("EDID"), RRF_RT_REG_BINARY,
+            None, Some(edid.as_mut_ptr() as *mut _), Some(&mut size as *mut u32),
+        );
+        if status != ERROR_SUCCESS {
+            return Err(anyhow!("failed to read edid, key: {}, err: {:?}", key, status));
+        }
+        edid.truncate(size as usize);
+        Ok(edid)
+    }
+}
+
+/// desired content max luminance (nits) from the hdr static metadata block,
+/// `None` when the display doesn't advertise one, common for sdr monitors
+pub fn max_luminance(edid: &[u8]) -> Option<f64> {
+    edid.chunks_exact(EDID_BLOCK_LEN)
+        .skip(1)
+        .filter(|block| block[0] == CTA_EXTENSION_TAG)
+        .find_map(hdr_max_luminance)
+}
+
+fn hdr_max_luminance(block: &[u8]) -> Option<f64> {
+    // data blocks sit between byte 4 & the detailed timing offset
+    let end = (block[2] as usize).clamp(4, EDID_BLOCK_LEN);
+    let mut i = 4;
+    while i < end {
+        let header = block[i];
+        let len = (header & 0x1f) as usize;
+        let data = block.get(i + 1..i + 1 + len)?;
+        if header >> 5 == CTA_EXTENDED_BLOCK && data.first() == Some(&CTA_HDR_STATIC_METADATA) {
+            // [tag, eotf, descriptors, max luminance, max frame avg, min luminance]
+            let code = *data.get(3)?;
+            return (code > 0).then(|| 50.0 * 2f64.powf(code as f64 / 32.0));
+        }
+        i += 1 + len;
+    }
+    None
+}
+
+/// image size in mm from the preferred detailed timing, falling back to the base block's
+/// size in cm. `None` for projectors & edids which leave it blank
+pub fn physical_size_mm(edid: &[u8]) -> Option<[u32; 2]> {
+    let base = edid.get(..EDID_BLOCK_LEN)?;
+    let dtd = &base[54..72];
+    // a zero pixel clock marks a display descriptor, not a timing
+    if dtd[0] != 0 || dtd[1] != 0 {
+        let width = dtd[12] as u32 | ((dtd[14] as u32 & 0xf0) << 4);
+        let height = dtd[13] as u32 | ((dtd[14] as u32 & 0x0f) << 8);
+        if width > 0 && height > 0 {
+            return Some([width, height]);
+        }
+    }
+    let (width, height) = (base[21] as u32, base[22] as u32);
+    (width > 0 && height > 0).then_some([width * 10, height * 10])
+}
+
+/// pnp manufacturer id from the base block, `DEL`, `GSM`, ...
+pub fn manufacturer(edid: &[u8]) -> Option<String> {
+    let id = u16::from_be_bytes([*edid.get(8)?, *edid.get(9)?]);
+    // three 5 bit letters, 1 is `A`
+    let letters: Option<String> = [10, 5, 0]
+        .iter()
+        .map(|shift| match (id >> shift) & 0x1f {
+            letter @ 1..=26 => Some((b'A' + letter as u8 - 1) as char),
+            _ => None,
+        })
+        .collect();
+    letters
+}
+
+/// serial number, the text of the serial descriptor when there is one, otherwise the
+/// numeric one from the base block. `None` when the edid leaves both blank
+pub fn serial(edid: &[u8]) -> Option<String> {
+    let base = edid.get(..EDID_BLOCK_LEN)?;
+    // the 4 18 byte descriptors, a zero pixel clock marks a display descriptor
+    for descriptor in base[54..126].chunks_exact(18) {
+        if descriptor[..3] == [0, 0, 0] && descriptor[3] == DESCRIPTOR_SERIAL {
+            let text: String = descriptor[5..]
+                .iter()
+                .take_while(|&&b| b != b'\n')
+                .map(|&b| b as char)
+                .collect();
+            let text = text.trim();
+            if !text.is_empty() {
+                return Some(text.to_string());
+            }
+        }
+    }
+    let numeric = u32::from_le_bytes([base[12], base[13], base[14], base[15]]);
+    (numeric != 0).then(|| numeric.to_string())
+}
+
+/// brightness percentage for `nits`, assumes luminance scales linearly with the backlight
+pub fn nits_to_percentage(nits: f64, max_nits: f64) -> u32 {
+    (nits / max_nits * 100.0).round().clamp(0.0, 100.0) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// base block followed by a cta-861 extension holding `data_blocks`
+    fn edid_with_cta(data_blocks: &[u8]) -> Vec<u8> {
+        let mut edid = vec![0u8; EDID_BLOCK_LEN * 2];
+        let cta = &mut edid[EDID_BLOCK_LEN..];
+        cta[0] = CTA_EXTENSION_TAG;
+        cta[1] = 3;
+        cta[2] = (4 + data_blocks.len()) as u8;
+        cta[4..4 + data_blocks.len()].copy_from_slice(data_blocks);
+        edid
+    }
+
+    /// extended tag header & `[tag, eotf, descriptors, max luminance, max frame avg, min luminance]`
+    fn hdr_block(max_luminance: u8) -> [u8; 7] {
+        [CTA_EXTENDED_BLOCK << 5 | 6, CTA_HDR_STATIC_METADATA, 0x0d, 0x01, max_luminance, 0x50, 0x00]
+    }
+
+    #[test]
+    fn max_luminance_from_hdr_metadata() {
+        // a 2 byte video data block first, the hdr block has to be found past it
+        let mut blocks = vec![2 << 5 | 2, 0x10, 0x04];
+        blocks.extend(hdr_block(96));
+        let nits = max_luminance(&edid_with_cta(&blocks)).unwrap();
+        assert!((nits - 400.0).abs() < 1e-9);
+
+        let nits = max_luminance(&edid_with_cta(&hdr_block(128))).unwrap();
+        assert!((nits - 800.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn max_luminance_missing() {
+        // base block only
+        assert_eq!(max_luminance(&[0u8; EDID_BLOCK_LEN]), None);
+        // extension without an hdr block
+        assert_eq!(max_luminance(&edid_with_cta(&[2 << 5 | 2, 0x10, 0x04])), None);
+        // hdr block leaving max luminance unset
+        assert_eq!(max_luminance(&edid_with_cta(&hdr_block(0))), None);
+        // not a cta extension
+        let mut edid = edid_with_cta(&hdr_block(96));
+        edid[EDID_BLOCK_LEN] = 0x70;
+        assert_eq!(max_luminance(&edid), None);
+    }
+
+    /// base block with the serial descriptor text in the second descriptor slot
+    fn edid_with_serial_text(text: &[u8]) -> Vec<u8> {
+        let mut edid = vec![0u8; EDID_BLOCK_LEN];
+        let descriptor = &mut edid[72..90];
+        descriptor[3] = DESCRIPTOR_SERIAL;
+        descriptor[5..5 + text.len()].copy_from_slice(text);
+        edid
+    }
+
+    #[test]
+    fn manufacturer_letters() {
+        let mut edid = vec![0u8; EDID_BLOCK_LEN];
+        edid[8..10].copy_from_slice(&[0x10, 0xac]);
+        assert_eq!(manufacturer(&edid).as_deref(), Some("DEL"));
+        edid[8..10].copy_from_slice(&[0x1e, 0x6d]);
+        assert_eq!(manufacturer(&edid).as_deref(), Some("GSM"));
+        // 0 & 27..=31 aren't letters
+        edid[8..10].copy_from_slice(&[0x00, 0x00]);
+        assert_eq!(manufacturer(&edid), None);
+        edid[8..10].copy_from_slice(&[0x10, 0xbf]);
+        assert_eq!(manufacturer(&edid), None);
+        assert_eq!(manufacturer(&[0u8; 9]), None);
+    }
+
+    #[test]
+    fn serial_from_descriptor_text() {
+        assert_eq!(serial(&edid_with_serial_text(b"7MT0186K1T0S\n")).as_deref(), Some("7MT0186K1T0S"));
+        // padded without a newline, filling the whole descriptor
+        assert_eq!(serial(&edid_with_serial_text(b"  CN0ABC123  ")).as_deref(), Some("CN0ABC123"));
+    }
+
+    #[test]
+    fn serial_falls_back_to_the_numeric_one() {
+        let mut edid = edid_with_serial_text(b"  \n");
+        edid[12..16].copy_from_slice(&0x0102_0304u32.to_le_bytes());
+        assert_eq!(serial(&edid).as_deref(), Some("16909060"));
+
+        // a detailed timing isn't a descriptor, even with the serial tag where the descriptor's would be
+        let mut edid = edid_with_serial_text(b"NOTASERIAL");
+        edid[72] = 0x01;
+        edid[12..16].copy_from_slice(&42u32.to_le_bytes());
+        assert_eq!(serial(&edid).as_deref(), Some("42"));
+    }
+
+    #[test]
+    fn serial_missing() {
+        assert_eq!(serial(&[0u8; EDID_BLOCK_LEN]), None);
+        assert_eq!(serial(&edid_with_serial_text(b"\n")), None);
+        assert_eq!(serial(&[0u8; EDID_BLOCK_LEN - 1]), None);
+    }
+
+    #[test]
+    fn nits_to_percentage_is_clamped() {
+        assert_eq!(nits_to_percentage(200.0, 400.0), 50);
+        assert_eq!(nits_to_percentage(0.0, 400.0), 0);
+        assert_eq!(nits_to_percentage(401.0, 400.0), 100);
+        assert_eq!(nits_to_percentage(-10.0, 400.0), 0);
+        assert_eq!(nits_to_percentage(1.0, 300.0), 0);
+        assert_eq!(nits_to_percentage(2.0, 300.0), 1);
+    }
+}
@@ -0,0 +1,130 @@
+/*
+ * typed failures of the monitor & brightness apis, so a monitor without ddc/ci can be told
+ * apart from a flaky one without matching on messages. `anyhow` wraps it at the app boundary
+*/
+use std::fmt;
+use windows::{
+    core::HRESULT,
+    Win32::Foundation::{
+        WIN32_ERROR, ERROR_ACCESS_DENIED, ERROR_INVALID_HANDLE,
+        ERROR_GRAPHICS_I2C_NOT_SUPPORTED, ERROR_GRAPHICS_I2C_DEVICE_DOES_NOT_EXIST,
+        ERROR_GRAPHICS_DDCCI_VCP_NOT_SUPPORTED,
+    },
+};
+
+use crate::utils::format_win_err;
+
+/// what ddc/ci calls fail with when the monitor won't ever answer, turned off in its osd or unsupported
+const DDCCI_UNSUPPORTED: [HRESULT; 3] = [
+    ERROR_GRAPHICS_I2C_NOT_SUPPORTED,
+    ERROR_GRAPHICS_I2C_DEVICE_DOES_NOT_EXIST,
+    ERROR_GRAPHICS_DDCCI_VCP_NOT_SUPPORTED,
+];
+
+#[derive(Debug)]
+pub enum MonitorError {
+    /// the os refused the monitor, e.g. an rdp session
+    AccessDenied,
+    /// the monitor doesn't answer ddc/ci at all
+    NotDdcCapable,
+    /// no physical monitor or display handle to talk to
+    HandleInvalid,
+    /// monitors were (dis)connected while they were being listed
+    LengthMismatch,
+    /// the display driver doesn't offer this brightness level (ioctl)
+    UnsupportedLevel { level: u8, supported: Vec<u8> },
+    /// the driver answered, but not with a brightness
+    UnexpectedResponse,
+    /// the write was accepted but the monitor stayed elsewhere, raw ddc/ci values
+    BrightnessLocked { requested: u32, actual: u32 },
+    Win32(WIN32_ERROR),
+    /// any other windows failure, ddc/ci transmission errors end up here
+    Os(windows::core::Error),
+}
+
+impl MonitorError {
+    /// worth another try, the same call may succeed in a moment
+    pub fn is_transient(&self) -> bool {
+        matches!(self, Self::Win32(_) | Self::Os(_) | Self::LengthMismatch)
+    }
+}
+
+impl fmt::Display for MonitorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::AccessDenied => write!(f, "access to the monitor was denied"),
+            Self::NotDdcCapable => write!(f, "the monitor doesn't support ddc/ci, or it's turned off in its menu"),
+            Self::HandleInvalid => write!(f, "no valid handle for the monitor"),
+            Self::LengthMismatch => write!(
+                f,
+                "physical monitors & display devices didn't match, monitors were probably (dis)connected meanwhile"
+            ),
+            Self::UnsupportedLevel { level, supported } => write!(
+                f,
+                "brightness level {} is not supported by the display (ioctl), supported levels: {:?}",
+                level, supported
+            ),
+            Self::UnexpectedResponse => write!(f, "unexpected response from the display driver"),
+            Self::BrightnessLocked { requested, actual } => write!(
+                f,
+                "the monitor kept its brightness at {} instead of {}, a picture mode or eco setting probably locks it",
+                actual, requested
+            ),
+            Self::Win32(err) => write!(f, "{}", format_win_err(*err)),
+            Self::Os(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for MonitorError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Os(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<WIN32_ERROR> for MonitorError {
+    fn from(err: WIN32_ERROR) -> Self {
+        match err {
+            ERROR_ACCESS_DENIED => Self::AccessDenied,
+            ERROR_INVALID_HANDLE => Self::HandleInvalid,
+            // `GetLastError` hands the graphics codes back as they are, `to_hresult` keeps them
+            err if DDCCI_UNSUPPORTED.contains(&err.to_hresult()) => Self::NotDdcCapable,
+            err => Self::Win32(err),
+        }
+    }
+}
+
+impl From<windows::core::Error> for MonitorError {
+    fn from(err: windows::core::Error) -> Self {
+        let code = err.code();
+        if code == ERROR_ACCESS_DENIED.to_hresult() {
+            Self::AccessDenied
+        } else if code == ERROR_INVALID_HANDLE.to_hresult() {
+            Self::HandleInvalid
+        } else if DDCCI_UNSUPPORTED.contains(&code) {
+            Self::NotDdcCapable
+        } else {
+            Self::Os(err)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn last_error_codes_are_classified() {
+        assert!(matches!(MonitorError::from(ERROR_ACCESS_DENIED), MonitorError::AccessDenied));
+        assert!(matches!(MonitorError::from(ERROR_INVALID_HANDLE), MonitorError::HandleInvalid));
+        for code in DDCCI_UNSUPPORTED {
+            let err = MonitorError::from(WIN32_ERROR(code.0 as u32));
+            assert!(matches!(err, MonitorError::NotDdcCapable), "{:?}", code);
+            assert!(!err.is_transient());
+        }
+        assert!(MonitorError::from(WIN32_ERROR(31)).is_transient());
+    }
+}
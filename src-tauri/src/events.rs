@@ -1,28 +1,42 @@
 use anyhow::anyhow;
 use axum::extract::ws::Utf8Bytes;
-use tracing::{error, debug, info};
+use tracing::{error, debug, info, warn};
 use futures::{StreamExt, SinkExt};
 use tokio::{
-    sync::broadcast,
-    net::TcpListener,
-    task, time::{sleep, Duration}
+    sync::{broadcast, watch, Notify, mpsc::Sender},
+    net::{TcpListener, TcpSocket},
+    task, time::{sleep, timeout, Duration}
 };
-use tauri::{Emitter, AppHandle, State};
-use crate::{app, monitors, app::AppState,
-    monitors::MonitorInfo, /* overlay */
+use tauri::{Emitter, AppHandle, Manager, State};
+use crate::{app, autostart, instance, buttons, reset, profiles, monitors, mock, history, schedule, selftest, gamma, conflicts, brightness, edid, reading, tray, app::AppState,
+    share::SharedSettings,
+    settings::{WakeBehavior, MonitorLevels},
+    conflicts::Conflict,
+    monitors::{MonitorInfo, MonitorDeviceImpl, ControlCapability, DisplayEntry, DimBackend, DimState, ColorTemp, ColorTempMechanism, slider_to_alpha}, /* overlay */
+    overlay,
+    overlay::{Overlay, OverlayControl},
+    protocol,
+    protocol::{CommandEnvelope, CommandError, CommandErrorKind, CommandReply},
 };
 use std::{
-    thread,
+    env, thread,
+    time::Instant,
+    collections::{HashMap, HashSet},
+    net::{SocketAddr, Ipv4Addr, Ipv6Addr},
     sync::{
-        Mutex,
+        Arc, Mutex, OnceLock, LazyLock,
+        atomic::{AtomicBool, AtomicUsize, AtomicU64, Ordering},
         mpsc::{
             self,
         },
     }
 };
+use serde::{Serialize, Deserialize};
 use axum::{
     Router,
     routing,
+    Json,
+    http::StatusCode,
     response::IntoResponse,
     extract::{
         ws::{Message, WebSocket},
@@ -36,47 +50,329 @@ pub struct MonitorBroadcaster {
 
 }
 
+impl Default for MonitorBroadcaster {
+    fn default() -> Self {
+        let (sender, _rx) = broadcast::channel(16);
+        Self { sender }
+    }
+}
+
+/// pauses watcher broadcasts during bulk operations, so clients see a
+/// single consolidated snapshot instead of every intermediate state
+#[derive(Debug, Default)]
+pub struct BroadcastFreeze {
+    holders: AtomicUsize,
+    /// frozen from the frontend via `freeze_broadcasts` until then, at most `MANUAL_FREEZE_MAX`
+    manual_until: Mutex<Option<Instant>>,
+    released: Notify,
+}
+
+/// a frontend that froze the broadcasts & then reloaded or crashed never resumes them
+const MANUAL_FREEZE_MAX: Duration = Duration::from_secs(30);
+
+/// releases the freeze on drop
+pub struct BroadcastFreezeGuard(Arc<BroadcastFreeze>);
+
+impl Drop for BroadcastFreezeGuard {
+    fn drop(&mut self) {
+        if self.0.holders.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.0.released.notify_one();
+        }
+    }
+}
+
+impl BroadcastFreeze {
+    pub fn hold(self: &Arc<Self>) -> BroadcastFreezeGuard {
+        self.holders.fetch_add(1, Ordering::SeqCst);
+        BroadcastFreezeGuard(Arc::clone(self))
+    }
+
+    pub fn set_manual(&self, frozen: bool) {
+        let was_frozen = self.manual_remaining().is_some();
+        if let Ok(mut until) = self.manual_until.lock() {
+            *until = frozen.then(|| Instant::now() + MANUAL_FREEZE_MAX);
+        }
+        if was_frozen && !frozen {
+            self.released.notify_one();
+        }
+    }
+
+    pub fn is_frozen(&self) -> bool {
+        self.holders.load(Ordering::SeqCst) > 0 || self.manual_remaining().is_some()
+    }
+
+    /// time left of the manual freeze, `None` when there's none or it ran out
+    fn manual_remaining(&self) -> Option<Duration> {
+        let until = (*self.manual_until.lock().ok()?)?;
+        until.checked_duration_since(Instant::now()).filter(|left| !left.is_zero())
+    }
+
+    /// sleeps for `duration` or until the freeze gets released, a manual freeze running out included
+    async fn wait(&self, duration: Duration) {
+        let duration = self.manual_remaining().map_or(duration, |left| duration.min(left));
+        let _ = timeout(duration, self.released.notified()).await;
+    }
+}
+
+async fn schema_handler() -> Json<serde_json::Value> {
+    Json(protocol::schema())
+}
+
+/// `GET /monitors`, what websocket clients get pushed, for clients which only poll
+async fn monitors_handler(state: axum::extract::State<AppState>) -> Json<Vec<MonitorInfo>> {
+    let devices = state.monitor_device.lock().await;
+    Json(monitor_infos(&state, &devices).await)
+}
+
+/// `POST /brightness`, any websocket command in the body, `{ "device_name", "value" }` is a slider.
+/// replies with the same json as the websocket & a status code matching it
+async fn brightness_handler(
+    state: axum::extract::State<AppState>,
+    body: String,
+) -> (StatusCode, Json<CommandReply>) {
+    let result = match CommandEnvelope::parse(&body) {
+        Ok(command) => execute_command(&state, command).await,
+        Err(e) => Err(e),
+    };
+    let status = match &result {
+        Ok(()) => StatusCode::OK,
+        Err(e) => {
+            warn!("http command rejected: {}", e);
+            match e.kind {
+                CommandErrorKind::Malformed | CommandErrorKind::OutOfRange => StatusCode::BAD_REQUEST,
+                CommandErrorKind::NotFound => StatusCode::NOT_FOUND,
+                CommandErrorKind::Locked => StatusCode::LOCKED,
+                CommandErrorKind::Failed => StatusCode::INTERNAL_SERVER_ERROR,
+            }
+        }
+    };
+    (status, Json(CommandReply::from(result)))
+}
+
+/// `POST /show` from a second launch, brings the window of this instance up
+async fn show_handler() -> StatusCode {
+    let Some(window) = app::app_handle().get_webview_window("main") else {
+        return StatusCode::NOT_FOUND;
+    };
+    info!("second instance launched, showing the window");
+    if let Err(e) = window.show().and_then(|_| window.unminimize()).and_then(|_| window.set_focus()) {
+        error!("failed to show the window for a second instance: {}", e);
+        return StatusCode::INTERNAL_SERVER_ERROR;
+    }
+    app::reveal_window_monitor(&window, true);
+    StatusCode::NO_CONTENT
+}
+
 async fn ws_monitors_handler(
     ws: WebSocketUpgrade,
-    broadcaster: axum::extract::State<MonitorBroadcaster>,
+    state: axum::extract::State<AppState>,
 ) -> impl IntoResponse {
     ws.on_upgrade(move |socket| {
         handle_monitor_socket(
             socket,
-            broadcaster.0.clone(),
+            state.0.clone(),
         )
     })
 }
 
-/// 2 sec sleep for brightness updates
-async fn brightness_changes(state: AppState, broadcaster: MonitorBroadcaster) {
+/// `MonitorInfo` of every readable device, with user labels attached
+async fn monitor_infos(state: &AppState, devices: &[MonitorDeviceImpl]) -> Vec<MonitorInfo> {
+    let ttl = Duration::from_millis(state.settings.lock().await.brightness_cache_ms);
+    // ddc/ci reads retry with a backoff, off the runtime workers
+    let infos = tauri::async_runtime::spawn_blocking({
+        let devices = devices.to_vec();
+        move || devices.iter().filter_map(|d| d.info(ttl).ok()).collect::<Vec<_>>()
+    }).await.unwrap_or_else(|e| {
+        error!("reading monitor infos failed: {}", e);
+        Vec::new()
+    });
+
+    let settings = state.settings.lock().await;
+    infos.into_iter()
+        .map(|mut info| {
+            info.label = settings.labels.get(&info.id).cloned();
+            info.alias = settings.aliases.iter()
+                .find(|(_, id)| **id == info.id)
+                .map(|(alias, _)| alias.clone());
+            info.locked = settings.locked.contains(&info.id);
+            info.warmth = settings.color_temps.get(&info.id)
+                .filter(|t| t.mechanism == ColorTempMechanism::Gamma)
+                .map(|t| gamma::kelvin_to_warmth(t.kelvin));
+            info.clone_group_id = devices.iter()
+                .find(|d| d.id == info.id)
+                .filter(|d| !monitors::clone_peers(devices, d).is_empty())
+                .map(|d| monitors::gdi_device_name(&d.device_name).to_string());
+            info
+        })
+        .collect()
+}
+
+/// holds automatic features back right after startup, monitors are still
+/// settling at login & ddc/ci is flaky. manual control works immediately
+#[derive(Debug)]
+pub struct ReadyGate(watch::Sender<bool>);
+
+impl Default for ReadyGate {
+    fn default() -> Self {
+        Self(watch::Sender::new(false))
+    }
+}
+
+impl ReadyGate {
+    pub fn is_ready(&self) -> bool {
+        *self.0.borrow()
+    }
+
+    pub async fn wait_ready(&self) {
+        let _ = self.0.subscribe().wait_for(|ready| *ready).await;
+    }
+
+    fn open(&self) {
+        self.0.send_replace(true);
+    }
+}
+
+/// opens the ready gate once the device list stayed the same for the grace period
+async fn startup_grace(state: AppState) {
+    let grace = Duration::from_secs(state.settings.lock().await.startup_grace_secs);
+    let mut last_ids: Vec<String> = Vec::new();
+    let mut stable_since = Instant::now();
+
+    loop {
+        match monitors::get_monitors() {
+            Ok(devices) => {
+                let ids: Vec<String> = devices.into_iter().map(|d| d.id).collect();
+                if ids != last_ids {
+                    last_ids = ids;
+                    stable_since = Instant::now();
+                }
+            }
+            Err(e) => {
+                debug!("device scan failed during startup grace: {e}");
+                stable_since = Instant::now();
+            }
+        }
+
+        if stable_since.elapsed() >= grace {
+            info!("device list stable for {:?}, enabling automatic features", grace);
+            state.ready_gate.open();
+            return;
+        }
+        sleep(Duration::from_secs(1)).await;
+    }
+}
+
+/// unexpected ddc/ci changes within `CONTENTION_WINDOW` before warning
+const CONTENTION_THRESHOLD: u32 = 3;
+const CONTENTION_WINDOW: Duration = Duration::from_secs(60);
+
+/// per device count of brightness changes fade didn't make,
+/// something else (gpu control panel, monitor software) may be writing ddc/ci
+#[derive(Default)]
+struct ContentionTracker {
+    seen: HashMap<String, (u32, u32, Instant)>, // id -> (last value, unexpected changes, window start)
+}
+
+impl ContentionTracker {
+    fn observe(&mut self, dev: &MonitorDeviceImpl, brightness: u32) {
+        let (last, count, window_start) = self.seen
+            .entry(dev.id.clone())
+            .or_insert((brightness, 0, Instant::now()));
+
+        if *last != brightness {
+            // ddc/ci reads may round off by one
+            let written_by_fade = dev.last_written.get()
+                .is_some_and(|v| v.abs_diff(brightness) <= 1);
+
+            if !written_by_fade {
+                if window_start.elapsed() > CONTENTION_WINDOW {
+                    *count = 0;
+                    *window_start = Instant::now();
+                }
+                *count += 1;
+                if *count >= CONTENTION_THRESHOLD {
+                    warn!(
+                        "brightness of '{}' keeps changing without fade ({} -> {}, fade set {:?}), \
+                        an external ddc/ci controller may be active",
+                        dev.friendly_name, last, brightness, dev.last_written.get()
+                    );
+                    *count = 0;
+                }
+            }
+        }
+        *last = brightness;
+    }
+}
+
+/// watcher intervals below this would keep ddc/ci busy
+const MIN_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// `ms` from the settings, raised to `MIN_POLL_INTERVAL`
+fn poll_interval(name: &str, ms: u64) -> Duration {
+    let interval = Duration::from_millis(ms);
+    if interval < MIN_POLL_INTERVAL {
+        warn!("{} of {}ms is too short, using {:?}", name, ms, MIN_POLL_INTERVAL);
+        return MIN_POLL_INTERVAL;
+    }
+    interval
+}
+
+/// polls brightness every `interval`, `Settings::brightness_poll_ms`
+async fn brightness_changes(state: AppState, broadcaster: MonitorBroadcaster, interval: Duration) {
     let mut last_infos = Vec::new();
+    let mut was_frozen = false;
+    let mut contention = ContentionTracker::default();
 
     loop {
-        let mut current_infos = Vec::new();
+        if state.broadcast_freeze.is_frozen() {
+            was_frozen = true;
+            state.broadcast_freeze.wait(interval).await;
+            continue;
+        }
+
         let devices = state.monitor_device.lock().await;
+        let current_infos = monitor_infos(&state, &devices).await;
 
-        for dev in devices.iter() {
-            if let Ok(info) = dev.info() {
-                current_infos.push(info);
+        // only observe, never act while the startup grace is running
+        if state.ready_gate.is_ready() && state.settings.lock().await.detect_ddcci_contention {
+            for dev in devices.iter().filter(|d| !d.is_internal()) {
+                if let Some(info) = current_infos.iter().find(|i| i.id == dev.id) {
+                    contention.observe(dev, info.brightness);
+                }
             }
         }
         drop(devices);
 
-        if current_infos != last_infos {
+        let (record, to_file, capacity) = {
+            let settings = state.settings.lock().await;
+            (settings.record_history, settings.history_to_file, settings.history_capacity)
+        };
+        if record {
+            let appended = state.history.lock().await.record(&current_infos, capacity);
+            if to_file {
+                if let Err(e) = history::append_to_file(&appended) {
+                    warn!("failed to write brightness history: {:?}", e);
+                }
+            }
+        }
+
+        // one consolidated snapshot after a freeze, even if nothing changed
+        if current_infos != last_infos || was_frozen {
             debug!("brightness changed detected, {:?}", current_infos);
             let _ = broadcaster.sender.send(current_infos.clone());
+            tray::show_brightness(&state, &current_infos).await;
             last_infos = current_infos;
         }
+        was_frozen = false;
 
-        sleep(Duration::from_secs(2)).await;
+        state.broadcast_freeze.wait(interval).await;
     }
 }
 
-/// 10 sec sleep for brightness updates
-async fn device_changes(state: AppState, broadcaster: MonitorBroadcaster) {
+/// rescans the monitors every `interval`, `Settings::device_poll_ms`
+async fn device_changes(state: AppState, broadcaster: MonitorBroadcaster, interval: Duration) {
     loop {
-        sleep(Duration::from_secs(10)).await;
+        sleep(interval).await;
 
         let new_devices = match monitors::get_monitors() {
             Ok(list) => list,
@@ -87,6 +383,12 @@ async fn device_changes(state: AppState, broadcaster: MonitorBroadcaster) {
         };
 
         let mut devices_lock = state.monitor_device.lock().await;
+        // a resolution change keeps the ids, only the dpi moves
+        for dev in devices_lock.iter_mut() {
+            if let Some(fresh) = new_devices.iter().find(|nd| nd.id == dev.id) {
+                dev.dpi = fresh.dpi;
+            }
+        }
 
         // compare device lists by IDs
         let changed = new_devices.len() != devices_lock.len()
@@ -95,90 +397,2345 @@ async fn device_changes(state: AppState, broadcaster: MonitorBroadcaster) {
             );
 
         if changed {
+            let added: Vec<&MonitorDeviceImpl> = new_devices.iter()
+                .filter(|nd| !devices_lock.iter().any(|d| d.id == nd.id))
+                .collect();
+            let removed: Vec<String> = devices_lock.iter()
+                .filter(|d| !new_devices.iter().any(|nd| nd.id == d.id))
+                .map(|d| d.id.clone())
+                .collect();
+            let added_ids: Vec<String> = added.iter().map(|d| d.id.clone()).collect();
+            restore_levels(&state, &added).await;
+            restore_color_temps(&state, &added).await;
+            restore_overlay_colors(&state, &added).await;
+            if state.settings.lock().await.reading_mode_enabled {
+                reading::apply(&state, &added).await;
+            }
+
             *devices_lock = new_devices.clone();
+            check_overlay_devices(&new_devices);
             // map devices → MonitorInfo for frontend broadcast
-            let infos: Vec<_> = new_devices
-                .iter()
-                .filter_map(|d| d.info().ok())
-                .collect();
+            let infos = monitor_infos(&state, &new_devices).await;
 
             debug!("monitor device configuration changed: {:?}", infos);
-            let _ = broadcaster.sender.send(infos);
+            emit_device_changes(&infos, &added_ids, &removed);
+            // the brightness watcher emits the snapshot once the freeze is released
+            if !state.broadcast_freeze.is_frozen() {
+                let _ = broadcaster.sender.send(infos);
+            }
         }
 
         drop(devices_lock);
     }
 }
 
+/// `monitor-added` with the `MonitorInfo` & `monitor-removed` with the `id`, one per monitor,
+/// for toasts. a monitor that showed up without a readable info only gets logged
+fn emit_device_changes(infos: &[MonitorInfo], added: &[String], removed: &[String]) {
+    let app = app::app_handle();
+    for id in added {
+        let Some(info) = infos.iter().find(|info| &info.id == id) else {
+            debug!("monitor '{}' connected, no info to emit yet", id);
+            continue;
+        };
+        info!("monitor connected: {}", info.name);
+        if let Err(e) = app.emit("monitor-added", info) {
+            warn!("failed to emit `monitor-added`: {}", e);
+        }
+    }
+    for id in removed {
+        info!("monitor disconnected: {}", id);
+        if let Err(e) = app.emit("monitor-removed", id) {
+            warn!("failed to emit `monitor-removed`: {}", e);
+        }
+    }
+}
+
+/// ddc/ci often doesn't answer right after the monitor wakes
+const WAKE_SETTLE: Duration = Duration::from_secs(3);
 
-/// Handle each connected websocket client
-async fn handle_monitor_socket(
-    mut socket: WebSocket,
-    broadcaster: MonitorBroadcaster,
-) {
-    let mut rx = broadcaster.sender.subscribe();
+/// reconciles brightness & dim with what fade last applied whenever the displays wake up,
+/// or clears the dim, depending on `Settings::wake_behavior`
+async fn wake_changes(state: AppState) {
+    loop {
+        overlay::WAKE.notified().await;
+        sleep(WAKE_SETTLE).await;
 
-    // send initial monitor list
-    if let Ok(monitors) = monitors::get_monitors() {
-        let infos: Vec<MonitorInfo> = monitors.iter()
-            .filter_map(|d| d.info().ok())
-            .collect();
-        let _ = socket.send(Message::Text(Utf8Bytes::from(
-            serde_json::to_string(&infos).unwrap()))
-        ).await;
+        if overlay::take_resumed() {
+            info!("resumed from sleep, re-enumerating monitors");
+            refresh_handles(&state).await;
+        }
+
+        let behavior = state.settings.lock().await.wake_behavior;
+        let devices = state.monitor_device.lock().await;
+        let overlay_tx = state.overlay_tx.lock().await.clone();
+        let Some(tx) = overlay_tx else {
+            continue;
+        };
+
+        let (mut restored, mut failed) = (Vec::new(), Vec::new());
+        for dev in devices.iter() {
+            let dim = state.dim_state.lock().await.get(&dev.device_name).copied().unwrap_or_default();
+            match behavior {
+                WakeBehavior::Reapply => {
+                    // a locked monitor keeps whatever it woke up with
+                    if ensure_unlocked(&state, dev).await.is_err() {
+                        continue;
+                    }
+                    if let Some(desired) = dev.last_written.get() {
+                        match dev.get_async().await {
+                            // ddc/ci reads may round off by one
+                            Ok(current) if current.abs_diff(desired) <= 1 => {}
+                            _ => {
+                                info!("restoring brightness of '{}' to {} after wake", dev.friendly_name, desired);
+                                match dev.set_async(desired).await {
+                                    Ok(()) => restored.push(dev.friendly_name.as_str()),
+                                    Err(e) => {
+                                        warn!("failed to restore brightness of '{}' after wake: {:?}", dev.friendly_name, e);
+                                        failed.push(dev.friendly_name.as_str());
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    if dim.level > 0 {
+                        if let Err(e) = dev.dim(dim.level, dim.backend, &tx).await {
+                            warn!("failed to restore dim of '{}' after wake: {:?}", dev.friendly_name, e);
+                        }
+                    }
+                }
+                WakeBehavior::Clear => {
+                    if dim.level == 0 || ensure_unlocked(&state, dev).await.is_err() {
+                        continue;
+                    }
+                    info!("clearing dim of '{}' after wake", dev.friendly_name);
+                    if let Err(e) = dev.clear_dim(dim.backend, &tx).await {
+                        warn!("failed to clear dim of '{}' after wake: {:?}", dev.friendly_name, e);
+                    }
+                    if let Some(dim) = state.dim_state.lock().await.get_mut(&dev.device_name) {
+                        dim.level = 0;
+                    }
+                }
+            }
+        }
+
+        if behavior == WakeBehavior::Reapply {
+            info!("after wake, brightness restored on {:?}, failed on {:?}", restored, failed);
+        }
+        broadcast_devices(&state, &devices).await;
     }
+}
 
-    // forward all broadcast updates to this websocket client
-    while let Ok(monitors) = rx.recv().await {
-        let json = serde_json::to_string(&monitors).unwrap();
-        let _ = socket.send(Message::Text(Utf8Bytes::from(json))).await;
+/// swaps the monitors for freshly enumerated ones, ddc/ci handles can go stale across sleep.
+/// matched by `id`, what fade last wrote carries over. the old ones stay on failure
+async fn refresh_handles(state: &AppState) {
+    let mut fresh = match monitors::get_monitors() {
+        Ok(fresh) => fresh,
+        Err(e) => {
+            warn!("failed to re-enumerate monitors after resume, keeping the old handles: {:?}", e);
+            return;
+        }
+    };
+    let mut devices = state.monitor_device.lock().await;
+    for dev in fresh.iter_mut() {
+        if let Some(old) = devices.iter().find(|d| d.id == dev.id) {
+            dev.last_written = Arc::clone(&old.last_written);
+        }
     }
+    debug!("monitor handles refreshed, {} before, {} now", devices.len(), fresh.len());
+    *devices = fresh;
 }
 
+/// how often the schedule is evaluated, often enough for the transitions to look like a ramp
+const SCHEDULE_TICK: Duration = Duration::from_secs(30);
 
-/// A simple websocket for monitors based updates
-pub async fn start_ws_server(state: AppState) -> anyhow::Result<()> {
-    let (tx, _rx) = broadcast::channel(16);
-    let broadcaster = MonitorBroadcaster { sender: tx.clone() };
+/// follows `Settings::schedule` while `apply_schedule` is on. only writes when what the schedule
+/// asks for moved since the last tick, so manual changes stick until the next transition.
+/// the sun schedule also eases the dim & gamma color temperature toward their night values
+async fn schedule_changes(state: AppState) {
+    state.ready_gate.wait_ready().await;
+    // brightness & night fraction in thousandths applied last, `None` to apply on the next tick
+    let mut last: Option<(Option<u32>, u32)> = None;
 
-    // start both watchers
-    tokio::spawn(device_changes(state.clone(), broadcaster.clone()));
-    tokio::spawn(brightness_changes(state.clone(), broadcaster.clone()));
+    loop {
+        let (enabled, schedule) = {
+            let settings = state.settings.lock().await;
+            (settings.apply_schedule, settings.schedule.clone())
+        };
+        if !enabled {
+            last = None;
+            sleep(SCHEDULE_TICK).await;
+            continue;
+        }
 
-    let app = Router::new()
-        .route("/ws/monitors", routing::get(ws_monitors_handler))
-        .with_state(broadcaster.clone());
+        // recomputed every tick, the sun times follow the date
+        let at = schedule::local_minutes();
+        let (day_of_year, utc_offset) = schedule::local_clock();
+        let brightness = match schedule::evaluate(&schedule, at, day_of_year, utc_offset) {
+            Ok(brightness) => brightness,
+            Err(e) => {
+                warn!("invalid schedule: {:?}", e);
+                sleep(SCHEDULE_TICK).await;
+                continue;
+            }
+        };
+        let night = match &schedule {
+            schedule::Schedule::Sun(sun) => 1.0 - schedule::daylight(sun, at, day_of_year, utc_offset),
+            schedule::Schedule::Fixed { .. } => 0.0,
+        };
+        let current = (brightness, (night * 1000.0).round() as u32);
+
+        if last != Some(current) {
+            let brightness_changed = last.is_none_or(|(b, _)| b != current.0);
+            let night_changed = last.is_none_or(|(_, n)| n != current.1);
+            last = Some(current);
+            debug!("schedule: brightness {:?}, night {:.3}", brightness, night);
+            let brightness = brightness.filter(|_| brightness_changed);
+            apply_schedule(&state, &schedule, brightness, night_changed.then_some(night)).await;
+        }
+        sleep(SCHEDULE_TICK).await;
+    }
+}
+
+/// `None`s are left alone: `brightness` when it didn't move, `night` for the dim & color temperature
+async fn apply_schedule(state: &AppState, schedule: &schedule::Schedule, brightness: Option<u32>, night: Option<f64>) {
+    let devices = state.monitor_device.lock().await;
+    let Some(tx) = state.overlay_tx.lock().await.clone() else {
+        return;
+    };
+    let (locked, color_temps) = {
+        let settings = state.settings.lock().await;
+        (settings.locked.clone(), settings.color_temps.clone())
+    };
+
+    for dev in devices.iter().filter(|d| !locked.contains(&d.id)) {
+        if let Some(brightness) = brightness {
+            if let Err(e) = dev.set_async(brightness).await {
+                warn!("schedule: failed to set brightness of '{}': {:?}", dev.friendly_name, e);
+            }
+        }
+
+        let (schedule::Schedule::Sun(sun), Some(night)) = (schedule, night) else {
+            continue;
+        };
+        if sun.night_dim > 0 {
+            let level = (sun.night_dim as f64 * night).round() as u8;
+            let backend = state.dim_state.lock().await.get(&dev.device_name).copied().unwrap_or_default().backend;
+            let result = if level > 0 {
+                dev.dim(level, backend, &tx).await
+            } else {
+                dev.clear_dim(backend, &tx).await
+            };
+            match result {
+                Ok(()) => state.dim_state.lock().await.entry(dev.device_name.clone()).or_default().level = level,
+                Err(e) => warn!("schedule: failed to dim '{}': {:?}", dev.friendly_name, e),
+            }
+        }
+        if let Some(night_kelvin) = sun.night_kelvin {
+            if DEFERRING_TO_NIGHT_LIGHT.load(Ordering::Relaxed) {
+                continue;
+            }
+            let day_kelvin = match color_temps.get(&dev.id) {
+                Some(temp) if temp.mechanism == ColorTempMechanism::Gamma => temp.kelvin,
+                _ => gamma::NEUTRAL_KELVIN,
+            };
+            let kelvin = day_kelvin as f64 + (night_kelvin as f64 - day_kelvin as f64) * night;
+            if let Err(e) = gamma::set_color_temperature(kelvin.round() as u32, &dev.device_name) {
+                warn!("schedule: failed to set color temperature of '{}': {:?}", dev.friendly_name, e);
+            }
+        }
+    }
+    broadcast_devices(state, &devices).await;
+}
+
+async fn first_launch_self_test(state: AppState) {
+    {
+        let settings = state.settings.lock().await;
+        if !settings.self_test_on_first_launch || settings.self_tested {
+            return;
+        }
+    }
+    state.ready_gate.wait_ready().await;
+    selftest::run(&state).await;
+
+    let mut settings = state.settings.lock().await;
+    settings.self_tested = true;
+    if let Err(e) = settings.save() {
+        warn!("failed to save settings: {:?}", e);
+    }
+}
+
+/// every managed device should have an overlay window, a mismatch means overlay dims go nowhere
+pub fn check_overlay_devices(devices: &[MonitorDeviceImpl]) {
+    let overlay_devices = overlay::overlay_devices();
+    if overlay_devices.is_empty() {
+        return; // overlay thread not up yet
+    }
+    for dev in monitors::devices_without_overlay(devices, &overlay_devices) {
+        warn!(
+            "no overlay window for '{}' (device: {}), overlay windows: {:?}",
+            dev.friendly_name, dev.device_name, overlay_devices
+        );
+    }
+}
+
+/// set while gamma color temperatures are held back for night light
+static DEFERRING_TO_NIGHT_LIGHT: AtomicBool = AtomicBool::new(false);
+const NIGHT_LIGHT_POLL: Duration = Duration::from_secs(10);
+
+/// watches night light's registry state when `defer_to_night_light` is on, gamma color
+/// temperatures go neutral while it's on & come back once it's off. ddc/ci presets are left alone
+async fn night_light_changes(state: AppState) {
+    loop {
+        sleep(NIGHT_LIGHT_POLL).await;
+
+        let defer = state.settings.lock().await.defer_to_night_light
+            && conflicts::night_light_enabled();
+        if defer == DEFERRING_TO_NIGHT_LIGHT.swap(defer, Ordering::Relaxed) {
+            continue;
+        }
+
+        let devices = state.monitor_device.lock().await;
+        if defer {
+            info!("night light is on, deferring gamma color temperatures to it");
+            let color_temps = state.settings.lock().await.color_temps.clone();
+            for dev in devices.iter() {
+                if color_temps.get(&dev.id).is_some_and(|t| t.mechanism == ColorTempMechanism::Gamma) {
+                    if let Err(e) = gamma::set_color_temperature(gamma::NEUTRAL_KELVIN, &dev.device_name) {
+                        warn!("failed to neutralize color temperature for '{}': {:?}", dev.friendly_name, e);
+                    }
+                }
+            }
+        } else {
+            info!("night light is off, restoring gamma color temperatures");
+            let targets: Vec<&MonitorDeviceImpl> = devices.iter().collect();
+            restore_color_temps(&state, &targets).await;
+        }
+    }
+}
+
+const FULLSCREEN_POLL: Duration = Duration::from_secs(2);
+
+/// moves the overlay dim of a `fullscreen_gamma` monitor to gamma while a fullscreen app covers it,
+/// & back once the app is gone. the dim state says `Gamma` meanwhile, so changes made in between
+/// go to the ramp too. gamma can't go below half brightness, a deeper dim gets lighter
+async fn fullscreen_changes(state: AppState) {
+    // `device_name` switched to gamma by this watcher
+    let mut switched: Option<String> = None;
+    loop {
+        sleep(FULLSCREEN_POLL).await;
+
+        let fullscreen = monitors::fullscreen_monitor();
+        let devices = state.monitor_device.lock().await;
+        let opted_in = state.settings.lock().await.fullscreen_gamma.clone();
+        let target = fullscreen.as_deref().and_then(|gdi_name| {
+            devices.iter().find(|d| monitors::gdi_device_name(&d.device_name) == gdi_name && opted_in.contains(&d.id))
+        });
+        if switched.as_deref() == target.map(|d| d.device_name.as_str()) {
+            continue;
+        }
+        let Some(tx) = state.overlay_tx.lock().await.clone() else {
+            continue;
+        };
+        let mut dim_state = state.dim_state.lock().await;
+
+        // the user may have picked another backend meanwhile, that one stays
+        if let Some(device_name) = switched.take() {
+            let dev = devices.iter().find(|d| d.device_name == device_name);
+            let dim = dim_state.get_mut(&device_name).filter(|dim| dim.backend == DimBackend::Gamma);
+            if let (Some(dev), Some(dim)) = (dev, dim) {
+                info!("fullscreen app left '{}', dimming through the overlay again", dev.friendly_name);
+                if let Err(e) = switch_dim_backend(dev, dim, DimBackend::Overlay, &tx).await {
+                    warn!("failed to move the dim of '{}' back to the overlay: {:?}", dev.friendly_name, e);
+                }
+            }
+        }
+        if let Some(dev) = target {
+            let dim = dim_state.entry(dev.device_name.clone()).or_default();
+            if dim.backend == DimBackend::Overlay {
+                info!("fullscreen app on '{}', dimming through gamma", dev.friendly_name);
+                match switch_dim_backend(dev, dim, DimBackend::Gamma, &tx).await {
+                    Ok(()) => switched = Some(dev.device_name.clone()),
+                    Err(e) => warn!("failed to move the dim of '{}' to gamma: {:?}", dev.friendly_name, e),
+                }
+            }
+        }
+    }
+}
+
+/// re-applies the saved color temperature of monitors which just showed up,
+/// the first scan counts too, so this covers startup
+async fn restore_color_temps(state: &AppState, devices: &[&MonitorDeviceImpl]) {
+    let color_temps = state.settings.lock().await.color_temps.clone();
+    let deferring = DEFERRING_TO_NIGHT_LIGHT.load(Ordering::Relaxed);
+    for dev in devices {
+        let Some(&temp) = color_temps.get(&dev.id) else {
+            continue;
+        };
+        if deferring && temp.mechanism == ColorTempMechanism::Gamma {
+            debug!("night light is on, not restoring color temperature for '{}'", dev.friendly_name);
+            continue;
+        }
+        match dev.set_color_temperature(temp) {
+            Ok(applied) => debug!("restored color temperature for '{}': {:?}", dev.friendly_name, applied),
+            Err(e) => warn!("failed to restore color temperature for '{}': {:?}", dev.friendly_name, e),
+        }
+    }
+}
+
+/// puts back the brightness & dim saved by `remember_levels`, monitors without any are left alone
+async fn restore_levels(state: &AppState, devices: &[&MonitorDeviceImpl]) {
+    let levels = {
+        let settings = state.settings.lock().await;
+        if !settings.restore_levels {
+            return;
+        }
+        settings.levels.clone()
+    };
+    let overlay_tx = state.overlay_tx.lock().await;
+    let mut dim_state = state.dim_state.lock().await;
+
+    for dev in devices {
+        let Some(saved) = levels.get(&dev.id) else {
+            continue;
+        };
+        if let Some(brightness) = saved.brightness {
+            match dev.set_async(brightness).await {
+                Ok(()) => debug!("restored brightness of '{}': {}", dev.friendly_name, brightness),
+                Err(e) => warn!("failed to restore brightness of '{}': {:?}", dev.friendly_name, e),
+            }
+        }
+        if let (true, Some(tx)) = (saved.dim_level > 0, overlay_tx.as_ref()) {
+            let dim = dim_state.entry(dev.device_name.clone()).or_default();
+            match dev.dim(saved.dim_level, dim.backend, tx).await {
+                Ok(()) => dim.level = saved.dim_level,
+                Err(e) => warn!("failed to restore dim of '{}': {:?}", dev.friendly_name, e),
+            }
+        }
+    }
+}
+
+/// bumped by every `remember_levels`, only the last one within `LEVELS_SAVE_DEBOUNCE` writes the file
+static LEVELS_SAVE: AtomicU64 = AtomicU64::new(0);
+/// a slider drag sends a command per step, the settings file is written once it settles
+const LEVELS_SAVE_DEBOUNCE: Duration = Duration::from_secs(2);
 
-    // keep it hardcoded :p
-    let listener = TcpListener::bind("127.0.0.1:8956").await?;
+/// keeps what the user set on a monitor for `restore_levels`, `brightness` is `None` for dim only changes
+pub async fn remember_levels(state: &AppState, id: &str, brightness: Option<u32>, dim_level: u8) {
+    {
+        let mut settings = state.settings.lock().await;
+        let levels = settings.levels.entry(id.to_string()).or_insert_with(MonitorLevels::default);
+        levels.brightness = brightness.or(levels.brightness);
+        levels.dim_level = dim_level;
+    }
+
+    let generation = LEVELS_SAVE.fetch_add(1, Ordering::Relaxed) + 1;
+    let state = state.clone();
     tokio::spawn(async move {
-        if let Err(e) = axum::serve(listener, app).await {
-            error!("WebSocket server failed: {}", e);
+        sleep(LEVELS_SAVE_DEBOUNCE).await;
+        if LEVELS_SAVE.load(Ordering::Relaxed) != generation {
+            return;
+        }
+        if let Err(e) = state.settings.lock().await.save() {
+            warn!("failed to save monitor levels: {:?}", e);
         }
     });
+}
 
-    Ok(())
+/// overlay windows start black, tint the ones with a configured color
+async fn restore_overlay_colors(state: &AppState, devices: &[&MonitorDeviceImpl]) {
+    let colors: Vec<(String, [u8; 3])> = {
+        let settings = state.settings.lock().await;
+        devices.iter()
+            .map(|d| (d.device_name.clone(), settings.overlay_color_for(&d.id)))
+            .filter(|(_, color)| *color != [0, 0, 0])
+            .collect()
+    };
+    for (device_name, color) in colors {
+        send_overlay_color(state, &device_name, color).await;
+    }
 }
 
-#[tauri::command]
-pub async fn set_brightness(
-    value: i32,
-    device_name: String,
-    state: tauri::State<'_, AppState>,
-) -> Result<(), String> {
+async fn send_overlay_color(state: &AppState, device_name: &str, color: [u8; 3]) {
+    if let Some(tx) = state.overlay_ctl_tx.lock().await.as_ref() {
+        let device_name = monitors::gdi_device_name(device_name).to_string();
+        let _ = tx.send(OverlayControl::SetColor { device_name, color }).await;
+    }
+}
+
+/// push the current state of `devices` to every client right away,
+/// skipped while frozen since the watcher sends it on release
+pub async fn broadcast_devices(state: &AppState, devices: &[MonitorDeviceImpl]) {
+    if state.broadcast_freeze.is_frozen() {
+        return;
+    }
+    let infos = monitor_infos(state, devices).await;
+    let _ = state.broadcaster.sender.send(infos);
+}
+
+/// slider jumps of at least this many percent ease over `brightness_transition_ms`
+const SMOOTH_MIN_DELTA: u32 = 20;
+
+/// runs a validated command against the live devices,
+/// shared by the websocket & the tauri commands
+pub async fn execute_command(state: &AppState, command: CommandEnvelope) -> Result<(), CommandError> {
+    command.validate()?;
+    // a slider or dim change takes over from a running cinema fade
+    CINEMA_FADE.fetch_add(1, Ordering::Relaxed);
+
     let devices = state.monitor_device.lock().await;
     let overlay_tx = state.overlay_tx.lock().await;
 
-    let tx = match overlay_tx.as_ref() {
-        Some(tx) => tx,
-        None => return Err("overlay channel not initialized".to_string()),
+    let tx = overlay_tx.as_ref().ok_or_else(|| {
+        CommandError::new(CommandErrorKind::Failed, "overlay channel not initialized")
+    })?;
+
+    let dev = resolve_device(state, &devices, command.device_name()).await?;
+
+    ensure_unlocked(state, dev).await?;
+
+    let failed = |e: anyhow::Error| CommandError::new(CommandErrorKind::Failed, e.to_string());
+    let mut dim_state = state.dim_state.lock().await;
+    let dim = dim_state.entry(dev.device_name.clone()).or_default();
+
+    let brightness = match command {
+        CommandEnvelope::Slider { value, .. } if value >= 0 => Some(value as u32),
+        CommandEnvelope::SetBrightness { brightness, .. } => Some(brightness as u32),
+        _ => None,
     };
+    match command {
+        CommandEnvelope::Slider { value, .. } => {
+            let value = value as i32;
+            let transition_ms = state.settings.lock().await.brightness_transition_ms;
+            let large_step = value >= 0 && dev.last_written.get()
+                .is_some_and(|last| last.abs_diff(value as u32) >= SMOOTH_MIN_DELTA);
+            if large_step && transition_ms > 0 {
+                // in the background, a newer slider value cancels it
+                let dev = dev.clone();
+                let generation = dev.start_ramp();
+                tokio::spawn(async move {
+                    if let Err(e) = dev.set_smooth(value as u32, Duration::from_millis(transition_ms), generation).await {
+                        warn!("brightness transition of '{}' failed: {:?}", dev.friendly_name, e);
+                    }
+                });
+            } else {
+                dev.slider(value, dim.backend, tx).await.map_err(failed)?;
+            }
+            if value < 0 {
+                dim.level = slider_to_alpha(value);
+            } else {
+                // back in the brightness range, the dim of either backend goes away
+                if dim.level > 0 {
+                    dev.clear_dim(dim.backend, tx).await.map_err(failed)?;
+                    dim.level = 0;
+                }
+                set_clones(state, &devices, dev, value as u32).await;
+            }
+        }
+        CommandEnvelope::SetBrightness { brightness, .. } => {
+            dev.set_async(brightness as u32).await.map_err(failed)?;
+            set_clones(state, &devices, dev, brightness as u32).await;
+            show_osd(state, dev, brightness as u32).await;
+        }
+        CommandEnvelope::SetDim { level, .. } => {
+            dev.dim(level as u8, dim.backend, tx).await.map_err(failed)?;
+            dim.level = level as u8;
+        }
+    }
 
-    if let Some(dev) = devices.iter().find(|d| d.device_name == device_name) {
-        let _ = dev.slider(value, tx).await.map_err(|e| error!("slider crashed: {:?}", e.to_string()));
-    } else {
-        return Err(format!("device not found: {}", device_name));
+    remember_levels(state, &dev.id, brightness, dim.level).await;
+    drop(dim_state);
+    // clients see the change now instead of on the next poll
+    broadcast_devices(state, &devices).await;
+    Ok(())
+}
+
+/// duplicated monitors show the same picture, so they get the same hardware brightness.
+/// dims need nothing, the overlay & gamma ramp already belong to the shared gdi device.
+/// physical monitors are matched to a shared `HMONITOR`'s devices by index, which may pick
+/// the wrong panel; writing the whole group keeps every panel right regardless
+async fn set_clones(state: &AppState, devices: &[MonitorDeviceImpl], dev: &MonitorDeviceImpl, percentage: u32) {
+    for peer in monitors::clone_peers(devices, dev) {
+        if ensure_unlocked(state, peer).await.is_err() {
+            continue;
+        }
+        if let Err(e) = peer.set_async(percentage).await {
+            warn!("failed to set brightness of duplicated monitor '{}': {:?}", peer.friendly_name, e);
+        }
+    }
+}
+
+/// a `device_name` or an alias from `Settings::aliases`, the device name wins on a clash
+async fn resolve_device<'a>(
+    state: &AppState,
+    devices: &'a [MonitorDeviceImpl],
+    selector: &str,
+) -> Result<&'a MonitorDeviceImpl, CommandError> {
+    if let Some(dev) = devices.iter().find(|d| d.device_name == selector) {
+        return Ok(dev);
     }
+    let id = state.settings.lock().await.aliases.get(selector).cloned();
+    match id {
+        Some(id) => devices.iter().find(|d| d.id == id).ok_or_else(|| CommandError::new(
+            CommandErrorKind::NotFound,
+            format!("alias '{}' maps to a monitor which isn't connected: {}", selector, id),
+        )),
+        None => Err(CommandError::new(
+            CommandErrorKind::NotFound,
+            format!("device not found: {}", selector),
+        )),
+    }
+}
 
+async fn ensure_unlocked(state: &AppState, dev: &MonitorDeviceImpl) -> Result<(), CommandError> {
+    if state.settings.lock().await.locked.contains(&dev.id) {
+        return Err(CommandError::new(
+            CommandErrorKind::Locked,
+            format!("monitor locked: {}", dev.device_name),
+        ));
+    }
     Ok(())
 }
+
+/// steps the hardware brightness by `delta`, clamped to the monitor's floor..=100, returns the new level.
+/// internal displays land on a level they support
+pub async fn step_brightness(state: &AppState, device_name: &str, delta: i32) -> Result<u32, CommandError> {
+    // held across get & set so concurrent steps don't overwrite each other
+    let devices = state.monitor_device.lock().await;
+    let dev = resolve_device(state, &devices, device_name).await?;
+
+    ensure_unlocked(state, dev).await?;
+
+    let failed = |e: anyhow::Error| CommandError::new(CommandErrorKind::Failed, e.to_string());
+    let current = dev.get_async().await.map_err(failed)? as i32;
+    let mut level = (current + delta).clamp(monitors::min_brightness(&dev.id) as i32, 100) as u32;
+    if dev.is_internal() && !mock::is_mock(&dev.id) {
+        level = snap_internal(dev, current as u32, level).map_err(failed)?;
+    }
+    if level as i32 != current {
+        dev.set_async(level).await.map_err(failed)?;
+        set_clones(state, &devices, dev, level).await;
+    }
+    show_osd(state, dev, level).await;
+    Ok(level)
+}
+
+/// the supported level nearest to `target`. when that's `current` again, the next one towards
+/// `target` instead, coarse panels would otherwise never move on small steps
+fn snap_internal(dev: &MonitorDeviceImpl, current: u32, target: u32) -> anyhow::Result<u32> {
+    let supported = brightness::ioctl_query_supported_brightness(dev)?;
+    let nearest = supported.get_nearest(target)
+        .ok_or_else(|| anyhow!("'{}' reports no supported brightness levels", dev.friendly_name))? as u32;
+    if nearest != current || target == current {
+        return Ok(nearest);
+    }
+    let next = supported.levels().iter()
+        .map(|&level| level as u32)
+        .filter(|&level| if target > current { level > current } else { level < current })
+        .min_by_key(|&level| level.abs_diff(current));
+    Ok(next.unwrap_or(current))
+}
+
+/// steps a monitor's brightness by `delta` from what it's on now, returns the new level
+#[tauri::command]
+pub async fn adjust_brightness(
+    device_name: String,
+    delta: i32,
+    state: tauri::State<'_, AppState>,
+) -> Result<u32, String> {
+    step_brightness(&state, &device_name, delta).await.map_err(|e| e.to_string())
+}
+
+/// feedback for changes made outside the window (scroll, websocket clients, ...)
+async fn show_osd(state: &AppState, dev: &MonitorDeviceImpl, level: u32) {
+    let label = {
+        let settings = state.settings.lock().await;
+        if !settings.show_osd {
+            return;
+        }
+        settings.labels.get(&dev.id).cloned().unwrap_or_else(|| dev.friendly_name.clone())
+    };
+    if let Some(tx) = state.overlay_ctl_tx.lock().await.as_ref() {
+        let _ = tx.send(OverlayControl::Osd { device_name: dev.device_name.clone(), label, level }).await;
+    }
+}
+
+/// Handle each connected websocket client
+async fn handle_monitor_socket(
+    socket: WebSocket,
+    state: AppState,
+) {
+    let (mut sender, mut receiver) = socket.split();
+    let mut rx = state.broadcaster.sender.subscribe();
+
+    // send initial monitor list, off the cached handles like every later update
+    let infos = {
+        let devices = state.monitor_device.lock().await;
+        monitor_infos(&state, &devices).await
+    };
+    let _ = sender.send(Message::Text(Utf8Bytes::from(
+        serde_json::to_string(&infos).unwrap()))
+    ).await;
+
+    loop {
+        tokio::select! {
+            // forward all broadcast updates to this websocket client
+            update = rx.recv() => {
+                let Ok(monitors) = update else { break };
+                let json = serde_json::to_string(&monitors).unwrap();
+                let _ = sender.send(Message::Text(Utf8Bytes::from(json))).await;
+            }
+            // commands from the client, every message gets a reply
+            incoming = receiver.next() => {
+                match incoming {
+                    Some(Ok(Message::Text(text))) => {
+                        let result = match CommandEnvelope::parse(text.as_str()) {
+                            Ok(command) => execute_command(&state, command).await,
+                            Err(e) => Err(e),
+                        };
+                        if let Err(e) = &result {
+                            warn!("websocket command rejected: {}", e);
+                        }
+                        let reply = serde_json::to_string(&CommandReply::from(result)).unwrap();
+                        let _ = sender.send(Message::Text(Utf8Bytes::from(reply))).await;
+                    }
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(_)) => {}
+                }
+            }
+        }
+    }
+}
+
+
+/// where the websocket server listens: `addr`, else `FADE_WS_ADDR`, else `127.0.0.1:8956`
+pub fn ws_addr(addr: Option<SocketAddr>) -> anyhow::Result<SocketAddr> {
+    if let Some(addr) = addr {
+        return Ok(addr);
+    }
+    match env::var(WS_ADDR_ENV) {
+        Ok(raw) => raw.trim().parse()
+            .map_err(|e| anyhow!("invalid {} '{}', expected ip:port: {}", WS_ADDR_ENV, raw, e)),
+        Err(_) => Ok(WS_ADDR.into()),
+    }
+}
+
+/// address the websocket server got bound to, set once
+static WS_BOUND_ADDR: OnceLock<SocketAddr> = OnceLock::new();
+
+/// A simple websocket for monitors based updates
+pub async fn start_ws_server(state: AppState, addr: Option<SocketAddr>) -> anyhow::Result<()> {
+    let addr = match ws_addr(addr) {
+        Ok(addr) => addr,
+        Err(e) => {
+            set_ws_status(&state, WsStatus::Down { error: e.to_string() }).await;
+            return Err(e);
+        }
+    };
+
+    let broadcaster = state.broadcaster.clone();
+    let (brightness_poll, device_poll) = {
+        let settings = state.settings.lock().await;
+        (
+            poll_interval("brightness_poll_ms", settings.brightness_poll_ms),
+            poll_interval("device_poll_ms", settings.device_poll_ms),
+        )
+    };
+
+    // start both watchers
+    tokio::spawn(startup_grace(state.clone()));
+    tokio::spawn(device_changes(state.clone(), broadcaster.clone(), device_poll));
+    tokio::spawn(brightness_changes(state.clone(), broadcaster.clone(), brightness_poll));
+    tokio::spawn(wake_changes(state.clone()));
+    tokio::spawn(schedule_changes(state.clone()));
+    tokio::spawn(night_light_changes(state.clone()));
+    tokio::spawn(fullscreen_changes(state.clone()));
+    tokio::spawn(first_launch_self_test(state.clone()));
+    tokio::spawn(buttons::button_changes(state.clone()));
+
+    let app = Router::new()
+        .route("/ws/monitors", routing::get(ws_monitors_handler))
+        .route("/schema", routing::get(schema_handler))
+        .route("/monitors", routing::get(monitors_handler))
+        .route("/brightness", routing::post(brightness_handler))
+        .route(instance::SHOW_PATH, routing::post(show_handler))
+        .with_state(state.clone());
+
+    let listener = match bind_ws_listener(addr).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            set_ws_status(&state, WsStatus::Down { error: e.to_string() }).await;
+            return Err(anyhow!("failed to bind websocket server to {}: {}", addr, e));
+        }
+    };
+    // port 0 binds a random one, the logged address is the real one
+    let bound = listener.local_addr().unwrap_or(addr);
+    info!("websocket server listening on {}", bound);
+    let _ = WS_BOUND_ADDR.set(bound);
+    tokio::spawn(supervise_ws_server(state, listener, app, bound));
+
+    Ok(())
+}
+
+/// health of the websocket server, emitted as `ws-status` on every change
+#[derive(Debug, Serialize, Clone, Default, PartialEq, Eq)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum WsStatus {
+    #[default]
+    Starting,
+    Running,
+    /// failed & waiting for the backoff before rebinding
+    Restarting { attempt: u32, error: String },
+    /// gave up, only an app restart brings it back
+    Down { error: String },
+    /// shut down on purpose
+    Stopped,
+}
+
+async fn set_ws_status(state: &AppState, status: WsStatus) {
+    let mut current = state.ws_status.lock().await;
+    if *current == status {
+        return;
+    }
+    debug!("websocket server status: {:?} -> {:?}", *current, status);
+    if let Err(e) = app::app_handle().emit("ws-status", &status) {
+        warn!("failed to emit websocket server status: {}", e);
+    }
+    *current = status;
+}
+
+/// default bind address
+const WS_ADDR: ([u8; 4], u16) = ([127, 0, 0, 1], 8956);
+/// overrides `WS_ADDR`, e.g. `0.0.0.0:9000` for kiosk setups
+const WS_ADDR_ENV: &str = "FADE_WS_ADDR";
+/// restarts in a row before the server is given up
+const WS_MAX_RESTARTS: u32 = 5;
+/// doubled on every restart in a row
+const WS_RESTART_BACKOFF: Duration = Duration::from_secs(1);
+/// a server that ran this long resets the restart count
+const WS_STABLE_AFTER: Duration = Duration::from_secs(60);
+
+/// `SO_REUSEADDR` so a restart isn't blocked by the old socket lingering in `TIME_WAIT`
+async fn bind_ws_listener(addr: SocketAddr) -> std::io::Result<TcpListener> {
+    let socket = if addr.is_ipv4() { TcpSocket::new_v4()? } else { TcpSocket::new_v6()? };
+    socket.set_reuseaddr(true)?;
+    socket.bind(addr)?;
+    socket.listen(1024)
+}
+
+/// keeps the control api alive, a crashed or failed server is rebound with backoff.
+/// `Ok` from `axum::serve` means an intentional shutdown & isn't restarted
+async fn supervise_ws_server(state: AppState, listener: TcpListener, app: Router, addr: SocketAddr) {
+    let mut listener = Some(listener);
+    let mut restarts = 0;
+
+    loop {
+        let started = Instant::now();
+        let bound = match listener.take() {
+            Some(listener) => Ok(listener),
+            None => bind_ws_listener(addr).await,
+        };
+
+        let error = match bound {
+            Ok(listener) => {
+                set_ws_status(&state, WsStatus::Running).await;
+                let app = app.clone();
+                // own task so a panic is caught as a `JoinError`
+                match tokio::spawn(async move { axum::serve(listener, app).await }).await {
+                    Ok(Ok(())) => {
+                        info!("websocket server shut down");
+                        set_ws_status(&state, WsStatus::Stopped).await;
+                        return;
+                    }
+                    Ok(Err(e)) => {
+                        error!("WebSocket server failed: {}", e);
+                        e.to_string()
+                    }
+                    Err(e) => {
+                        error!("WebSocket server task died: {}", e);
+                        e.to_string()
+                    }
+                }
+            }
+            Err(e) => {
+                error!("failed to rebind websocket server: {}", e);
+                e.to_string()
+            }
+        };
+
+        if started.elapsed() >= WS_STABLE_AFTER {
+            restarts = 0;
+        }
+        restarts += 1;
+        if restarts > WS_MAX_RESTARTS {
+            error!("websocket server gave up after {} restarts", WS_MAX_RESTARTS);
+            set_ws_status(&state, WsStatus::Down { error }).await;
+            return;
+        }
+
+        let backoff = WS_RESTART_BACKOFF * 2u32.pow(restarts - 1);
+        warn!("restarting websocket server in {:?} (attempt {}/{})", backoff, restarts, WS_MAX_RESTARTS);
+        set_ws_status(&state, WsStatus::Restarting { attempt: restarts, error }).await;
+        sleep(backoff).await;
+    }
+}
+
+/// queues a slider value, returns once it's validated & the monitor is unlocked.
+/// the write happens afterwards, a failed one is emitted as `brightness-write-failed`
+#[tauri::command]
+pub async fn set_brightness(
+    value: i32,
+    device_name: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), String> {
+    let command = CommandEnvelope::Slider { device_name, value: value as i64 };
+    command.validate().map_err(|e| e.to_string())?;
+    // checked now, the write itself happens later & reports through `write_slider`
+    let device_name = {
+        let devices = state.monitor_device.lock().await;
+        let dev = resolve_device(&state, &devices, command.device_name()).await.map_err(|e| e.to_string())?;
+        ensure_unlocked(&state, dev).await.map_err(|e| e.to_string())?;
+        dev.device_name.clone()
+    };
+    queue_slider(&state, device_name, value as i64);
+    Ok(())
+}
+
+/// ddc/ci takes ~50ms a write & a slider drag sends far more values than that,
+/// a device's values are written at least this far apart, each time the latest one
+const SLIDER_DEBOUNCE: Duration = Duration::from_millis(40);
+/// quiet time after which a device's writer task ends, the next value starts another
+const SLIDER_WRITER_IDLE: Duration = Duration::from_secs(1);
+
+/// latest slider value per `device_name`, for the device's writer task
+static SLIDER_LATEST: LazyLock<Mutex<HashMap<String, watch::Sender<i64>>>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// hands `value` to the device's writer, starting one if there's none
+fn queue_slider(state: &AppState, device_name: String, value: i64) {
+    let Ok(mut latest) = SLIDER_LATEST.lock() else {
+        return;
+    };
+    if let Some(tx) = latest.get(&device_name) {
+        tx.send_replace(value);
+        return;
+    }
+    let (tx, mut rx) = watch::channel(value);
+    rx.mark_changed();
+    latest.insert(device_name.clone(), tx);
+    tokio::spawn(write_slider(state.clone(), device_name, rx));
+}
+
+/// a slider write that failed after `set_brightness` already returned
+#[derive(Debug, Serialize, Clone)]
+pub struct BrightnessWriteFailed {
+    pub device_name: String,
+    pub value: i64,
+    pub error: String,
+}
+
+/// writes the latest value whenever there's a new one, the last value of a drag always lands.
+/// failures are emitted as `brightness-write-failed`, nobody awaits the write
+async fn write_slider(state: AppState, device_name: String, mut rx: watch::Receiver<i64>) {
+    loop {
+        if timeout(SLIDER_WRITER_IDLE, rx.changed()).await.is_err() {
+            // under the lock, a value queued right now still finds a running writer
+            let Ok(mut latest) = SLIDER_LATEST.lock() else {
+                return;
+            };
+            if rx.has_changed().unwrap_or(false) {
+                continue;
+            }
+            latest.remove(&device_name);
+            return;
+        }
+        let value = *rx.borrow_and_update();
+        let command = CommandEnvelope::Slider { device_name: device_name.clone(), value };
+        if let Err(e) = execute_command(&state, command).await {
+            error!("slider crashed: {}", e);
+            let failed = BrightnessWriteFailed { device_name: device_name.clone(), value, error: e.to_string() };
+            if let Err(e) = app::app_handle().emit("brightness-write-failed", failed) {
+                warn!("failed to emit slider write failure: {}", e);
+            }
+        }
+        sleep(SLIDER_DEBOUNCE).await;
+    }
+}
+
+/// current brightness percentage, read from the monitor instead of waiting for the next broadcast
+#[tauri::command]
+pub async fn get_brightness(
+    device_name: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<u32, String> {
+    let dev = {
+        let devices = state.monitor_device.lock().await;
+        resolve_device(&state, &devices, &device_name).await.map_err(|e| e.to_string())?.clone()
+    };
+    tauri::async_runtime::spawn_blocking(move || dev.get())
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| {
+            error!("failed to read brightness: {:?}", e);
+            e.to_string()
+        })
+}
+
+/// ddc/ci contrast percentage, external monitors only
+#[tauri::command]
+pub async fn set_contrast(
+    contrast: u32,
+    device_name: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), String> {
+    if contrast > 100 {
+        return Err(format!("contrast must be within 0..=100, got {}", contrast));
+    }
+    let devices = state.monitor_device.lock().await;
+    let dev = resolve_device(&state, &devices, &device_name).await.map_err(|e| e.to_string())?;
+    dev.set_contrast(contrast).map_err(|e| {
+        error!("failed to set contrast: {:?}", e);
+        e.to_string()
+    })?;
+    broadcast_devices(&state, &devices).await;
+    Ok(())
+}
+
+/// ddc/ci input source of a monitor, e.g. `0x11` for hdmi 1 or `0x0f` for displayport 1
+#[tauri::command]
+pub async fn set_input_source(
+    device_name: String,
+    input: u8,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), String> {
+    let devices = state.monitor_device.lock().await;
+    let dev = resolve_device(&state, &devices, &device_name).await.map_err(|e| e.to_string())?;
+    dev.switch_input(input).map_err(|e| {
+        error!("failed to switch input: {:?}", e);
+        e.to_string()
+    })
+}
+
+/// json schema of the websocket protocol, same as `GET /schema`
+#[tauri::command]
+pub async fn protocol_schema() -> Result<serde_json::Value, String> {
+    Ok(protocol::schema())
+}
+
+/// raw ddc/ci feature `code` of a monitor as `(current, max)`, for features fade has no command for
+#[tauri::command]
+pub async fn get_vcp(
+    code: u8,
+    device_name: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<(u32, u32), String> {
+    let devices = state.monitor_device.lock().await;
+    let dev = resolve_device(&state, &devices, &device_name).await.map_err(|e| e.to_string())?;
+    dev.get_vcp(code).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn set_vcp(
+    code: u8,
+    value: u32,
+    device_name: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), String> {
+    let devices = state.monitor_device.lock().await;
+    let dev = resolve_device(&state, &devices, &device_name).await.map_err(|e| e.to_string())?;
+    dev.set_vcp(code, value).map_err(|e| {
+        error!("failed to set vcp: {:?}", e);
+        e.to_string()
+    })
+}
+
+/// vcp codes & values the monitor advertises, for showing only the controls it supports.
+/// the first call per monitor takes a few seconds
+#[tauri::command]
+pub async fn get_capabilities(
+    device_name: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<brightness::Capabilities, String> {
+    let dev = {
+        let devices = state.monitor_device.lock().await;
+        resolve_device(&state, &devices, &device_name).await.map_err(|e| e.to_string())?.clone()
+    };
+    tauri::async_runtime::spawn_blocking(move || dev.capabilities())
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| {
+            warn!("no capabilities for '{}': {:?}", device_name, e);
+            e.to_string()
+        })
+}
+
+/// device under the mouse cursor, errors if fade doesn't manage that monitor
+pub async fn cursor_device_name(state: &AppState) -> Result<String, String> {
+    let gdi_name = monitors::cursor_monitor()
+        .ok_or_else(|| "cursor is not on any monitor".to_string())?;
+    state.monitor_device.lock().await
+        .iter()
+        .find(|d| monitors::gdi_device_name(&d.device_name) == gdi_name)
+        .map(|d| d.device_name.clone())
+        .ok_or_else(|| format!("monitor under the cursor is not managed by fade: {}", gdi_name))
+}
+
+/// `device_name` of the monitor under the cursor, `None` if it isn't managed
+#[tauri::command]
+pub async fn cursor_monitor(
+    state: tauri::State<'_, AppState>,
+) -> Result<Option<String>, String> {
+    Ok(cursor_device_name(&state).await.ok())
+}
+
+/// same as `set_brightness`, for the monitor under the cursor
+#[tauri::command]
+pub async fn set_cursor_brightness(
+    value: i32,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), String> {
+    let device_name = cursor_device_name(&state).await?;
+    let command = CommandEnvelope::Slider { device_name, value: value as i64 };
+    execute_command(&state, command).await.map_err(|e| e.to_string())
+}
+
+/// steps the brightness of the monitor under the cursor, returns the new level
+#[tauri::command]
+pub async fn adjust_cursor_brightness(
+    delta: i32,
+    state: tauri::State<'_, AppState>,
+) -> Result<u32, String> {
+    let device_name = cursor_device_name(&state).await?;
+    step_brightness(&state, &device_name, delta).await.map_err(|e| e.to_string())
+}
+
+/// discrete levels of an internal display, so the slider can snap to them
+#[tauri::command]
+pub async fn internal_supported_levels(
+    device_name: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<u8>, String> {
+    let devices = state.monitor_device.lock().await;
+    let dev = resolve_device(&state, &devices, &device_name).await.map_err(|e| e.to_string())?;
+    if !dev.is_internal() {
+        return Err(format!("supported levels are only available for internal displays: {}", device_name));
+    }
+    supported_levels(dev)
+}
+
+/// the levels an internal display supports, for notches on the slider.
+/// empty for external & mock monitors, their brightness is continuous
+#[tauri::command]
+pub async fn get_supported_levels(
+    device_name: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<u8>, String> {
+    let devices = state.monitor_device.lock().await;
+    let dev = resolve_device(&state, &devices, &device_name).await.map_err(|e| e.to_string())?;
+    if !dev.is_internal() || mock::is_mock(&dev.id) {
+        return Ok(Vec::new());
+    }
+    supported_levels(dev)
+}
+
+/// sorted, without the duplicates some drivers report
+fn supported_levels(dev: &MonitorDeviceImpl) -> Result<Vec<u8>, String> {
+    let mut levels = brightness::ioctl_query_supported_brightness(dev)
+        .map_err(|e| e.to_string())?
+        .levels()
+        .to_vec();
+    levels.sort_unstable();
+    levels.dedup();
+    Ok(levels)
+}
+
+/// label a monitor by its `id`, persisted in the settings
+#[tauri::command]
+pub async fn set_monitor_label(
+    id: String,
+    label: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), String> {
+    let label = label.trim().to_string();
+    if label.is_empty() {
+        return Err("label can't be empty".to_string());
+    }
+    update_label(&state, id, Some(label)).await
+}
+
+#[tauri::command]
+pub async fn clear_monitor_label(
+    id: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), String> {
+    update_label(&state, id, None).await
+}
+
+async fn update_label(state: &AppState, id: String, label: Option<String>) -> Result<(), String> {
+    let devices = state.monitor_device.lock().await;
+    if !devices.iter().any(|d| d.id == id) {
+        return Err(format!("device not found: {}", id));
+    }
+
+    {
+        let mut settings = state.settings.lock().await;
+        match label {
+            Some(label) => settings.labels.insert(id, label),
+            None => settings.labels.remove(&id),
+        };
+        settings.save().map_err(|e| e.to_string())?;
+    }
+
+    broadcast_devices(state, &devices).await;
+    Ok(())
+}
+
+/// lowest brightness `set_brightness` & the slider go to on monitor `id`, `None` back to the default
+#[tauri::command]
+pub async fn set_min_brightness(
+    id: String,
+    floor: Option<u32>,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), String> {
+    if floor.is_some_and(|floor| floor > 100) {
+        return Err(format!("minimum brightness out of range: {:?}", floor));
+    }
+    if !state.monitor_device.lock().await.iter().any(|d| d.id == id) {
+        return Err(format!("device not found: {}", id));
+    }
+
+    let mut settings = state.settings.lock().await;
+    match floor {
+        Some(floor) => settings.min_brightness.insert(id, floor),
+        None => settings.min_brightness.remove(&id),
+    };
+    monitors::set_min_brightness(settings.min_brightness.clone());
+    settings.save().map_err(|e| e.to_string())
+}
+
+/// stable name for a monitor, usable instead of its `device_name` by the websocket & commands
+#[tauri::command]
+pub async fn set_monitor_alias(
+    id: String,
+    alias: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), String> {
+    let alias = alias.trim().to_string();
+    if alias.is_empty() {
+        return Err("alias can't be empty".to_string());
+    }
+    if alias.starts_with(r"\\.\") {
+        return Err(format!("alias can't look like a device name: {}", alias));
+    }
+
+    let devices = state.monitor_device.lock().await;
+    if !devices.iter().any(|d| d.id == id) {
+        return Err(format!("device not found: {}", id));
+    }
+    {
+        let mut settings = state.settings.lock().await;
+        // one alias per monitor, renaming replaces the old one
+        settings.aliases.retain(|_, aliased| *aliased != id);
+        settings.aliases.insert(alias, id);
+        settings.save().map_err(|e| e.to_string())?;
+    }
+    broadcast_devices(&state, &devices).await;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn clear_monitor_alias(
+    alias: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), String> {
+    {
+        let mut settings = state.settings.lock().await;
+        if settings.aliases.remove(&alias).is_none() {
+            return Err(format!("unknown alias: {}", alias));
+        }
+        settings.save().map_err(|e| e.to_string())?;
+    }
+    let devices = state.monitor_device.lock().await;
+    broadcast_devices(&state, &devices).await;
+    Ok(())
+}
+
+/// brightness back to 100% & every dim cleared, for a single monitor
+#[tauri::command]
+pub async fn reset_monitor(
+    device_name: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), String> {
+    let devices = state.monitor_device.lock().await;
+    let overlay_tx = state.overlay_tx.lock().await;
+
+    let tx = match overlay_tx.as_ref() {
+        Some(tx) => tx,
+        None => return Err("overlay channel not initialized".to_string()),
+    };
+
+    let dev = match devices.iter().find(|d| d.device_name == device_name) {
+        Some(dev) => dev,
+        None => return Err(format!("device not found: {}", device_name)),
+    };
+    ensure_unlocked(&state, dev).await.map_err(|e| e.to_string())?;
+
+    let result = reset_device(&state, dev, tx).await;
+    broadcast_devices(&state, &devices).await;
+    result
+}
+
+/// the tray's "Reset": `reset_monitor` for every unlocked monitor
+pub async fn reset_all(state: &AppState) {
+    let freeze = state.broadcast_freeze.hold();
+    let devices = state.monitor_device.lock().await;
+    let overlay_tx = state.overlay_tx.lock().await;
+    let Some(tx) = overlay_tx.as_ref() else {
+        error!("reset failed, overlay channel not initialized");
+        return;
+    };
+
+    let mut reset = 0;
+    for dev in devices.iter() {
+        if ensure_unlocked(state, dev).await.is_err() {
+            info!("not resetting locked monitor '{}'", dev.friendly_name);
+            continue;
+        }
+        match reset_device(state, dev, tx).await {
+            Ok(()) => reset += 1,
+            Err(e) => warn!("{}", e),
+        }
+    }
+    drop(freeze);
+    broadcast_devices(state, &devices).await;
+    info!("reset {} of {} monitors", reset, devices.len());
+}
+
+async fn reset_device(state: &AppState, dev: &MonitorDeviceImpl, tx: &Sender<Overlay>) -> Result<(), String> {
+    let mut errors = Vec::new();
+    if let Err(e) = dev.set_async(100).await {
+        errors.push(format!("brightness: {}", e));
+    }
+    if let Err(e) = dev.clear_dim(DimBackend::Overlay, tx).await {
+        errors.push(format!("overlay: {}", e));
+    }
+    if let Err(e) = dev.clear_dim(DimBackend::Gamma, tx).await {
+        errors.push(format!("gamma: {}", e));
+    }
+    if let Some(dim) = state.dim_state.lock().await.get_mut(&dev.device_name) {
+        dim.level = 0;
+    }
+    remember_levels(state, &dev.id, Some(100), 0).await;
+
+    if errors.is_empty() {
+        info!("device '{}' reset", dev.device_name);
+        Ok(())
+    } else {
+        Err(format!("failed to fully reset device {}: {}", dev.device_name, errors.join(", ")))
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum GradientAxis {
+    /// left to right
+    Horizontal,
+    /// top to bottom
+    Vertical,
+}
+
+/// `count` evenly spaced levels from `start` to `end`
+fn gradient_levels(start: u8, end: u8, count: usize) -> Vec<u8> {
+    if count <= 1 {
+        return vec![start; count];
+    }
+    (0..count)
+        .map(|i| {
+            let t = i as f32 / (count - 1) as f32;
+            (start as f32 + (end as f32 - start as f32) * t).round() as u8
+        })
+        .collect()
+}
+
+/// bumped by every `cinema_fade` & command, a fade stops once it's outdated
+static CINEMA_FADE: AtomicU64 = AtomicU64::new(0);
+/// an overlay update per monitor & step, the overlay loop takes one message per tick
+const CINEMA_STEP: Duration = Duration::from_millis(50);
+
+/// fades the dim of every unlocked monitor from its current level to `target_alpha` over
+/// `duration_ms`, 255 is black & 0 back to clear. returns right away, a newer fade or
+/// any command interrupts it where it is
+#[tauri::command]
+pub async fn cinema_fade(
+    target_alpha: u8,
+    duration_ms: u64,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), String> {
+    let tx = state.overlay_tx.lock().await.clone()
+        .ok_or_else(|| "overlay channel not initialized".to_string())?;
+    let locked = state.settings.lock().await.locked.clone();
+    let starts: Vec<(MonitorDeviceImpl, DimState)> = {
+        let devices = state.monitor_device.lock().await;
+        let dim_state = state.dim_state.lock().await;
+        devices.iter()
+            .filter(|d| !locked.contains(&d.id))
+            .map(|d| (d.clone(), dim_state.get(&d.device_name).copied().unwrap_or_default()))
+            .collect()
+    };
+
+    let generation = CINEMA_FADE.fetch_add(1, Ordering::Relaxed) + 1;
+    let state = state.inner().clone();
+    tokio::spawn(async move {
+        let steps = (duration_ms / CINEMA_STEP.as_millis() as u64).max(1);
+        for step in 1..=steps {
+            let t = step as f64 / steps as f64;
+            for (dev, from) in &starts {
+                if CINEMA_FADE.load(Ordering::Relaxed) != generation {
+                    debug!("cinema fade interrupted at step {}/{}", step, steps);
+                    return;
+                }
+                let level = (from.level as f64 + (target_alpha as f64 - from.level as f64) * t).round() as u8;
+                // overlay monitors get an `Overlay` message, the others go through their own backend
+                if let Err(e) = dev.dim(level, from.backend, &tx).await {
+                    warn!("cinema fade failed on '{}': {:?}", dev.friendly_name, e);
+                    continue;
+                }
+                state.dim_state.lock().await.entry(dev.device_name.clone()).or_default().level = level;
+            }
+            if step < steps {
+                sleep(CINEMA_STEP).await;
+            }
+        }
+        info!("cinema fade to {} done on {} monitors", target_alpha, starts.len());
+    });
+    Ok(())
+}
+
+/// dims monitors progressively by their position on the desktop,
+/// `start_level` for the leftmost/topmost up to `end_level` for the rightmost/bottommost
+#[tauri::command]
+pub async fn gradient_dim(
+    axis: GradientAxis,
+    start_level: u8,
+    end_level: u8,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), String> {
+    let devices = state.monitor_device.lock().await;
+    let overlay_tx = state.overlay_tx.lock().await;
+
+    let tx = match overlay_tx.as_ref() {
+        Some(tx) => tx,
+        None => return Err("overlay channel not initialized".to_string()),
+    };
+
+    let rects = monitors::monitor_rects().map_err(|e| e.to_string())?;
+    let locked = state.settings.lock().await.locked.clone();
+    // locked monitors keep their level & don't take a step in the gradient
+    let mut ordered: Vec<(i32, &MonitorDeviceImpl)> = devices.iter()
+        .filter(|d| !locked.contains(&d.id))
+        .filter_map(|d| {
+            rects.get(monitors::gdi_device_name(&d.device_name)).map(|rect| {
+                match axis {
+                    GradientAxis::Horizontal => (rect.left, d),
+                    GradientAxis::Vertical => (rect.top, d),
+                }
+            })
+        })
+        .collect();
+    ordered.sort_by_key(|(position, _)| *position);
+
+    let levels = gradient_levels(start_level, end_level, ordered.len());
+    let mut dim_state = state.dim_state.lock().await;
+    let mut errors = Vec::new();
+
+    for ((_, dev), level) in ordered.into_iter().zip(levels) {
+        let dim = dim_state.entry(dev.device_name.clone()).or_default();
+        match dev.dim(level, dim.backend, tx).await {
+            Ok(()) => dim.level = level,
+            Err(e) => errors.push(format!("{}: {}", dev.device_name, e)),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors.join(", "))
+    }
+}
+
+/// reveals every monitor dimmed by `gradient_dim`
+#[tauri::command]
+pub async fn clear_gradient_dim(
+    state: tauri::State<'_, AppState>,
+) -> Result<(), String> {
+    gradient_dim(GradientAxis::Horizontal, 0, 0, state).await
+}
+
+/// dims only the `targets` (device names or aliases) to `level` & clears every other monitor, e.g. just
+/// the secondary one. each monitor goes through its own `DimBackend`. nothing changes when a target is
+/// unknown or its backend needs an overlay window it doesn't have, the error lists them
+#[tauri::command]
+pub async fn dim_monitors(
+    targets: Vec<String>,
+    level: u8,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), String> {
+    let devices = state.monitor_device.lock().await;
+    let overlay_tx = state.overlay_tx.lock().await;
+    let tx = overlay_tx.as_ref().ok_or("overlay channel not initialized")?;
+
+    let mut resolved = Vec::with_capacity(targets.len());
+    let mut unknown = Vec::new();
+    for target in &targets {
+        match resolve_device(&state, &devices, target).await {
+            Ok(dev) => resolved.push(dev),
+            Err(_) => unknown.push(target),
+        }
+    }
+    if !unknown.is_empty() {
+        return Err(format!("device not found: {:?}", unknown));
+    }
+
+    let mut dim_state = state.dim_state.lock().await;
+    let overlay_devices = overlay::overlay_devices();
+    let no_overlay: Vec<&str> = resolved.iter()
+        .filter(|d| {
+            let backend = dim_state.get(&d.device_name).map(|s| s.backend).unwrap_or_default();
+            backend != DimBackend::Gamma
+                && !overlay_devices.iter().any(|o| o == monitors::gdi_device_name(&d.device_name))
+        })
+        .map(|d| d.device_name.as_str())
+        .collect();
+    if !no_overlay.is_empty() {
+        return Err(format!("no overlay window for: {:?}", no_overlay));
+    }
+
+    // clones share one gdi device, a targeted clone dims its peers too
+    let dimmed: HashSet<&str> = resolved.iter().map(|d| monitors::gdi_device_name(&d.device_name)).collect();
+    let locked = state.settings.lock().await.locked.clone();
+    let mut errors = Vec::new();
+
+    for dev in devices.iter().filter(|d| !locked.contains(&d.id)) {
+        let level = if dimmed.contains(monitors::gdi_device_name(&dev.device_name)) { level } else { 0 };
+        let dim = dim_state.entry(dev.device_name.clone()).or_default();
+        let result = if level > 0 {
+            dev.dim(level, dim.backend, tx).await
+        } else {
+            dev.clear_dim(dim.backend, tx).await
+        };
+        match result {
+            Ok(()) => dim.level = level,
+            Err(e) => errors.push(format!("{}: {}", dev.device_name, e)),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors.join(", "))
+    }
+}
+
+/// launch at login, minimized to the tray
+#[tauri::command]
+pub async fn set_autostart(
+    enabled: bool,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), String> {
+    autostart::set_enabled(enabled).map_err(|e| e.to_string())?;
+    let mut settings = state.settings.lock().await;
+    settings.autostart = enabled;
+    settings.save().map_err(|e| e.to_string())
+}
+
+/// whether the login entry is there, it can be removed outside of fade
+#[tauri::command]
+pub async fn get_autostart() -> Result<bool, String> {
+    autostart::is_enabled().map_err(|e| e.to_string())
+}
+
+/// pause/resume watcher broadcasts around a bulk operation driven by the frontend.
+/// a freeze that isn't resumed ends on its own after `MANUAL_FREEZE_MAX`
+#[tauri::command]
+pub async fn freeze_broadcasts(
+    frozen: bool,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), String> {
+    state.broadcast_freeze.set_manual(frozen);
+    debug!("broadcasts {}", if frozen { "frozen" } else { "resumed" });
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_dim_backend(
+    device_name: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<DimBackend, String> {
+    let devices = state.monitor_device.lock().await;
+    if !devices.iter().any(|d| d.device_name == device_name) {
+        return Err(format!("device not found: {}", device_name));
+    }
+
+    Ok(state.dim_state.lock().await
+        .get(&device_name)
+        .map(|dim| dim.backend)
+        .unwrap_or_default())
+}
+
+/// switch the dim backend live, the old backend's effect is cleared
+/// before the current dim level is applied with the new one
+#[tauri::command]
+pub async fn set_dim_backend(
+    device_name: String,
+    backend: DimBackend,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), String> {
+    let devices = state.monitor_device.lock().await;
+    let overlay_tx = state.overlay_tx.lock().await;
+
+    let tx = match overlay_tx.as_ref() {
+        Some(tx) => tx,
+        None => return Err("overlay channel not initialized".to_string()),
+    };
+
+    let dev = match devices.iter().find(|d| d.device_name == device_name) {
+        Some(dev) => dev,
+        None => return Err(format!("device not found: {}", device_name)),
+    };
+
+    let mut dim_state = state.dim_state.lock().await;
+    let dim = dim_state.entry(device_name.clone()).or_default();
+    switch_dim_backend(dev, dim, backend, tx).await.map_err(|e| e.to_string())
+}
+
+/// moves the monitor's dim over to `backend` at the same level
+async fn switch_dim_backend(
+    dev: &MonitorDeviceImpl,
+    dim: &mut DimState,
+    backend: DimBackend,
+    tx: &Sender<Overlay>,
+) -> anyhow::Result<()> {
+    if dim.backend == backend {
+        return Ok(());
+    }
+
+    dev.clear_dim(dim.backend, tx).await?;
+    if dim.level > 0 {
+        dev.dim(dim.level, backend, tx).await?;
+    }
+
+    info!("dim backend for device '{}' switched: {:?} -> {:?}", dev.device_name, dim.backend, backend);
+    dim.backend = backend;
+    Ok(())
+}
+
+/// opt a monitor in or out of moving its overlay dim to gamma under fullscreen apps
+#[tauri::command]
+pub async fn set_fullscreen_gamma(
+    id: String,
+    enabled: bool,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), String> {
+    if !state.monitor_device.lock().await.iter().any(|d| d.id == id) {
+        return Err(format!("device not found: {}", id));
+    }
+    let mut settings = state.settings.lock().await;
+    if enabled {
+        settings.fullscreen_gamma.insert(id);
+    } else {
+        settings.fullscreen_gamma.remove(&id);
+    }
+    settings.save().map_err(|e| e.to_string())
+}
+
+/// looks for running programs & os features known to overwrite fade's gamma/brightness
+#[tauri::command]
+pub async fn check_conflicts(
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<Conflict>, String> {
+    let extra = state.settings.lock().await.conflict_rules.clone();
+    let conflicts = tauri::async_runtime::spawn_blocking(move || conflicts::detect(&extra))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())?;
+
+    if !conflicts.is_empty() {
+        warn!("conflicting software detected: {:?}", conflicts.iter().map(|c| &c.name).collect::<Vec<_>>());
+    }
+    Ok(conflicts)
+}
+
+/// applies & persists a color temperature, returns the kelvin actually applied.
+/// gamma changes ease over `duration_ms`, `color_temp_transition_ms` when not given
+#[tauri::command]
+pub async fn set_color_temperature(
+    device_name: String,
+    kelvin: u32,
+    mechanism: ColorTempMechanism,
+    duration_ms: Option<u64>,
+    state: tauri::State<'_, AppState>,
+) -> Result<u32, String> {
+    let duration = Duration::from_millis(
+        duration_ms.unwrap_or(state.settings.lock().await.color_temp_transition_ms)
+    );
+    let applied = apply_color_temperature(&state, &device_name, ColorTemp { mechanism, kelvin }, duration).await?;
+    Ok(applied.kelvin)
+}
+
+/// night light style warmth, 0% neutral up to 100% at `gamma::MIN_KELVIN`. it's the gamma
+/// color temperature under another scale, so it composes with the gamma dim the same way.
+/// returns the warmth applied
+#[tauri::command]
+pub async fn set_warmth(
+    device_name: String,
+    percent: u8,
+    state: tauri::State<'_, AppState>,
+) -> Result<u8, String> {
+    if percent > 100 {
+        return Err(format!("warmth out of range 0..=100: {}", percent));
+    }
+    let duration = Duration::from_millis(state.settings.lock().await.color_temp_transition_ms);
+    let temp = ColorTemp { mechanism: ColorTempMechanism::Gamma, kelvin: gamma::warmth_to_kelvin(percent) };
+    let applied = apply_color_temperature(&state, &device_name, temp, duration).await?;
+
+    let devices = state.monitor_device.lock().await;
+    broadcast_devices(&state, &devices).await;
+    Ok(gamma::kelvin_to_warmth(applied.kelvin))
+}
+
+/// applies & persists `temp` on the device, a gamma one is only persisted while night light is on
+async fn apply_color_temperature(
+    state: &AppState,
+    device_name: &str,
+    temp: ColorTemp,
+    duration: Duration,
+) -> Result<ColorTemp, String> {
+    let (id, applied) = {
+        let devices = state.monitor_device.lock().await;
+        let dev = devices.iter()
+            .find(|d| d.device_name == device_name)
+            .ok_or_else(|| format!("device not found: {}", device_name))?;
+        let applied = if temp.mechanism == ColorTempMechanism::Gamma && DEFERRING_TO_NIGHT_LIGHT.load(Ordering::Relaxed) {
+            info!("night light is on, saving color temperature for '{}' without applying it", dev.friendly_name);
+            temp
+        } else {
+            dev.transition_color_temperature(temp, duration).map_err(|e| e.to_string())?
+        };
+        (dev.id.clone(), applied)
+    };
+
+    let mut settings = state.settings.lock().await;
+    settings.color_temps.insert(id, applied);
+    settings.save().map_err(|e| e.to_string())?;
+    Ok(applied)
+}
+
+#[tauri::command]
+pub async fn get_color_temperature(
+    device_name: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<Option<ColorTemp>, String> {
+    let id = state.monitor_device.lock().await
+        .iter()
+        .find(|d| d.device_name == device_name)
+        .map(|d| d.id.clone())
+        .ok_or_else(|| format!("device not found: {}", device_name))?;
+    Ok(state.settings.lock().await.color_temps.get(&id).copied())
+}
+
+/// overlay color of a single monitor, `None` falls back to the global `overlay_color`
+#[tauri::command]
+pub async fn set_overlay_color(
+    device_name: String,
+    color: Option<[u8; 3]>,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), String> {
+    let id = state.monitor_device.lock().await
+        .iter()
+        .find(|d| d.device_name == device_name)
+        .map(|d| d.id.clone())
+        .ok_or_else(|| format!("device not found: {}", device_name))?;
+
+    let color = {
+        let mut settings = state.settings.lock().await;
+        match color {
+            Some(color) => settings.overlay_colors.insert(id.clone(), color),
+            None => settings.overlay_colors.remove(&id),
+        };
+        settings.save().map_err(|e| e.to_string())?;
+        settings.overlay_color_for(&id)
+    };
+
+    send_overlay_color(&state, &device_name, color).await;
+    Ok(())
+}
+
+/// `false` makes the monitor's dim overlay block mouse input, see `OverlayControl::ClickThrough`
+#[tauri::command]
+pub async fn set_overlay_click_through(
+    device_name: String,
+    enabled: bool,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), String> {
+    if !state.monitor_device.lock().await.iter().any(|d| d.device_name == device_name) {
+        return Err(format!("device not found: {}", device_name));
+    }
+    let tx = state.overlay_ctl_tx.lock().await.clone()
+        .ok_or("overlay channel not initialized")?;
+    tx.send(OverlayControl::ClickThrough { device_name: Some(device_name), enabled }).await
+        .map_err(|e| e.to_string())
+}
+
+/// monitors for the "manage" view, `include_inactive` also lists disabled ones flagged `active: false`
+#[tauri::command]
+pub async fn list_all_monitors(include_inactive: bool) -> Result<Vec<DisplayEntry>, String> {
+    let entries = monitors::list_all_monitors().map_err(|e| e.to_string())?;
+    Ok(entries.into_iter().filter(|e| include_inactive || e.active).collect())
+}
+
+/// sets brightness to a luminance target, the monitor's max luminance comes
+/// from the settings (calibrated) or else from its edid
+#[tauri::command]
+pub async fn set_brightness_nits(
+    device_name: String,
+    nits: f64,
+    state: tauri::State<'_, AppState>,
+) -> Result<u32, String> {
+    if !nits.is_finite() || nits < 0.0 {
+        return Err(format!("nits must be a positive number, got {}", nits));
+    }
+
+    let id = state.monitor_device.lock().await
+        .iter()
+        .find(|d| d.device_name == device_name)
+        .map(|d| d.id.clone())
+        .ok_or_else(|| format!("device not found: {}", device_name))?;
+    let max_nits = max_luminance(&state, &id, &device_name).await?;
+
+    let percentage = edid::nits_to_percentage(nits, max_nits);
+    debug!("{} nits of {} max -> {}% for '{}'", nits, max_nits, percentage, device_name);
+    let command = CommandEnvelope::SetBrightness { device_name, brightness: percentage as i64 };
+    execute_command(&state, command).await.map_err(|e| e.to_string())?;
+    Ok(percentage)
+}
+
+/// calibrated in the settings, or else from the edid
+async fn max_luminance(state: &AppState, id: &str, device_name: &str) -> Result<f64, String> {
+    let calibrated = state.settings.lock().await.max_nits.get(id).copied().filter(|n| *n > 0.0);
+    match calibrated {
+        Some(max_nits) => Ok(max_nits),
+        None => edid::read_edid(id)
+            .ok()
+            .and_then(|edid| edid::max_luminance(&edid))
+            .ok_or_else(|| format!(
+                "max luminance of '{}' is unknown, calibrate it by setting `max_nits` for this monitor",
+                device_name
+            )),
+    }
+}
+
+/// like `set_brightness_nits`, but written straight to the luminance vcp code of a monitor
+/// whose ddc/ci capabilities advertise it. returns the raw vcp value written
+#[tauri::command]
+pub async fn set_nits(
+    device_name: String,
+    nits: u32,
+    state: tauri::State<'_, AppState>,
+) -> Result<u32, String> {
+    let devices = state.monitor_device.lock().await.clone();
+    let dev = resolve_device(&state, &devices, &device_name).await.map_err(|e| e.to_string())?;
+    ensure_unlocked(&state, dev).await.map_err(|e| e.to_string())?;
+    let max_nits = max_luminance(&state, &dev.id, &device_name).await?;
+
+    let value = tauri::async_runtime::spawn_blocking({
+        let dev = dev.clone();
+        move || dev.set_nits(nits, max_nits)
+    }).await.map_err(|e| e.to_string())?.map_err(|e| {
+        error!("failed to set {} nits on '{}': {:?}", nits, device_name, e);
+        e.to_string()
+    })?;
+    let dim_level = state.dim_state.lock().await.get(&dev.device_name).map_or(0, |d| d.level);
+    remember_levels(&state, &dev.id, dev.last_written.get(), dim_level).await;
+    broadcast_devices(&state, &devices).await;
+    Ok(value)
+}
+
+/// comfort preset on every managed monitor, see `reading.rs`
+#[tauri::command]
+pub async fn reading_mode(
+    on: bool,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), String> {
+    set_reading_mode(&state, on).await;
+    Ok(())
+}
+
+pub async fn set_reading_mode(state: &AppState, on: bool) {
+    let freeze = state.broadcast_freeze.hold();
+    let devices = state.monitor_device.lock().await;
+    let targets: Vec<&MonitorDeviceImpl> = devices.iter().collect();
+
+    state.settings.lock().await.reading_mode_enabled = on;
+    if on {
+        reading::apply(state, &targets).await;
+    } else {
+        reading::restore(state, &targets).await;
+    }
+
+    info!("reading mode {}", if on { "enabled" } else { "disabled" });
+    drop(freeze);
+    broadcast_devices(state, &devices).await;
+}
+
+/// covers every monitor & blocks input until dismissed, ctrl + alt + p always dismisses it
+#[tauri::command]
+pub async fn privacy_screen(
+    on: bool,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), String> {
+    let ctl_tx = state.overlay_ctl_tx.lock().await;
+    let tx = ctl_tx.as_ref().ok_or_else(|| "overlay channel not initialized".to_string())?;
+    tx.send(OverlayControl::Privacy(on)).await.map_err(|e| e.to_string())
+}
+
+/// protects a monitor's brightness & dim from the ui, websocket clients & automatic features
+#[tauri::command]
+pub async fn lock_monitor(
+    id: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), String> {
+    set_locked(&state, id, true, None).await
+}
+
+/// `pin` must match `lock_pin` when one is configured
+#[tauri::command]
+pub async fn unlock_monitor(
+    id: String,
+    pin: Option<String>,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), String> {
+    set_locked(&state, id, false, pin).await
+}
+
+async fn set_locked(state: &AppState, id: String, locked: bool, pin: Option<String>) -> Result<(), String> {
+    let devices = state.monitor_device.lock().await;
+    if !devices.iter().any(|d| d.id == id) {
+        return Err(format!("device not found: {}", id));
+    }
+
+    {
+        let mut settings = state.settings.lock().await;
+        if locked {
+            settings.locked.insert(id.clone());
+        } else {
+            if settings.lock_pin.is_some() && settings.lock_pin != pin {
+                warn!("unlock refused for '{}', wrong pin", id);
+                return Err("wrong pin".to_string());
+            }
+            settings.locked.remove(&id);
+        }
+        settings.save().map_err(|e| e.to_string())?;
+    }
+
+    info!("monitor '{}' {}", id, if locked { "locked" } else { "unlocked" });
+    broadcast_devices(state, &devices).await;
+    Ok(())
+}
+
+/// monitor specific settings of the connected monitors as a base64 string, see `share.rs`
+#[tauri::command]
+pub async fn export_settings(state: tauri::State<'_, AppState>) -> Result<String, String> {
+    let devices = state.monitor_device.lock().await;
+    let settings = state.settings.lock().await;
+    SharedSettings::collect(&settings, &devices).encode().map_err(|e| e.to_string())
+}
+
+/// applies an `export_settings` string to the matching monitors, see `share.rs`.
+/// returns the names of the monitors it matched
+#[tauri::command]
+pub async fn import_settings(
+    blob: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<String>, String> {
+    let shared = SharedSettings::decode(&blob).map_err(|e| e.to_string())?;
+    let devices = state.monitor_device.lock().await;
+
+    let (matched, colors) = {
+        let mut settings = state.settings.lock().await;
+        let matched = shared.apply(&mut settings, &devices);
+        monitors::set_min_brightness(settings.min_brightness.clone());
+        settings.save().map_err(|e| e.to_string())?;
+        let colors: Vec<(String, [u8; 3])> = devices.iter()
+            .map(|d| (d.device_name.clone(), settings.overlay_color_for(&d.id)))
+            .collect();
+        (matched, colors)
+    };
+    if matched.is_empty() {
+        warn!("imported settings matched none of the connected monitors");
+    }
+    info!("imported settings, matched monitors: {:?}", matched);
+
+    let targets: Vec<&MonitorDeviceImpl> = devices.iter().collect();
+    restore_color_temps(&state, &targets).await;
+    for (device_name, color) in colors {
+        send_overlay_color(&state, &device_name, color).await;
+    }
+
+    Ok(matched)
+}
+
+/// changes a mock monitor's brightness as if done on the monitor itself,
+/// the brightness watcher picks it up & broadcasts it like any external change
+#[tauri::command]
+pub async fn simulate_brightness_change(id: String, brightness: u32) -> Result<(), String> {
+    mock::simulate_external_change(&id, brightness).map_err(|e| e.to_string())
+}
+
+/// `(unix ms, brightness)` samples of the last `window_secs`, empty unless `record_history` is on
+#[tauri::command]
+pub async fn brightness_history(
+    device_name: String,
+    window_secs: u64,
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<history::Sample>, String> {
+    let id = state.monitor_device.lock().await
+        .iter()
+        .find(|d| d.device_name == device_name)
+        .map(|d| d.id.clone())
+        .ok_or_else(|| format!("device not found: {}", device_name))?;
+    Ok(state.history.lock().await.window(&id, window_secs))
+}
+
+/// slider values per `device_name` in one call, the monitors are written concurrently.
+/// every entry gets its own result, an unknown or failing device doesn't stop the others
+#[tauri::command]
+pub async fn set_brightness_map(
+    map: HashMap<String, i32>,
+    state: tauri::State<'_, AppState>,
+) -> Result<HashMap<String, Result<(), CommandError>>, String> {
+    apply_brightness_map(&state, map).await
+}
+
+/// the same slider value on every connected monitor, concurrently like `set_brightness_map`.
+/// returns one message per monitor that failed, empty when all of them took it
+#[tauri::command]
+pub async fn set_brightness_all(
+    value: i32,
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<String>, String> {
+    let map = state.monitor_device.lock().await
+        .iter()
+        .map(|d| (d.device_name.clone(), value))
+        .collect();
+    let results = apply_brightness_map(&state, map).await?;
+    Ok(results.into_iter()
+        .filter_map(|(device_name, result)| result.err().map(|e| format!("{}: {}", device_name, e)))
+        .collect())
+}
+
+async fn apply_brightness_map(
+    state: &AppState,
+    map: HashMap<String, i32>,
+) -> Result<HashMap<String, Result<(), CommandError>>, String> {
+    // one snapshot once every monitor is written, not one per finished write
+    let freeze = state.broadcast_freeze.hold();
+    let devices = state.monitor_device.lock().await.clone();
+    let tx = state.overlay_tx.lock().await.clone()
+        .ok_or_else(|| "overlay channel not initialized".to_string())?;
+
+    let tasks = map.into_iter().map(|(device_name, value)| {
+        let (devices, tx) = (&devices, &tx);
+        async move {
+            let dev = resolve_device(state, devices, &device_name).await;
+            let result = match dev {
+                Ok(dev) => {
+                    let result = apply_map_entry(state, dev.clone(), value, tx).await;
+                    if result.is_ok() && value >= 0 {
+                        set_clones(state, devices, dev, value as u32).await;
+                    }
+                    result
+                }
+                Err(e) => Err(e),
+            };
+            (device_name, result)
+        }
+    });
+    let results: HashMap<_, _> = futures::future::join_all(tasks).await.into_iter().collect();
+
+    let failed = results.values().filter(|r| r.is_err()).count();
+    if failed > 0 {
+        warn!("brightness map applied with {} of {} entries failing", failed, results.len());
+    }
+    drop(freeze);
+    broadcast_devices(state, &devices).await;
+    Ok(results)
+}
+
+async fn apply_map_entry(
+    state: &AppState,
+    dev: MonitorDeviceImpl,
+    value: i32,
+    tx: &Sender<Overlay>,
+) -> Result<(), CommandError> {
+    let device_name = dev.device_name.clone();
+    CommandEnvelope::Slider { device_name: device_name.clone(), value: value as i64 }.validate()?;
+    ensure_unlocked(state, &dev).await?;
+
+    let failed = |e: anyhow::Error| CommandError::new(CommandErrorKind::Failed, e.to_string());
+    let dim = state.dim_state.lock().await.get(&device_name).copied().unwrap_or_default();
+    let (brightness, level) = if value >= 0 {
+        // on the blocking pool, so the monitors are written in parallel
+        dev.set_async(value as u32).await.map_err(failed)?;
+        // back in the brightness range, the dim of either backend goes away like in `execute_command`
+        if dim.level > 0 {
+            dev.clear_dim(dim.backend, tx).await.map_err(failed)?;
+        }
+        (Some(value as u32), 0)
+    } else {
+        let level = slider_to_alpha(value);
+        dev.dim(level, dim.backend, tx).await.map_err(failed)?;
+        (None, level)
+    };
+    state.dim_state.lock().await.entry(device_name).or_default().level = level;
+    remember_levels(state, &dev.id, brightness, level).await;
+    Ok(())
+}
+
+/// whether hardware brightness & overlay dimming work on a monitor, with the reason when they don't
+#[tauri::command]
+pub async fn can_control(
+    device_name: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<ControlCapability, String> {
+    let dev = state.monitor_device.lock().await
+        .iter()
+        .find(|d| d.device_name == device_name)
+        .cloned()
+        .ok_or_else(|| format!("device not found: {}", device_name))?;
+
+    let hardware = tauri::async_runtime::spawn_blocking({
+        let dev = dev.clone();
+        move || dev.probe_hardware_control()
+    })
+        .await
+        .map_err(|e| e.to_string())?;
+    let overlay = overlay::overlay_devices()
+        .iter()
+        .any(|d| d == monitors::gdi_device_name(&dev.device_name));
+
+    let mut reasons = Vec::new();
+    if let Err(reason) = &hardware {
+        reasons.push(reason.clone());
+    }
+    if !overlay {
+        reasons.push("no overlay window covers this monitor".to_string());
+    }
+    Ok(ControlCapability {
+        hardware: hardware.is_ok(),
+        overlay,
+        reason: (!reasons.is_empty()).then(|| reasons.join(", ")),
+    })
+}
+
+/// which backends work per monitor, from the last run unless `rerun` or there's none yet
+#[tauri::command]
+pub async fn self_test(
+    rerun: bool,
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<selftest::SelfTestReport>, String> {
+    let cached = state.self_test.lock().await.clone();
+    if !rerun && !cached.is_empty() {
+        return Ok(cached);
+    }
+    Ok(selftest::run(&state).await)
+}
+
+/// `ws://` url of `/ws/monitors` for clients on this machine,
+/// an unspecified bind address (`0.0.0.0`) is reached through loopback
+#[tauri::command]
+pub async fn ws_url() -> Result<String, String> {
+    let mut addr = *WS_BOUND_ADDR.get().ok_or_else(|| "websocket server isn't bound".to_string())?;
+    if addr.ip().is_unspecified() {
+        addr.set_ip(if addr.is_ipv4() { Ipv4Addr::LOCALHOST.into() } else { Ipv6Addr::LOCALHOST.into() });
+    }
+    Ok(format!("ws://{}/ws/monitors", addr))
+}
+
+#[tauri::command]
+pub async fn ws_status(state: tauri::State<'_, AppState>) -> Result<WsStatus, String> {
+    Ok(state.ws_status.lock().await.clone())
+}
+
+/// tick interval & alpha latency of the overlay loop, to tell whether it keeps up
+#[tauri::command]
+pub async fn overlay_stats() -> Result<overlay::OverlayStats, String> {
+    Ok(overlay::overlay_stats())
+}
+
+/// every monitor back to full brightness without dims or color changes & the settings moved aside,
+/// like a first run. `confirmation` must be `reset`. returns where the settings were backed up
+#[tauri::command]
+pub async fn factory_reset(
+    confirmation: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<Option<String>, String> {
+    if confirmation != reset::CONFIRMATION {
+        return Err(format!("factory reset not confirmed, pass '{}' to confirm", reset::CONFIRMATION));
+    }
+    let backup = reset::factory_reset(&state).await.map_err(|e| e.to_string())?;
+    Ok(backup.map(|p| p.to_string_lossy().to_string()))
+}
+
+/// saves every monitor's brightness, dim & color temperature as profile `name`, see `profiles.rs`.
+/// returns how many monitors went in
+#[tauri::command]
+pub async fn save_profile(
+    name: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<usize, String> {
+    profiles::save(&state, &name).await.map_err(|e| e.to_string())
+}
+
+/// returns one message per connected monitor of the profile that failed, disconnected ones are skipped
+#[tauri::command]
+pub async fn apply_profile(
+    name: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<String>, String> {
+    profiles::apply(&state, &name).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn list_profiles(state: tauri::State<'_, AppState>) -> Result<Vec<String>, String> {
+    Ok(profiles::list(&state).await)
+}
+
+/// level a monitor goes back to unless its change gets confirmed
+#[derive(Debug)]
+pub struct PendingConfirm {
+    brightness: u32,
+    dim_level: u8,
+    revert: task::JoinHandle<()>,
+}
+
+impl PendingConfirm {
+    /// drops the revert, the current level stays
+    pub fn cancel(self) {
+        self.revert.abort();
+    }
+}
+
+/// applies a slider value & reverts it after `timeout_secs` unless `confirm_brightness` is called,
+/// like windows does for resolution changes. another unconfirmed change keeps the original level
+#[tauri::command]
+pub async fn set_brightness_confirm(
+    device_name: String,
+    value: i32,
+    timeout_secs: u64,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), String> {
+    if timeout_secs == 0 {
+        return Err("timeout must be at least 1 second".to_string());
+    }
+
+    // aliases are resolved once, the revert keeps working if the alias changes meanwhile
+    let device_name = {
+        let devices = state.monitor_device.lock().await;
+        resolve_device(&state, &devices, &device_name).await.map_err(|e| e.to_string())?.device_name.clone()
+    };
+
+    let previous = state.pending_confirms.lock().await.remove(&device_name);
+    let (brightness, dim_level) = match previous {
+        Some(pending) => {
+            pending.revert.abort();
+            (pending.brightness, pending.dim_level)
+        }
+        None => {
+            let brightness = state.monitor_device.lock().await
+                .iter()
+                .find(|d| d.device_name == device_name)
+                .ok_or_else(|| format!("device not found: {}", device_name))?
+                .get()
+                .map_err(|e| e.to_string())?;
+            let dim_level = state.dim_state.lock().await.get(&device_name).map_or(0, |d| d.level);
+            (brightness, dim_level)
+        }
+    };
+
+    let command = CommandEnvelope::Slider { device_name: device_name.clone(), value: value as i64 };
+    execute_command(&state, command).await.map_err(|e| e.to_string())?;
+
+    let revert = tokio::spawn({
+        let state = state.inner().clone();
+        let device_name = device_name.clone();
+        async move {
+            sleep(Duration::from_secs(timeout_secs)).await;
+            state.pending_confirms.lock().await.remove(&device_name);
+            revert_brightness(&state, &device_name, brightness, dim_level).await;
+        }
+    });
+    state.pending_confirms.lock().await.insert(device_name, PendingConfirm { brightness, dim_level, revert });
+    Ok(())
+}
+
+async fn revert_brightness(state: &AppState, device_name: &str, brightness: u32, dim_level: u8) {
+    info!("brightness change of '{}' not confirmed, reverting to {} (dim {})", device_name, brightness, dim_level);
+    let commands = [
+        CommandEnvelope::SetDim { device_name: device_name.to_string(), level: dim_level as i64 },
+        CommandEnvelope::SetBrightness { device_name: device_name.to_string(), brightness: brightness as i64 },
+    ];
+    for command in commands {
+        if let Err(e) = execute_command(state, command).await {
+            error!("failed to revert brightness of '{}': {}", device_name, e);
+        }
+    }
+    if let Err(e) = app::app_handle().emit("brightness-reverted", device_name) {
+        warn!("failed to emit brightness revert: {}", e);
+    }
+    let devices = state.monitor_device.lock().await;
+    broadcast_devices(state, &devices).await;
+}
+
+/// keeps unconfirmed changes from `set_brightness_confirm`, every pending one when `device_name` is `None`.
+/// takes an alias like `set_brightness_confirm`, pending reverts are keyed by the resolved device name
+#[tauri::command]
+pub async fn confirm_brightness(
+    device_name: Option<String>,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), String> {
+    let device_name = match device_name {
+        Some(selector) => {
+            let devices = state.monitor_device.lock().await;
+            match resolve_device(&state, &devices, &selector).await {
+                Ok(dev) => Some(dev.device_name.clone()),
+                // disconnected meanwhile, its revert is still pending under the name it had
+                Err(_) if state.pending_confirms.lock().await.contains_key(&selector) => Some(selector),
+                Err(e) => return Err(e.to_string()),
+            }
+        }
+        None => None,
+    };
+
+    let mut pending = state.pending_confirms.lock().await;
+    let confirmed: Vec<(String, PendingConfirm)> = match device_name {
+        Some(device_name) => pending.remove_entry(&device_name).into_iter().collect(),
+        None => pending.drain().collect(),
+    };
+    for (device_name, p) in confirmed {
+        p.cancel();
+        info!("brightness of '{}' confirmed", device_name);
+    }
+    Ok(())
+}
+
+/// brightness the schedule gives at `at` (`HH:MM`, today), nothing is applied.
+/// `None` when no schedule is configured
+#[tauri::command]
+pub async fn preview_schedule(
+    at: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<Option<u32>, String> {
+    let at = schedule::parse_time(&at).map_err(|e| e.to_string())?;
+    let (day_of_year, utc_offset) = schedule::local_clock();
+    let schedule = state.settings.lock().await.schedule.clone();
+    schedule::evaluate(&schedule, at, day_of_year, utc_offset).map_err(|e| e.to_string())
+}
@@ -0,0 +1,247 @@
+/*
+ * gamma ramp based dimming, alternative to the overlay windows
+ * it survives fullscreen exclusive apps where layered windows get hidden
+*/
+use anyhow::anyhow;
+use tokio::time::{sleep, Duration};
+use std::{
+    iter,
+    collections::HashMap,
+    sync::{Mutex, LazyLock},
+    ffi::{c_void, OsStr},
+    os::windows::ffi::OsStrExt,
+};
+use windows::{
+    core::PCWSTR,
+    Win32::{
+        Graphics::Gdi::{CreateDCW, DeleteDC, HDC},
+        UI::ColorSystem::SetDeviceGammaRamp,
+    }
+};
+use crate::monitors::gdi_device_name;
+
+/// windows refuses ramps which are too far from the identity ramp,
+/// anything below this multiplier gets rejected by most drivers
+pub const MIN_GAMMA_MULTIPLIER: f64 = 0.5;
+
+/// neutral white point, no channel gets scaled
+pub const NEUTRAL_KELVIN: u32 = 6500;
+/// warmest temperature the gamma ramp can reach within `MIN_GAMMA_MULTIPLIER`
+pub const MIN_KELVIN: u32 = 3000;
+/// coolest temperature, past it blue can't go up & the other channels only get darker
+pub const MAX_KELVIN: u32 = 10000;
+
+/// `[red, green, blue]` ramp, layout expected by `SetDeviceGammaRamp`
+type GammaRamp = [[u16; 256]; 3];
+
+/// dim & color temperature share the ramp, so both are kept per device
+#[derive(Debug, Clone, Copy)]
+struct GammaState {
+    multiplier: f64,
+    kelvin: u32,
+}
+
+impl Default for GammaState {
+    fn default() -> Self {
+        Self { multiplier: 1.0, kelvin: NEUTRAL_KELVIN }
+    }
+}
+
+static STATE: LazyLock<Mutex<HashMap<String, GammaState>>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// `[r, g, b]` of a black body at `kelvin`, 0.0..=1.0
+/// approximation by tanner helland, good enough for 1000k..40000k
+fn kelvin_to_rgb(kelvin: u32) -> [f64; 3] {
+    let t = kelvin as f64 / 100.0;
+    let red = if t <= 66.0 { 255.0 } else { 329.698727446 * (t - 60.0).powf(-0.1332047592) };
+    let green = if t <= 66.0 {
+        99.4708025861 * t.ln() - 161.1195681661
+    } else {
+        288.1221695283 * (t - 60.0).powf(-0.0755148492)
+    };
+    let blue = if t >= 66.0 {
+        255.0
+    } else if t <= 19.0 {
+        0.0
+    } else {
+        138.5177312231 * (t - 10.0).ln() - 305.0447927307
+    };
+    [red, green, blue].map(|c| (c / 255.0).clamp(0.0, 1.0))
+}
+
+/// `[r, g, b]` multipliers at `kelvin` relative to `NEUTRAL_KELVIN`, which is exactly `[1.0; 3]`
+fn tint(kelvin: u32) -> [f64; 3] {
+    if kelvin == NEUTRAL_KELVIN {
+        return [1.0; 3];
+    }
+    let (rgb, neutral) = (kelvin_to_rgb(kelvin), kelvin_to_rgb(NEUTRAL_KELVIN));
+    [0, 1, 2].map(|i| (rgb[i] / neutral[i]).min(1.0))
+}
+
+/// warmth percentage -> kelvin, 0% is `NEUTRAL_KELVIN` (the identity ramp) & 100% `MIN_KELVIN`
+pub fn warmth_to_kelvin(percent: u8) -> u32 {
+    let fraction = percent.min(100) as f64 / 100.0;
+    (NEUTRAL_KELVIN as f64 - (NEUTRAL_KELVIN - MIN_KELVIN) as f64 * fraction).round() as u32
+}
+
+/// the other way round, anything at or above `NEUTRAL_KELVIN` is 0%
+pub fn kelvin_to_warmth(kelvin: u32) -> u8 {
+    let warmed = NEUTRAL_KELVIN.saturating_sub(clamp_kelvin(kelvin)) as f64;
+    (warmed / (NEUTRAL_KELVIN - MIN_KELVIN) as f64 * 100.0).round() as u8
+}
+
+fn clamp_kelvin(kelvin: u32) -> u32 {
+    kelvin.clamp(MIN_KELVIN, MAX_KELVIN)
+}
+
+/// linear ramp scaled by `multiplier` & tinted to `kelvin`, `(1.0, NEUTRAL_KELVIN)` is the identity ramp
+fn ramp(state: GammaState) -> GammaRamp {
+    let mut ramp = [[0u16; 256]; 3];
+    for (channel, tint) in ramp.iter_mut().zip(tint(state.kelvin)) {
+        let multiplier = (state.multiplier * tint).max(MIN_GAMMA_MULTIPLIER);
+        for (i, value) in channel.iter_mut().enumerate() {
+            *value = (i as f64 * 257.0 * multiplier).round().min(u16::MAX as f64) as u16;
+        }
+    }
+    ramp
+}
+
+/// updates the stored state of the device & writes the resulting ramp
+fn apply(device_name: &str, update: impl FnOnce(&mut GammaState)) -> anyhow::Result<()> {
+    let state = {
+        let mut states = STATE.lock().map_err(|e| anyhow!("gamma state poisoned: {}", e))?;
+        let state = states.entry(gdi_device_name(device_name).to_string()).or_default();
+        update(state);
+        *state
+    };
+    set_ramp(device_name, &ramp(state))
+}
+
+/// opens a device context for the display, runs `f` on it & releases it
+fn with_display_dc<T>(
+    device_name: &str,
+    f: impl FnOnce(HDC) -> anyhow::Result<T>,
+) -> anyhow::Result<T> {
+    let wide: Vec<u16> = OsStr::new(gdi_device_name(device_name))
+        .encode_wide()
+        .chain(iter::once(0))
+        .collect();
+
+    unsafe {
+        let hdc = CreateDCW(PCWSTR::null(), PCWSTR(wide.as_ptr()), PCWSTR::null(), None);
+        if hdc.is_invalid() {
+            return Err(anyhow!("failed to create device context (CreateDCW) for device: {}", device_name));
+        }
+        let result = f(hdc);
+        let _ = DeleteDC(hdc);
+        result
+    }
+}
+
+fn set_ramp(device_name: &str, ramp: &GammaRamp) -> anyhow::Result<()> {
+    with_display_dc(device_name, |hdc| unsafe {
+        SetDeviceGammaRamp(hdc, ramp.as_ptr() as *const c_void)
+            .ok()
+            .map_err(|e|
+                anyhow!(
+                    "failed to set gamma ramp, device: {}, err: {:#?}",
+                    device_name, e
+                ))
+    })
+}
+
+/// dims the display by scaling down its gamma ramp,
+/// `level` has same meaning as overlay alpha: 0 is untouched, 255 is darkest
+pub fn dim_brightness(level: u8, device_name: &str) -> anyhow::Result<()> {
+    let fraction = level as f64 / u8::MAX as f64;
+    apply(device_name, |s| s.multiplier = 1.0 - fraction * (1.0 - MIN_GAMMA_MULTIPLIER))
+}
+
+/// dims the display to `multiplier` of its brightness, clamped to `MIN_GAMMA_MULTIPLIER..=1.0`
+pub fn dim_multiplier(multiplier: f64, device_name: &str) -> anyhow::Result<()> {
+    apply(device_name, |s| s.multiplier = multiplier.clamp(MIN_GAMMA_MULTIPLIER, 1.0))
+}
+
+/// writes the ramp the device should already have, the identity ramp if fade never touched it
+pub fn rewrite(device_name: &str) -> anyhow::Result<()> {
+    apply(device_name, |_| {})
+}
+
+/// removes the gamma dim, the color temperature is kept
+pub fn reset_gamma(device_name: &str) -> anyhow::Result<()> {
+    apply(device_name, |s| s.multiplier = 1.0)
+}
+
+/// tints the ramp to `kelvin`, clamped to `MIN_KELVIN..=MAX_KELVIN`, returns the kelvin applied.
+/// the dim multiplier is kept, `NEUTRAL_KELVIN` removes the tint. cancels a running transition of the device
+pub fn set_color_temperature(kelvin: u32, device_name: &str) -> anyhow::Result<u32> {
+    next_transition(device_name);
+    let kelvin = clamp_kelvin(kelvin);
+    apply(device_name, |s| s.kelvin = kelvin)?;
+    Ok(kelvin)
+}
+
+/// bumped per device by every color temperature change, a transition stops once it's outdated
+static TRANSITIONS: LazyLock<Mutex<HashMap<String, u64>>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+/// a ramp write per step, about 30 fps
+const TRANSITION_STEP: Duration = Duration::from_millis(33);
+
+fn next_transition(device_name: &str) -> u64 {
+    let mut transitions = TRANSITIONS.lock().unwrap_or_else(|e| e.into_inner());
+    let generation = transitions.entry(gdi_device_name(device_name).to_string()).or_default();
+    *generation += 1;
+    *generation
+}
+
+fn is_current_transition(device_name: &str, generation: u64) -> bool {
+    TRANSITIONS.lock()
+        .map(|t| t.get(gdi_device_name(device_name)) == Some(&generation))
+        .unwrap_or(false)
+}
+
+/// smoothstep from the current kelvin to `kelvin` over `duration`, a newer change of the
+/// device stops it where it is. returns the target kelvin, clamped like `set_color_temperature`
+pub async fn transition_color_temperature(
+    kelvin: u32,
+    device_name: &str,
+    duration: Duration,
+) -> anyhow::Result<u32> {
+    let target = clamp_kelvin(kelvin);
+    let generation = next_transition(device_name);
+    let from = STATE.lock()
+        .map_err(|e| anyhow!("gamma state poisoned: {}", e))?
+        .get(gdi_device_name(device_name))
+        .map_or(NEUTRAL_KELVIN, |s| s.kelvin);
+
+    let steps = (duration.as_millis() / TRANSITION_STEP.as_millis()).max(1) as u32;
+    for step in 1..=steps {
+        if !is_current_transition(device_name, generation) {
+            tracing::debug!("color temperature transition of '{}' cancelled", device_name);
+            break;
+        }
+        let t = step as f64 / steps as f64;
+        let eased = t * t * (3.0 - 2.0 * t);
+        let kelvin = from as f64 + (target as f64 - from as f64) * eased;
+        apply(device_name, |s| s.kelvin = kelvin.round() as u32)?;
+        if step < steps {
+            sleep(TRANSITION_STEP).await;
+        }
+    }
+    Ok(target)
+}
+
+/// runs `transition_color_temperature` in the background, returns the kelvin it heads to
+pub fn spawn_color_transition(kelvin: u32, device_name: String, duration: Duration) -> u32 {
+    if duration.is_zero() {
+        if let Err(e) = set_color_temperature(kelvin, &device_name) {
+            tracing::warn!("failed to set color temperature of '{}': {:?}", device_name, e);
+        }
+    } else {
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = transition_color_temperature(kelvin, &device_name, duration).await {
+                tracing::warn!("color temperature transition of '{}' failed: {:?}", device_name, e);
+            }
+        });
+    }
+    clamp_kelvin(kelvin)
+}
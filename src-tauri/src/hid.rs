@@ -0,0 +1,240 @@
+/*
+ * vendor usb hid brightness control, built with the `hid` feature
+ * ADVANCED FALLBACK: only used when the native ddc/ci or ioctl backend fails,
+ * for panels (usb-c, displaylink, ...) which expose brightness over hid only
+*/
+use anyhow::anyhow;
+use std::{
+    slice,
+    collections::HashMap,
+    sync::{Mutex, OnceLock, LazyLock},
+};
+use windows::{
+    core::PCWSTR,
+    Win32::{
+        Devices::{
+            HumanInterfaceDevice::{
+                HidD_GetAttributes, HidD_GetHidGuid, HidD_SetFeature, HIDD_ATTRIBUTES,
+            },
+            DeviceAndDriverInstallation::{
+                SetupDiDestroyDeviceInfoList, SetupDiEnumDeviceInterfaces, SetupDiGetClassDevsW,
+                SetupDiGetDeviceInterfaceDetailW, DIGCF_DEVICEINTERFACE, DIGCF_PRESENT, HDEVINFO,
+                SP_DEVICE_INTERFACE_DATA, SP_DEVICE_INTERFACE_DETAIL_DATA_W,
+            },
+        },
+        Storage::FileSystem::{
+            CreateFileW, FILE_GENERIC_READ, FILE_GENERIC_WRITE, FILE_SHARE_READ, FILE_SHARE_WRITE,
+            OPEN_EXISTING,
+        },
+    }
+};
+use crate::{
+    brightness::BrightnessBackend,
+    monitors::{MonitorDeviceImpl, SafeDisplayHandle},
+    settings::{HidDeviceConfig, HidReportFormat},
+};
+
+/// configured hid devices, set once from the settings on startup
+static HID_DEVICES: OnceLock<Vec<HidDeviceConfig>> = OnceLock::new();
+
+/// hid controls are write only, remember what was written so `get` has an answer
+static LAST_WRITTEN: LazyLock<Mutex<HashMap<String, u32>>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// configs with a broken report layout are dropped with a warning
+pub fn init(devices: Vec<HidDeviceConfig>) {
+    let devices = devices.into_iter()
+        .filter(|d| match d.report.validate() {
+            Ok(()) => true,
+            Err(e) => {
+                tracing::warn!("ignoring hid device for model '{}': {}", d.model, e);
+                false
+            }
+        })
+        .collect();
+    if HID_DEVICES.set(devices).is_err() {
+        tracing::warn!("hid devices were already initialized");
+    }
+}
+
+impl HidReportFormat {
+    /// the value can't overlap the report id or run past a set `length`
+    fn validate(&self) -> anyhow::Result<()> {
+        if self.value_offset == 0 {
+            return Err(anyhow!("value_offset 0 overlaps the report id"));
+        }
+        let end = self.value_offset + self.value_width.clamp(1, 2) as usize;
+        if self.length > 0 && end > self.length {
+            return Err(anyhow!(
+                "value at {}..{} doesn't fit a {} byte report",
+                self.value_offset, end, self.length
+            ));
+        }
+        Ok(())
+    }
+
+    fn build(&self, percentage: u32) -> Vec<u8> {
+        let width = self.value_width.clamp(1, 2) as usize;
+        let mut report = vec![0u8; self.length.max(self.value_offset + width)];
+        report[0] = self.report_id;
+
+        let range = self.max.saturating_sub(self.min) as u32;
+        let value = self.min as u32 + range * percentage.min(100) / 100;
+        let bytes = (value as u16).to_le_bytes();
+        report[self.value_offset..self.value_offset + width].copy_from_slice(&bytes[..width]);
+        report
+    }
+}
+
+/// device interface path (nul terminated) of an enumerated hid interface
+fn interface_path(
+    info_set: HDEVINFO,
+    interface: &SP_DEVICE_INTERFACE_DATA,
+) -> Option<Vec<u16>> {
+    unsafe {
+        let mut required = 0u32;
+        let _ = SetupDiGetDeviceInterfaceDetailW(info_set, interface, None, 0, Some(&mut required as *mut u32), None);
+        if required == 0 {
+            return None;
+        }
+
+        // u32 backed buffer keeps the detail struct aligned
+        let mut buffer = vec![0u32; (required as usize).div_ceil(4)];
+        let detail = buffer.as_mut_ptr() as *mut SP_DEVICE_INTERFACE_DETAIL_DATA_W;
+        (*detail).cbSize = size_of::<SP_DEVICE_INTERFACE_DETAIL_DATA_W>() as u32;
+
+        SetupDiGetDeviceInterfaceDetailW(info_set, interface, Some(detail), required, None, None).ok()?;
+
+        let path_ptr = std::ptr::addr_of!((*detail).DevicePath) as *const u16;
+        let len = (0..).take_while(|&i| *path_ptr.add(i) != 0).count();
+        let mut path = slice::from_raw_parts(path_ptr, len).to_vec();
+        path.push(0);
+        Some(path)
+    }
+}
+
+/// opens the first present hid interface matching vendor/product ids
+fn open_device(vendor_id: u16, product_id: u16) -> anyhow::Result<Option<SafeDisplayHandle>> {
+    unsafe {
+        let guid = HidD_GetHidGuid();
+        let info_set = SetupDiGetClassDevsW(
+            Some(&guid as *const _),
+            PCWSTR::null(),
+            None,
+            DIGCF_PRESENT | DIGCF_DEVICEINTERFACE,
+        )
+        .map_err(|e| anyhow!("failed to enumerate hid devices, err: {:#?}", e))?;
+
+        let mut found = None;
+        for index in 0.. {
+            let mut interface = SP_DEVICE_INTERFACE_DATA {
+                cbSize: size_of::<SP_DEVICE_INTERFACE_DATA>() as u32,
+                ..Default::default()
+            };
+            if SetupDiEnumDeviceInterfaces(info_set, None, &guid, index, &mut interface).is_err() {
+                break;
+            }
+
+            let Some(path) = interface_path(info_set, &interface) else {
+                continue;
+            };
+
+            let handle = match CreateFileW(
+                PCWSTR(path.as_ptr()),
+                (FILE_GENERIC_READ | FILE_GENERIC_WRITE).0,
+                FILE_SHARE_READ | FILE_SHARE_WRITE,
+                None,
+                OPEN_EXISTING,
+                Default::default(),
+                None,
+            ) {
+                Ok(h) if !h.is_invalid() => SafeDisplayHandle(h),
+                _ => continue, // keyboards & mice are opened exclusively by the os
+            };
+
+            let mut attributes = HIDD_ATTRIBUTES {
+                Size: size_of::<HIDD_ATTRIBUTES>() as u32,
+                ..Default::default()
+            };
+            if HidD_GetAttributes(handle.0, &mut attributes)
+                && attributes.VendorID == vendor_id
+                && attributes.ProductID == product_id
+            {
+                found = Some(handle);
+                break;
+            }
+        }
+
+        let _ = SetupDiDestroyDeviceInfoList(info_set);
+        Ok(found)
+    }
+}
+
+fn config_for(device: &MonitorDeviceImpl) -> anyhow::Result<&'static HidDeviceConfig> {
+    HID_DEVICES
+        .get()
+        .and_then(|devices| devices.iter().find(|d| d.model == device.friendly_name))
+        .ok_or_else(|| anyhow!("no hid device configured for model: {}", device.friendly_name))
+}
+
+pub struct HidBackend;
+
+impl BrightnessBackend for HidBackend {
+    fn get(&self, device: &MonitorDeviceImpl) -> anyhow::Result<u32> {
+        config_for(device)?;
+        LAST_WRITTEN
+            .lock()
+            .map_err(|e| anyhow!("hid cache poisoned: {}", e))?
+            .get(&device.id)
+            .copied()
+            .ok_or_else(|| anyhow!("hid brightness unknown until first write, device: {}", device.friendly_name))
+    }
+
+    fn set(&self, device: &MonitorDeviceImpl, percentage: u32) -> anyhow::Result<()> {
+        let config = config_for(device)?;
+        let handle = open_device(config.vendor_id, config.product_id)?
+            .ok_or_else(|| anyhow!(
+                "hid device {:04x}:{:04x} not present for model: {}",
+                config.vendor_id, config.product_id, device.friendly_name
+            ))?;
+
+        let report = config.report.build(percentage);
+        unsafe {
+            if !HidD_SetFeature(handle.0, report.as_ptr() as *const _, report.len() as u32) {
+                return Err(anyhow!(
+                    "failed to send hid brightness report, device: {}, report: {:02x?}",
+                    device.friendly_name, report
+                ));
+            }
+        }
+
+        if let Ok(mut last) = LAST_WRITTEN.lock() {
+            last.insert(device.id.clone(), percentage);
+        }
+        tracing::debug!("hid brightness set to {}% for '{}'", percentage, device.friendly_name);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(length: usize, value_offset: usize, value_width: u8) -> HidReportFormat {
+        HidReportFormat { report_id: 0x05, length, value_offset, value_width, min: 0, max: 1000 }
+    }
+
+    #[test]
+    fn validate_report_layout() {
+        assert!(format(3, 1, 2).validate().is_ok());
+        assert!(format(0, 1, 2).validate().is_ok());
+        assert!(format(8, 0, 1).validate().is_err());
+        assert!(format(2, 1, 2).validate().is_err());
+        assert!(format(4, 4, 1).validate().is_err());
+    }
+
+    #[test]
+    fn build_scales_into_the_raw_range() {
+        assert_eq!(format(4, 1, 2).build(50), vec![0x05, 0xf4, 0x01, 0x00]);
+        assert_eq!(format(0, 1, 2).build(100), vec![0x05, 0xe8, 0x03]);
+    }
+}
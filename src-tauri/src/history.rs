@@ -0,0 +1,86 @@
+/*
+ * opt-in brightness history, fed by the `brightness_changes` watcher.
+ * only changes are recorded, a value holds until the next sample, so a full day fits in a few hundred entries
+*/
+use std::{
+    fs::OpenOptions,
+    io::Write,
+    path::PathBuf,
+    collections::{HashMap, VecDeque},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tauri::Manager;
+use crate::{app, monitors::MonitorInfo};
+
+const HISTORY_FILE: &str = "brightness_history.csv";
+
+/// `(unix timestamp in ms, brightness)`
+pub type Sample = (u64, u32);
+
+/// ring buffer of samples per monitor `id`
+#[derive(Debug, Default)]
+pub struct BrightnessHistory {
+    samples: HashMap<String, VecDeque<Sample>>,
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+fn file_path() -> anyhow::Result<PathBuf> {
+    Ok(app::app_handle()
+        .path()
+        .app_local_data_dir()?
+        .join(HISTORY_FILE))
+}
+
+impl BrightnessHistory {
+    /// appends the monitors whose brightness differs from their last sample,
+    /// the oldest ones are dropped past `capacity`. returns what was appended
+    pub fn record(&mut self, infos: &[MonitorInfo], capacity: usize) -> Vec<(String, Sample)> {
+        let now = now_ms();
+        let mut appended = Vec::new();
+        for info in infos {
+            let samples = self.samples.entry(info.id.clone()).or_default();
+            if samples.back().is_some_and(|&(_, last)| last == info.brightness) {
+                continue;
+            }
+            while samples.len() >= capacity.max(1) {
+                samples.pop_front();
+            }
+            samples.push_back((now, info.brightness));
+            appended.push((info.id.clone(), (now, info.brightness)));
+        }
+        appended
+    }
+
+    /// samples within the last `window_secs`, plus the one before so the start of the window has a value
+    pub fn window(&self, id: &str, window_secs: u64) -> Vec<Sample> {
+        let Some(samples) = self.samples.get(id) else {
+            return Vec::new();
+        };
+        let since = now_ms().saturating_sub(window_secs.saturating_mul(1000));
+        let start = samples.iter()
+            .rposition(|&(ts, _)| ts < since)
+            .unwrap_or(0);
+        samples.iter().skip(start).copied().collect()
+    }
+}
+
+/// appends `timestamp,id,brightness` lines next to the settings file
+pub fn append_to_file(samples: &[(String, Sample)]) -> anyhow::Result<()> {
+    if samples.is_empty() {
+        return Ok(());
+    }
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(file_path()?)?;
+    for (id, (ts, brightness)) in samples {
+        writeln!(file, "{},{},{}", ts, id, brightness)?;
+    }
+    Ok(())
+}
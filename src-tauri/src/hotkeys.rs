@@ -0,0 +1,146 @@
+/*
+ * global shortcuts for the monitor under the cursor. registered with `RegisterHotKey` on their
+ * own thread like the tray scroll hook, presses are handled on the async side
+*/
+use anyhow::{anyhow, bail};
+use tracing::{debug, info, warn};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
+use windows::Win32::UI::{
+    Input::KeyboardAndMouse::{
+        RegisterHotKey, UnregisterHotKey, HOT_KEY_MODIFIERS, MOD_ALT, MOD_CONTROL, MOD_NOREPEAT, MOD_SHIFT,
+        MOD_WIN, VK_DOWN, VK_END, VK_F1, VK_HOME, VK_LEFT, VK_NEXT, VK_PRIOR, VK_RIGHT, VK_SPACE, VK_UP,
+    },
+    WindowsAndMessaging::{GetMessageW, MSG, WM_HOTKEY},
+};
+use crate::{
+    events,
+    app::AppState,
+    settings::Hotkeys,
+    protocol::CommandEnvelope,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HotkeyAction {
+    BrightnessUp,
+    BrightnessDown,
+    ToggleDim,
+}
+
+/// `ctrl+alt+up` -> modifiers & virtual key, case & spaces don't matter
+fn parse(combo: &str) -> anyhow::Result<(HOT_KEY_MODIFIERS, u32)> {
+    let mut modifiers = HOT_KEY_MODIFIERS(0);
+    let mut key = None;
+    for part in combo.split('+').map(|p| p.trim().to_lowercase()) {
+        let vk = match part.as_str() {
+            "ctrl" | "control" => { modifiers |= MOD_CONTROL; continue; }
+            "alt" => { modifiers |= MOD_ALT; continue; }
+            "shift" => { modifiers |= MOD_SHIFT; continue; }
+            "win" | "super" => { modifiers |= MOD_WIN; continue; }
+            "up" => VK_UP.0 as u32,
+            "down" => VK_DOWN.0 as u32,
+            "left" => VK_LEFT.0 as u32,
+            "right" => VK_RIGHT.0 as u32,
+            "pageup" => VK_PRIOR.0 as u32,
+            "pagedown" => VK_NEXT.0 as u32,
+            "home" => VK_HOME.0 as u32,
+            "end" => VK_END.0 as u32,
+            "space" => VK_SPACE.0 as u32,
+            f if f.len() > 1 && f.starts_with('f') && f[1..].parse::<u32>().is_ok_and(|n| (1..=24).contains(&n)) => {
+                VK_F1.0 as u32 + f[1..].parse::<u32>()? - 1
+            }
+            c if c.len() == 1 && c.chars().all(|c| c.is_ascii_alphanumeric()) => {
+                c.to_ascii_uppercase().as_bytes()[0] as u32
+            }
+            other => bail!("unknown key '{}' in '{}'", other, combo),
+        };
+        if key.replace(vk).is_some() {
+            bail!("more than one key in '{}'", combo);
+        }
+    }
+    let key = key.ok_or_else(|| anyhow!("no key in '{}'", combo))?;
+    if modifiers.0 == 0 {
+        bail!("'{}' needs a modifier, a bare key would be taken from every app", combo);
+    }
+    Ok((modifiers, key))
+}
+
+/// registers the configured shortcuts on their own thread, presses come out of the receiver.
+/// a combo which doesn't parse or is taken by another app is skipped with a warning
+pub fn init(config: &Hotkeys) -> UnboundedReceiver<HotkeyAction> {
+    let (tx, rx) = unbounded_channel();
+    // the index is the `WM_HOTKEY` id, ids are per thread so they can't clash with the privacy screen's
+    let bindings = [
+        (HotkeyAction::BrightnessUp, config.brightness_up.clone()),
+        (HotkeyAction::BrightnessDown, config.brightness_down.clone()),
+        (HotkeyAction::ToggleDim, config.toggle_dim.clone()),
+    ];
+
+    std::thread::spawn(move || unsafe {
+        let mut registered = Vec::new();
+        for (id, (action, combo)) in bindings.iter().enumerate() {
+            let Some(combo) = combo else {
+                continue;
+            };
+            let result = parse(combo).and_then(|(modifiers, key)| {
+                RegisterHotKey(None, id as i32, modifiers | MOD_NOREPEAT, key)
+                    .map_err(|e| anyhow!("already taken: {:?}", e))
+            });
+            match result {
+                Ok(()) => {
+                    info!("hotkey '{}' registered for {:?}", combo, action);
+                    registered.push(id as i32);
+                }
+                Err(e) => warn!("hotkey '{}' for {:?} unavailable: {:?}", combo, action, e),
+            }
+        }
+        if registered.is_empty() {
+            return;
+        }
+
+        let mut msg = MSG::default();
+        while GetMessageW(&mut msg, None, 0, 0).as_bool() {
+            if msg.message != WM_HOTKEY {
+                continue;
+            }
+            if let Some((action, _)) = bindings.get(msg.wParam.0) {
+                let _ = tx.send(*action);
+            }
+        }
+        for id in registered {
+            let _ = UnregisterHotKey(None, id);
+        }
+    });
+
+    rx
+}
+
+/// runs the pressed shortcuts against the monitor under the cursor
+pub async fn handle_hotkeys(state: AppState, mut rx: UnboundedReceiver<HotkeyAction>) {
+    while let Some(action) = rx.recv().await {
+        let device_name = match events::cursor_device_name(&state).await {
+            Ok(device_name) => device_name,
+            Err(e) => {
+                debug!("hotkey {:?} ignored: {}", action, e);
+                continue;
+            }
+        };
+        let (step, dim_level) = {
+            let settings = state.settings.lock().await;
+            (settings.hotkeys.step.min(100) as i32, settings.hotkeys.dim_level)
+        };
+
+        let result = match action {
+            // clamped to 0..=100 & snapped to the supported levels of internal displays by `set`
+            HotkeyAction::BrightnessUp => events::step_brightness(&state, &device_name, step).await.map(|_| ()),
+            HotkeyAction::BrightnessDown => events::step_brightness(&state, &device_name, -step).await.map(|_| ()),
+            HotkeyAction::ToggleDim => {
+                let dimmed = state.dim_state.lock().await.get(&device_name).is_some_and(|d| d.level > 0);
+                let level = if dimmed { 0 } else { dim_level as i64 };
+                events::execute_command(&state, CommandEnvelope::SetDim { device_name: device_name.clone(), level }).await
+            }
+        };
+        if let Err(e) = result {
+            warn!("hotkey {:?} failed for '{}': {}", action, device_name, e);
+        }
+    }
+}
@@ -0,0 +1,33 @@
+/*
+ * one tray app per session: a named mutex marks the running instance, a second launch asks it
+ * to show its window over the http api & exits instead of fighting over the websocket port.
+ * cli invocations never get here, they're clients of the running instance anyway
+*/
+use windows::{
+    core::w,
+    Win32::{
+        Foundation::{GetLastError, ERROR_ALREADY_EXISTS},
+        System::Threading::CreateMutexW,
+    },
+};
+use crate::{cli, events};
+
+/// the running instance's window pops up on a second launch
+pub const SHOW_PATH: &str = "/show";
+
+/// `true` for the first instance of the session. the mutex handle is left open on purpose,
+/// the os releases it with the process
+pub fn acquire() -> bool {
+    match unsafe { CreateMutexW(None, false, w!(r"Local\fade-single-instance")) } {
+        Ok(_) => unsafe { GetLastError() != ERROR_ALREADY_EXISTS },
+        // can't tell, better two instances than none
+        Err(_) => true,
+    }
+}
+
+/// best effort, the running instance may still be starting its server
+pub fn show_running() {
+    if let Ok(addr) = events::ws_addr(None) {
+        let _ = cli::request(addr, "POST", SHOW_PATH, None);
+    }
+}
@@ -6,6 +6,8 @@ use tauri::{App, Manager};
 use tracing_appender::non_blocking::WorkerGuard;
 use tracing_subscriber::{prelude::*, EnvFilter, fmt};
 
+pub const LOG_FILE: &str = "fade.log";
+
 pub fn init_logging(app: &App) -> Result<WorkerGuard> {
     let resolver = app.path();
     let app_data_local = resolver
@@ -15,7 +17,7 @@ pub fn init_logging(app: &App) -> Result<WorkerGuard> {
         fs::create_dir_all(&app_data_local)?;
     }
 
-    let log_path = app_data_local.join("fade.log");
+    let log_path = app_data_local.join(LOG_FILE);
 
     let env_filter = EnvFilter::try_from_default_env()
         .unwrap_or_else(|_| EnvFilter::new("info"));
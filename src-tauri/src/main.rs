@@ -2,13 +2,45 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 mod app;
+mod autostart;
+mod cli;
+mod instance;
 mod log;
 mod utils;
+mod gamma;
+mod edid;
+mod settings;
 mod events;
+mod protocol;
 mod overlay;
+mod osd;
+mod privacy;
+mod tray;
 mod monitors;
 mod brightness;
+mod error;
+mod conflicts;
+mod reading;
+mod schedule;
+mod selftest;
+mod history;
+mod mock;
+mod wmi;
+mod share;
+mod buttons;
+mod reset;
+mod hotkeys;
+mod profiles;
+#[cfg(feature = "hid")]
+mod hid;
 
 fn main() {
+    if let Some(invocation) = crate::cli::parse() {
+        std::process::exit(crate::cli::run(invocation));
+    }
+    if !crate::instance::acquire() {
+        crate::instance::show_running();
+        return;
+    }
     crate::app::run();
 }
@@ -0,0 +1,136 @@
+/*
+ * simulated monitors for working on the ui without hardware, enabled by `--mock-monitors N`
+ * or `mock_monitors` in the settings. they replace the real ones in `get_monitors` and keep
+ * their brightness in memory, everything above the backend treats them like real monitors.
+ * `--mock-duplicate` puts the first two in duplicate mode: both sit on `\\.\MOCK1` like two panels
+ * sharing one `HMONITOR`, so they form one clone group & a brightness change on either reaches both
+*/
+use anyhow::anyhow;
+use std::{
+    ptr, env,
+    collections::HashMap,
+    sync::{
+        Arc, Mutex, LazyLock,
+        atomic::{AtomicBool, AtomicU32, Ordering},
+    },
+};
+use windows::{
+    Win32::{
+        Foundation::HANDLE,
+        Devices::Display::DISPLAYCONFIG_OUTPUT_TECHNOLOGY_HDMI,
+    }
+};
+use crate::{
+    brightness::BrightnessBackend,
+    monitors::{MonitorDeviceImpl, SafeDisplayHandle, SafePhysicalMonitor},
+};
+
+const MOCK_ARG: &str = "--mock-monitors";
+const MOCK_DUPLICATE_ARG: &str = "--mock-duplicate";
+/// shaped like a real `monitorDevicePath` so `pnp_model` & friends keep working
+const MOCK_ID_PREFIX: &str = r"\\?\MOCK#";
+const MOCK_DEFAULT_BRIGHTNESS: u32 = 50;
+
+static MOCK_COUNT: AtomicU32 = AtomicU32::new(0);
+static MOCK_DUPLICATE: AtomicBool = AtomicBool::new(false);
+/// brightness per mock `id`, survives rescans like real hardware would
+static LEVELS: LazyLock<Mutex<HashMap<String, u32>>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// the cli argument wins over `from_settings`
+pub fn init(from_settings: u32) {
+    let mut args = env::args().skip_while(|arg| arg != MOCK_ARG).skip(1);
+    let count = match args.next().map(|n| n.parse::<u32>()) {
+        Some(Ok(n)) => n,
+        Some(Err(e)) => {
+            tracing::warn!("ignoring invalid `{}` value: {}", MOCK_ARG, e);
+            from_settings
+        }
+        None => from_settings,
+    };
+    if count > 0 {
+        tracing::info!("using {} mock monitors instead of real ones", count);
+    }
+    MOCK_COUNT.store(count, Ordering::Relaxed);
+    MOCK_DUPLICATE.store(count >= 2 && env::args().any(|arg| arg == MOCK_DUPLICATE_ARG), Ordering::Relaxed);
+}
+
+pub fn enabled() -> bool {
+    MOCK_COUNT.load(Ordering::Relaxed) > 0
+}
+
+pub fn is_mock(id: &str) -> bool {
+    id.starts_with(MOCK_ID_PREFIX)
+}
+
+pub fn get_monitors() -> Vec<MonitorDeviceImpl> {
+    (1..=MOCK_COUNT.load(Ordering::Relaxed))
+        .map(|n| MonitorDeviceImpl::new(
+            format!(r"{}FADE{:04}#mock&{}#{{fade}}", MOCK_ID_PREFIX, n, n),
+            device_name(n),
+            format!("Mock Monitor {}", n),
+            Arc::new(SafeDisplayHandle(HANDLE(ptr::null_mut()))),
+            Arc::new(SafePhysicalMonitor(HANDLE(ptr::null_mut()))),
+            DISPLAYCONFIG_OUTPUT_TECHNOLOGY_HDMI,
+        ))
+        .collect()
+}
+
+/// `\\.\MOCK2`, or `\\.\MOCK1\Monitor1` for the second of a duplicated pair
+fn device_name(n: u32) -> String {
+    if MOCK_DUPLICATE.load(Ordering::Relaxed) && n <= 2 {
+        format!(r"\\.\MOCK1\Monitor{}", n - 1)
+    } else {
+        format!(r"\\.\MOCK{}", n)
+    }
+}
+
+/// changes a mock monitor's brightness behind fade's back, like the monitor's osd would
+pub fn simulate_external_change(id: &str, percentage: u32) -> anyhow::Result<()> {
+    if !is_mock(id) {
+        return Err(anyhow!("not a mock monitor: {}", id));
+    }
+    let mut levels = LEVELS.lock().map_err(|e| anyhow!("mock levels poisoned: {}", e))?;
+    levels.insert(id.to_string(), percentage.min(100));
+    Ok(())
+}
+
+pub struct MockBackend;
+
+impl BrightnessBackend for MockBackend {
+    fn get(&self, device: &MonitorDeviceImpl) -> anyhow::Result<u32> {
+        let levels = LEVELS.lock().map_err(|e| anyhow!("mock levels poisoned: {}", e))?;
+        Ok(levels.get(&device.id).copied().unwrap_or(MOCK_DEFAULT_BRIGHTNESS))
+    }
+
+    fn set(&self, device: &MonitorDeviceImpl, percentage: u32) -> anyhow::Result<()> {
+        let mut levels = LEVELS.lock().map_err(|e| anyhow!("mock levels poisoned: {}", e))?;
+        levels.insert(device.id.clone(), percentage.min(100));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monitors;
+
+    /// `--mock-duplicate` with three monitors: the first two form a clone group, the third stays alone
+    #[test]
+    fn duplicate_mode_groups_the_first_two() {
+        MOCK_COUNT.store(3, Ordering::Relaxed);
+        MOCK_DUPLICATE.store(true, Ordering::Relaxed);
+        let devices = get_monitors();
+        MOCK_COUNT.store(0, Ordering::Relaxed);
+        MOCK_DUPLICATE.store(false, Ordering::Relaxed);
+
+        let names: Vec<&str> = devices.iter().map(|d| d.device_name.as_str()).collect();
+        assert_eq!(names, [r"\\.\MOCK1\Monitor0", r"\\.\MOCK1\Monitor1", r"\\.\MOCK3"]);
+
+        let peers = |i: usize| {
+            monitors::clone_peers(&devices, &devices[i]).iter().map(|d| d.device_name.as_str()).collect::<Vec<_>>()
+        };
+        assert_eq!(peers(0), [r"\\.\MOCK1\Monitor1"]);
+        assert_eq!(peers(1), [r"\\.\MOCK1\Monitor0"]);
+        assert!(peers(2).is_empty());
+    }
+}
@@ -3,15 +3,21 @@
  * SPDX-License-Identifier: AGPL-3.0
  * api for handling multiple monitors
 */
-use anyhow::anyhow;
+use anyhow::{anyhow, bail};
+use schemars::JsonSchema;
 use serde::{
     Serialize,
     Deserialize
 };
-use tokio::sync::mpsc::Sender;
+use tokio::{sync::mpsc::Sender, time::sleep};
 use std::{
-    sync::Arc,
     fmt, ptr, iter,
+    collections::HashMap,
+    sync::{
+        Arc, Mutex, LazyLock,
+        atomic::{AtomicU32, Ordering},
+    },
+    time::{Duration, Instant},
     ffi::{OsString, OsStr},
     os::windows::ffi::{OsStringExt, OsStrExt},
 };
@@ -19,11 +25,12 @@ use windows::{
     core::{BOOL, PCWSTR},
     Win32::{
         Foundation::{
-            ERROR_SUCCESS, HANDLE, CloseHandle, ERROR_ACCESS_DENIED,  LPARAM, RECT,
+            ERROR_SUCCESS, HANDLE, CloseHandle, ERROR_ACCESS_DENIED, ERROR_INSUFFICIENT_BUFFER, LPARAM, POINT, RECT,
         },
         Graphics::Gdi::{
             DISPLAY_DEVICE_ACTIVE, DISPLAY_DEVICEW, EnumDisplayDevicesW, EnumDisplayMonitors,
-            GetMonitorInfoW, HDC, HMONITOR, MONITORINFO, MONITORINFOEXW,
+            GetMonitorInfoW, MonitorFromPoint, MonitorFromWindow, HDC, HMONITOR, MONITORINFO, MONITORINFOEXW,
+            MONITOR_DEFAULTTOPRIMARY, MONITOR_DEFAULTTONULL, DISPLAYCONFIG_PATH_ACTIVE,
         },
         Devices::Display::{
             QueryDisplayConfig, DestroyPhysicalMonitor,
@@ -31,19 +38,27 @@ use windows::{
             GetNumberOfPhysicalMonitorsFromHMONITOR, GetPhysicalMonitorsFromHMONITOR,
             DISPLAYCONFIG_OUTPUT_TECHNOLOGY_DISPLAYPORT_EMBEDDED,
             DISPLAYCONFIG_PATH_INFO, DISPLAYCONFIG_TARGET_DEVICE_NAME,
-            QDC_ONLY_ACTIVE_PATHS, DISPLAYCONFIG_MODE_INFO, PHYSICAL_MONITOR,
+            QDC_ONLY_ACTIVE_PATHS, QDC_ALL_PATHS, DISPLAYCONFIG_MODE_INFO, PHYSICAL_MONITOR,
             DISPLAYCONFIG_DEVICE_INFO_HEADER, DISPLAYCONFIG_MODE_INFO_TYPE_TARGET,
             DISPLAYCONFIG_OUTPUT_TECHNOLOGY_LVDS, DISPLAYCONFIG_VIDEO_OUTPUT_TECHNOLOGY,
             DISPLAYCONFIG_DEVICE_INFO_GET_TARGET_NAME, DISPLAYCONFIG_OUTPUT_TECHNOLOGY_INTERNAL,
+            DISPLAYCONFIG_OUTPUT_TECHNOLOGY_HD15, DISPLAYCONFIG_OUTPUT_TECHNOLOGY_DVI,
+            DISPLAYCONFIG_OUTPUT_TECHNOLOGY_HDMI, DISPLAYCONFIG_OUTPUT_TECHNOLOGY_DISPLAYPORT_EXTERNAL,
+            DISPLAYCONFIG_OUTPUT_TECHNOLOGY_UDI_EXTERNAL, DISPLAYCONFIG_OUTPUT_TECHNOLOGY_MIRACAST,
+            DISPLAYCONFIG_OUTPUT_TECHNOLOGY_INDIRECT_WIRED, DISPLAYCONFIG_OUTPUT_TECHNOLOGY_INDIRECT_VIRTUAL,
+        },
+        UI::WindowsAndMessaging::{
+            EDD_GET_DEVICE_INTERFACE_NAME, GetCursorPos, GetForegroundWindow, GetWindowRect, GetClassNameW,
         },
-        UI::WindowsAndMessaging::EDD_GET_DEVICE_INTERFACE_NAME,
         Storage::FileSystem::{
             CreateFileW, FILE_GENERIC_READ, FILE_GENERIC_WRITE, FILE_SHARE_READ, FILE_SHARE_WRITE,
             OPEN_EXISTING,
         },
     }
 };
-use crate::{brightness, overlay::Overlay};
+#[cfg(feature = "hid")]
+use crate::hid;
+use crate::{brightness, edid, error::MonitorError, gamma, mock, wmi, utils::format_win_err, overlay::Overlay, brightness::BrightnessBackend, protocol::SLIDER_RANGE, settings::EdidSerial};
 
 #[inline]
 fn flag_set<T: std::ops::BitAnd<Output = T> + std::cmp::PartialEq + Copy>(t: T, flag: T) -> bool {
@@ -96,6 +111,68 @@ impl Drop for SafePhysicalMonitor {
 unsafe impl Send for SafePhysicalMonitor {}
 unsafe impl Sync for SafePhysicalMonitor {}
 
+/// last brightness percentage written by fade, shared between clones
+#[derive(Debug)]
+pub struct LastWritten(AtomicU32);
+
+impl Default for LastWritten {
+    fn default() -> Self {
+        Self(AtomicU32::new(u32::MAX))
+    }
+}
+
+impl PartialEq for LastWritten {
+    fn eq(&self, other: &Self) -> bool {
+        self.get() == other.get()
+    }
+}
+
+impl Eq for LastWritten {}
+
+impl LastWritten {
+    pub fn get(&self) -> Option<u32> {
+        match self.0.load(Ordering::Relaxed) {
+            u32::MAX => None,
+            value => Some(value),
+        }
+    }
+
+    pub fn set(&self, percentage: u32) {
+        self.0.store(percentage, Ordering::Relaxed);
+    }
+}
+
+/// last read brightness per monitor `id`, ddc/ci reads are slow & several paths
+/// (watcher, new websocket clients, ...) want it at about the same time.
+/// global rather than per `MonitorDeviceImpl` since every rescan builds new ones
+static BRIGHTNESS_CACHE: LazyLock<Mutex<HashMap<String, (u32, Instant)>>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// same as `BRIGHTNESS_CACHE` for contrast, `None` for monitors without it
+static CONTRAST_CACHE: LazyLock<Mutex<HashMap<String, (Option<u32>, Instant)>>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// ddc/ci capabilities string per monitor `id`, it takes seconds to request & never changes
+static CAPABILITIES: LazyLock<Mutex<HashMap<String, String>>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// bumped per monitor `id` by every brightness write, a `set_smooth` stops once it's outdated
+static RAMPS: LazyLock<Mutex<HashMap<String, u64>>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+/// shortest pause between ramp writes, a ddc/ci write takes tens of ms
+const RAMP_STEP: Duration = Duration::from_millis(50);
+
+fn next_ramp(id: &str) -> u64 {
+    let mut ramps = RAMPS.lock().unwrap_or_else(|e| e.into_inner());
+    let generation = ramps.entry(id.to_string()).or_default();
+    *generation += 1;
+    *generation
+}
+
+fn is_current_ramp(id: &str, generation: u64) -> bool {
+    RAMPS.lock()
+        .map(|r| r.get(id) == Some(&generation))
+        .unwrap_or(false)
+}
+
+/// result of `probe_hardware_control` per `id`, access doesn't change within a session
+static HARDWARE_PROBES: LazyLock<Mutex<HashMap<String, Result<(), String>>>> = LazyLock::new(|| Mutex::new(HashMap::new()));
 
 #[derive(Debug, PartialEq, Eq)]
 pub struct MonitorDeviceImpl {
@@ -111,6 +188,16 @@ pub struct MonitorDeviceImpl {
     pub physical_monitor: Arc<SafePhysicalMonitor>,
     /// output display technology for determining internal display
     pub output_technology: DISPLAYCONFIG_VIDEO_OUTPUT_TECHNOLOGY,
+    /// what fade itself wrote, to tell apart changes made by other apps
+    pub last_written: Arc<LastWritten>,
+    /// `[width, height]` read from the edid once
+    pub physical_size_mm: Option<[u32; 2]>,
+    /// horizontal, from `physical_size_mm` & the resolution when `get_monitors` ran
+    pub dpi: Option<u32>,
+    /// edid pnp manufacturer id, e.g. `DEL`
+    pub manufacturer: Option<String>,
+    /// edid serial number, tells identical monitors apart
+    pub serial: Option<String>,
 }
 
 /// send + sync
@@ -128,25 +215,241 @@ impl Clone for MonitorDeviceImpl {
             display_handle: Arc::clone(&self.display_handle),
             physical_monitor: Arc::clone(&self.physical_monitor),
             output_technology: self.output_technology,
+            last_written: Arc::clone(&self.last_written),
+            physical_size_mm: self.physical_size_mm,
+            dpi: self.dpi,
+            manufacturer: self.manufacturer.clone(),
+            serial: self.serial.clone(),
         }
     }
 }
 
 /// especially for passing to the frontend
-#[derive(Debug, Serialize, Deserialize, Clone, Eq, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, Eq, PartialEq)]
 pub struct MonitorInfo {
+    /// `monitorDevicePath` as unique identifier
+    pub id: String,
     /// win32 `DeviceName`
     pub device_name: String,           
     /// actual monitors name (as shown in settings)
     pub name: String,         
+    /// user given label, shown instead of `name`
+    pub label: Option<String>,
+    /// stable selector from `Settings::aliases`
+    pub alias: Option<String>,
+    /// shared by monitors in duplicate mode, the gdi device they all show
+    pub clone_group_id: Option<String>,
+    /// brightness & dim locked by `lock_monitor`
+    pub locked: bool,
     // current brightness percentage
     pub brightness: u32,
+    /// ddc/ci range & value `brightness` was computed from, `None` for internal displays
+    pub raw_min: Option<u32>,
+    pub raw_current: Option<u32>,
+    pub raw_max: Option<u32>,
+    /// ddc/ci contrast percentage, `None` for internal displays & monitors without it
+    pub contrast: Option<u32>,
+    /// `set_warmth` percentage, `None` without a gamma color temperature
+    pub warmth: Option<u8>,
+    /// laptop panel, no ddc/ci features like contrast or input source
+    #[serde(default)]
+    pub is_internal: bool,
+    /// how it's connected, see `MonitorDeviceImpl::connection`
+    #[serde(default)]
+    pub connection: String,
+    /// `[width, height]` from the edid, `None` when it doesn't say
+    pub physical_size_mm: Option<[u32; 2]>,
+    /// horizontal, from the current resolution & `physical_size_mm`
+    pub dpi: Option<u32>,
+    /// edid pnp manufacturer id, e.g. `DEL`
+    pub manufacturer: Option<String>,
+    /// edid serial number, tells identical monitors apart
+    pub serial: Option<String>,
 }
 
 // send + sync
 unsafe impl Sync for MonitorInfo {}
 unsafe impl Send for MonitorInfo {}
 
+/// how the negative range of the slider darkens a monitor
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DimBackend {
+    /// black layered window on top of the monitor
+    #[default]
+    Overlay,
+    /// scaled down gamma ramp, see `gamma.rs`
+    Gamma,
+    /// both, the level is split between them by `Settings::dim_split`
+    Both,
+}
+
+/// how `DimBackend::Both` splits a dim level. the darkness multiplies, so either way
+/// the monitor ends up equally dark, what differs is how it looks on the way there:
+/// gamma shrinks the tonal range (banding in gradients, a slight color shift) but survives
+/// fullscreen exclusive apps, the overlay keeps colors exact but is a veil in the overlay color
+/// which those apps can hide. gamma can't go below half brightness either way
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DimSplit {
+    /// gamma down to its limit, the overlay the rest. the most darkness survives fullscreen apps
+    GammaFirst,
+    /// the overlay up to `OVERLAY_FIRST_MAX_ALPHA`, gamma deepens it past that
+    OverlayFirst,
+    /// an equal share of the darkness each, as far as gamma reaches
+    #[default]
+    Blend,
+}
+
+/// overlay alpha `DimSplit::OverlayFirst` stops at before gamma takes over
+const OVERLAY_FIRST_MAX_ALPHA: u8 = 204;
+
+/// read by `DimBackend::Both`, set from the settings like `wmi::init`
+static DIM_SPLIT: Mutex<DimSplit> = Mutex::new(DimSplit::Blend);
+
+pub fn set_dim_split(split: DimSplit) {
+    if let Ok(mut current) = DIM_SPLIT.lock() {
+        *current = split;
+    }
+}
+
+/// some panels go fully black at 0, no way to see what to undo
+pub const DEFAULT_MIN_BRIGHTNESS: u32 = 5;
+
+/// `Settings::min_brightness`, set from the settings like `wmi::init`
+static MIN_BRIGHTNESS: LazyLock<Mutex<HashMap<String, u32>>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+
+pub fn set_min_brightness(floors: HashMap<String, u32>) {
+    if let Ok(mut current) = MIN_BRIGHTNESS.lock() {
+        *current = floors;
+    }
+}
+
+/// brightness floor of the monitor `id`
+pub fn min_brightness(id: &str) -> u32 {
+    MIN_BRIGHTNESS.lock().ok()
+        .and_then(|floors| floors.get(id).copied())
+        .unwrap_or(DEFAULT_MIN_BRIGHTNESS)
+        .min(100)
+}
+
+/// `percentage` within the floor of the monitor `id` & 100
+pub fn clamp_brightness(id: &str, percentage: u32) -> u32 {
+    percentage.clamp(min_brightness(id), 100)
+}
+
+/// dim `level` -> `(overlay alpha, gamma multiplier)` under the configured `DimSplit`,
+/// the remaining light is the product of `1 - alpha / 255` & the multiplier
+pub fn split_dim(level: u8) -> (u8, f64) {
+    let split = DIM_SPLIT.lock().map(|s| *s).unwrap_or_default();
+    let target = 1.0 - level as f64 / u8::MAX as f64;
+    let gamma = match split {
+        DimSplit::GammaFirst => target,
+        DimSplit::OverlayFirst => {
+            let overlay = target.max(1.0 - OVERLAY_FIRST_MAX_ALPHA as f64 / u8::MAX as f64);
+            target / overlay
+        }
+        DimSplit::Blend => target.sqrt(),
+    }.max(gamma::MIN_GAMMA_MULTIPLIER);
+    let overlay = target / gamma;
+    (((1.0 - overlay) * u8::MAX as f64).round() as u8, gamma)
+}
+
+/// how a monitor's color temperature is applied
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorTempMechanism {
+    /// the monitor's own color preset (vcp 0x14), snapped to the nearest preset
+    Ddcci,
+    /// tinted gamma ramp, see `gamma.rs`
+    Gamma,
+}
+
+/// color temperature chosen for a monitor, persisted by `id`
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Eq, PartialEq)]
+pub struct ColorTemp {
+    pub mechanism: ColorTempMechanism,
+    pub kelvin: u32,
+}
+
+/// what can be done to a monitor, for the ui to only offer working controls
+#[derive(Debug, Serialize, Clone, Eq, PartialEq)]
+pub struct ControlCapability {
+    /// brightness through ioctl, ddc/ci or a fallback backend
+    pub hardware: bool,
+    /// dimming through an overlay window
+    pub overlay: bool,
+    /// why something isn't available
+    pub reason: Option<String>,
+}
+
+/// dim backend & level currently applied on a monitor
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct DimState {
+    pub backend: DimBackend,
+    pub level: u8,
+}
+
+/// mccs `luminance` vcp code, what `SetMonitorBrightness` writes
+pub const VCP_LUMINANCE: u8 = 0x10;
+/// mccs `input select` vcp code
+pub const VCP_INPUT_SOURCE: u8 = 0x60;
+/// common `VCP_INPUT_SOURCE` values, monitors may use others for their extra ports
+pub const INPUT_DISPLAYPORT1: u8 = 0x0f;
+pub const INPUT_DISPLAYPORT2: u8 = 0x10;
+pub const INPUT_HDMI1: u8 = 0x11;
+pub const INPUT_HDMI2: u8 = 0x12;
+
+/// readable name of a common input source value
+pub fn input_source_name(input: u8) -> Option<&'static str> {
+    match input {
+        INPUT_DISPLAYPORT1 => Some("DisplayPort 1"),
+        INPUT_DISPLAYPORT2 => Some("DisplayPort 2"),
+        INPUT_HDMI1 => Some("HDMI 1"),
+        INPUT_HDMI2 => Some("HDMI 2"),
+        _ => None,
+    }
+}
+
+/// negative slider range `[-100..0)` -> alpha value
+pub fn slider_to_alpha(value: i32) -> u8 {
+    ((-value) as f32 * 2.55) as u8
+}
+
+/// what a slider value asks for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SliderTarget {
+    /// hardware brightness percentage, from `0..=100`
+    Brightness(u32),
+    /// dim alpha, from `-100..0`
+    Dim(u8),
+}
+
+/// checks `value` against `SLIDER_RANGE` & splits it into a brightness or a dim
+pub fn slider_target(value: i32) -> anyhow::Result<SliderTarget> {
+    if !SLIDER_RANGE.contains(&(value as i64)) {
+        bail!("slider value must be within {}..={}, got {}", SLIDER_RANGE.start(), SLIDER_RANGE.end(), value);
+    }
+    Ok(if value >= 0 {
+        SliderTarget::Brightness(value as u32)
+    } else {
+        SliderTarget::Dim(slider_to_alpha(value))
+    })
+}
+
+/// `\\.\DISPLAY1\Monitor0` -> `\\.\DISPLAY1`, the gdi device owning the monitor
+pub fn gdi_device_name(device_name: &str) -> &str {
+    match device_name.match_indices('\\').nth(3) {
+        Some((idx, _)) => &device_name[..idx],
+        None => device_name,
+    }
+}
+
+/// pnp model of a `monitorDevicePath`, `\\?\DISPLAY#GSM5B7F#...` -> `GSM5B7F`,
+/// the same for every unit of a model unlike the instance part
+pub fn pnp_model(device_path: &str) -> Option<&str> {
+    device_path.split('#').nth(1).filter(|m| !m.is_empty())
+}
 
 fn wchar_to_string(s: &[u16]) -> String {
     let end = s.iter().position(|&x| x == 0).unwrap_or(s.len());
@@ -158,7 +461,7 @@ fn wchar_to_string(s: &[u16]) -> String {
 /// passing the `monitorDevicePath` as string cause to relate with frontend in easier way
 fn get_handler_from_device_path(
     device_path: &str,
-) -> anyhow::Result<Option<SafeDisplayHandle>> {
+) -> Result<Option<SafeDisplayHandle>, MonitorError> {
     unsafe {
         let wide: Vec<u16> = OsStr::new(device_path)
             .encode_wide()
@@ -178,17 +481,13 @@ fn get_handler_from_device_path(
         match handle {
             Ok(h) if !h.is_invalid() => Ok(Some(SafeDisplayHandle(h))),
             Ok(_) => Ok(None), // invalid
-            Err(e) => {
-                if e.code() == ERROR_ACCESS_DENIED.to_hresult() {
-                    Ok(None) // not a real monitor [eg. rdp session]
-                } else {
-                    Err(anyhow!(
-                        "failed to open monitor handle (CreateFileW) for device: {}, err={:?}",
-                        device_path,
-                        e
-                    ))
+            Err(e) => match MonitorError::from(e) {
+                MonitorError::AccessDenied => Ok(None), // not a real monitor [eg. rdp session]
+                e => {
+                    tracing::debug!("failed to open monitor handle (CreateFileW) for device: {}", device_path);
+                    Err(e)
                 }
-            }
+            },
         }
     }
 }
@@ -197,24 +496,21 @@ fn get_handler_from_device_path(
 // it'd also return a valid handle for [ddc/ci, non ddc/ci, rdp, ...] monitors.
 fn get_physical_monitors_from_hmonitor(
     hmonitor: HMONITOR,
-) -> anyhow::Result<Vec<SafePhysicalMonitor>> {
+) -> Result<Vec<SafePhysicalMonitor>, MonitorError> {
     let mut physical_number: u32 = 0;
     unsafe {
-        GetNumberOfPhysicalMonitorsFromHMONITOR(hmonitor, &mut physical_number) 
-        .map_err(|e| 
-            anyhow!("the length of GetPhysicalMonitorsFromHMONITOR() and EnumDisplayDevicesW() results did not \n
-                    match, this could be because monitors were connected/disconnected while loading devices, err: {:#?}", e
-            ))?;
+        GetNumberOfPhysicalMonitorsFromHMONITOR(hmonitor, &mut physical_number)?;
 
         let mut raw_physical_monitors = vec![PHYSICAL_MONITOR::default(); physical_number as usize];
         // allocate first so that pushing the wrapped handles always succeeds.
         let mut physical_monitors = Vec::with_capacity(raw_physical_monitors.len());
 
+        // a buffer that no longer fits the count above means monitors were (dis)connected meanwhile
         GetPhysicalMonitorsFromHMONITOR(hmonitor, &mut raw_physical_monitors)
-            .map_err(|e| 
-                anyhow!("the length of GetPhysicalMonitorsFromHMONITOR() and EnumDisplayDevicesW() results did not \n
-                        match, this could be because monitors were connected/disconnected while loading devices, err: {:#?}", e 
-                ))?;
+            .map_err(|e| match e.code() {
+                code if code == ERROR_INSUFFICIENT_BUFFER.to_hresult() => MonitorError::LengthMismatch,
+                _ => MonitorError::from(e),
+            })?;
         // transform immediately into WrappedPhysicalMonitor so the handles don't leak
         raw_physical_monitors
             .into_iter()
@@ -227,15 +523,13 @@ fn get_physical_monitors_from_hmonitor(
 /// connected but inactive displays will filtered out
 fn get_display_devices_from_hmonitor(
     hmonitor: HMONITOR,
-) -> anyhow::Result<Vec<DISPLAY_DEVICEW>> {
+) -> Result<Vec<DISPLAY_DEVICEW>, MonitorError> {
     unsafe {
         let mut info = MONITORINFOEXW::default();
         info.monitorInfo.cbSize = size_of::<MONITORINFOEXW>() as u32;
 
         let info_ptr = &mut info as *mut _ as *mut MONITORINFO;
-            GetMonitorInfoW(hmonitor, info_ptr)
-            .ok()
-            .map_err(|e| anyhow!("failed to get monitor info: {:#?}", e))?;
+            GetMonitorInfoW(hmonitor, info_ptr).ok()?;
 
         Ok((0..)
             .map_while(|device_number| {
@@ -260,7 +554,7 @@ fn get_display_devices_from_hmonitor(
 /// returns a list of `HMONITOR` handles,
 /// it's a logical construct that might correspond to multiple physical monitors
 /// e.g. when in "Duplicate" mode two physical monitors will belong to the same `HMONITOR`
-pub fn enum_display_monitors() -> anyhow::Result<Vec<HMONITOR>> {
+pub fn enum_display_monitors() -> Result<Vec<HMONITOR>, MonitorError> {
     unsafe{
         extern "system" fn enum_monitors(
             handle: HMONITOR,
@@ -281,13 +575,119 @@ pub fn enum_display_monitors() -> anyhow::Result<Vec<HMONITOR>> {
             Some(enum_monitors),
             LPARAM(&mut hmonitors as *mut _ as isize),
         )
-        .ok()
-        .map_err(|e| anyhow!("failed to enumerate device monitors, err: {:#?}", e))?;
+        .ok()?;
 
         Ok(hmonitors)
     }
 }
 
+/// display device name (`\\.\DISPLAY1\Monitor0`) of the monitor with this `monitorDevicePath`
+fn device_name_for_path(device_path: &str) -> Option<String> {
+    enum_display_monitors().ok()?
+        .into_iter()
+        .filter_map(|hm| get_display_devices_from_hmonitor(hm).ok())
+        .flatten()
+        .find(|dev| wchar_to_string(&dev.DeviceID) == device_path)
+        .map(|dev| wchar_to_string(&dev.DeviceName))
+}
+
+/// managed devices without an overlay window, their overlay dim would go nowhere
+pub fn devices_without_overlay<'a>(
+    devices: &'a [MonitorDeviceImpl],
+    overlay_devices: &[String],
+) -> Vec<&'a MonitorDeviceImpl> {
+    devices.iter()
+        .filter(|d| !overlay_devices.iter().any(|o| o == gdi_device_name(&d.device_name)))
+        .collect()
+}
+
+/// other managed monitors showing the same source as `dev` (duplicate mode), they share
+/// the gdi device & so the `HMONITOR`
+pub fn clone_peers<'a>(devices: &'a [MonitorDeviceImpl], dev: &MonitorDeviceImpl) -> Vec<&'a MonitorDeviceImpl> {
+    let gdi_name = gdi_device_name(&dev.device_name);
+    devices.iter()
+        .filter(|d| d.id != dev.id && gdi_device_name(&d.device_name) == gdi_name)
+        .collect()
+}
+
+/// gdi device name (`szDevice`) of a `HMONITOR`
+pub fn hmonitor_device_name(hmonitor: HMONITOR) -> Option<String> {
+    unsafe {
+        let mut info = MONITORINFOEXW::default();
+        info.monitorInfo.cbSize = size_of::<MONITORINFOEXW>() as u32;
+        GetMonitorInfoW(hmonitor, &mut info as *mut _ as *mut MONITORINFO)
+            .as_bool()
+            .then(|| wchar_to_string(&info.szDevice))
+    }
+}
+
+/// gdi device name of the primary monitor, it always contains the desktop origin
+pub fn primary_device_name() -> Option<String> {
+    hmonitor_device_name(unsafe { MonitorFromPoint(POINT { x: 0, y: 0 }, MONITOR_DEFAULTTOPRIMARY) })
+}
+
+/// gdi device name of the monitor under the mouse cursor,
+/// `None` if the cursor position can't be read or is off every monitor
+pub fn cursor_monitor() -> Option<String> {
+    unsafe {
+        let mut point = POINT::default();
+        GetCursorPos(&mut point).ok()?;
+        let hmonitor = MonitorFromPoint(point, MONITOR_DEFAULTTONULL);
+        if hmonitor.is_invalid() {
+            return None;
+        }
+        hmonitor_device_name(hmonitor)
+    }
+}
+
+/// window classes of the desktop, which covers its monitor without being a fullscreen app
+const DESKTOP_CLASSES: [&str; 2] = ["Progman", "WorkerW"];
+
+/// gdi device name of the monitor the foreground window covers entirely, a fullscreen game or video
+pub fn fullscreen_monitor() -> Option<String> {
+    unsafe {
+        let hwnd = GetForegroundWindow();
+        if hwnd.is_invalid() {
+            return None;
+        }
+        let mut class = [0u16; 64];
+        let len = GetClassNameW(hwnd, &mut class).max(0) as usize;
+        if DESKTOP_CLASSES.contains(&String::from_utf16_lossy(&class[..len]).as_str()) {
+            return None;
+        }
+
+        let mut rect = RECT::default();
+        GetWindowRect(hwnd, &mut rect).ok()?;
+        let hmonitor = MonitorFromWindow(hwnd, MONITOR_DEFAULTTONULL);
+        if hmonitor.is_invalid() {
+            return None;
+        }
+        let mut info = MONITORINFOEXW::default();
+        info.monitorInfo.cbSize = size_of::<MONITORINFOEXW>() as u32;
+        if !GetMonitorInfoW(hmonitor, &mut info as *mut _ as *mut MONITORINFO).as_bool() {
+            return None;
+        }
+        let m = info.monitorInfo.rcMonitor;
+        (rect.left <= m.left && rect.top <= m.top && rect.right >= m.right && rect.bottom >= m.bottom)
+            .then(|| wchar_to_string(&info.szDevice))
+    }
+}
+
+/// desktop rect of every `HMONITOR`, keyed by its gdi device name (`szDevice`)
+pub fn monitor_rects() -> anyhow::Result<HashMap<String, RECT>> {
+    let mut rects = HashMap::new();
+    for hmonitor in enum_display_monitors()? {
+        unsafe {
+            let mut info = MONITORINFOEXW::default();
+            info.monitorInfo.cbSize = size_of::<MONITORINFOEXW>() as u32;
+            if GetMonitorInfoW(hmonitor, &mut info as *mut _ as *mut MONITORINFO).as_bool() {
+                rects.insert(wchar_to_string(&info.szDevice), info.monitorInfo.rcMonitor);
+            }
+        }
+    }
+    Ok(rects)
+}
+
 impl MonitorDeviceImpl {
     pub fn new(
         id: String,
@@ -297,6 +697,10 @@ impl MonitorDeviceImpl {
         physical_monitor: Arc<SafePhysicalMonitor>,
         output_technology: DISPLAYCONFIG_VIDEO_OUTPUT_TECHNOLOGY,
     ) -> Self {
+        let edid = edid::read_edid(&id).ok();
+        let physical_size_mm = edid.as_deref().and_then(edid::physical_size_mm);
+        let manufacturer = edid.as_deref().and_then(edid::manufacturer);
+        let serial = edid.as_deref().and_then(edid::serial);
         Self {
             id,
             device_name,
@@ -304,19 +708,83 @@ impl MonitorDeviceImpl {
             display_handle,
             physical_monitor,
             output_technology,
+            last_written: Arc::new(LastWritten::default()),
+            physical_size_mm,
+            dpi: None,
+            manufacturer,
+            serial,
         }
     }
 
-    pub fn info(&self) -> anyhow::Result<MonitorInfo> {
+    /// the brightness may come from a read within `ttl`, `Duration::ZERO` always reads
+    pub fn info(&self, ttl: Duration) -> anyhow::Result<MonitorInfo> {
+        let brightness = self.get_cached(ttl)?;
+        let raw = if self.is_internal() { None } else { brightness::ddcci_last_read(&self.id) };
         Ok(
             MonitorInfo {
+                id: self.id.clone(),
                 device_name: self.device_name.clone(),
                 name: self.friendly_name.clone(),
-                brightness: self.get()?,
+                label: None,
+                alias: None,
+                clone_group_id: None,
+                locked: false,
+                brightness,
+                raw_min: raw.map(|r| r.min),
+                raw_current: raw.map(|r| r.current),
+                raw_max: raw.map(|r| r.max),
+                contrast: self.contrast_cached(ttl),
+                warmth: None,
+                is_internal: self.is_internal(),
+                connection: self.connection().to_string(),
+                physical_size_mm: self.physical_size_mm,
+                dpi: self.dpi,
+                manufacturer: self.manufacturer.clone(),
+                serial: self.serial.clone(),
             }
         )
     }
 
+    /// `None` unless the edid gives both the manufacturer & a serial
+    pub fn edid_serial(&self) -> Option<EdidSerial> {
+        Some(EdidSerial { manufacturer: self.manufacturer.clone()?, serial: self.serial.clone()? })
+    }
+
+    fn dpi_in(&self, rects: &HashMap<String, RECT>) -> Option<u32> {
+        let [width_mm, _] = self.physical_size_mm?;
+        let rect = rects.get(gdi_device_name(&self.device_name))?;
+        let width_px = (rect.right - rect.left) as f64;
+        Some((width_px / (width_mm as f64 / 25.4)).round() as u32)
+    }
+
+    /// whether hardware brightness works, probed with a read once per session
+    pub fn probe_hardware_control(&self) -> Result<(), String> {
+        if let Some(probe) = HARDWARE_PROBES.lock().ok().and_then(|p| p.get(&self.id).cloned()) {
+            return probe;
+        }
+
+        let handle_missing = if self.is_internal() {
+            self.display_handle.0.is_invalid()
+        } else {
+            self.physical_monitor.0.is_invalid()
+        };
+        let probe = match self.get() {
+            Ok(_) => Ok(()),
+            // `get_handler_from_device_path` only gives up silently on access denied (rdp, ...)
+            Err(_) if handle_missing && self.is_internal() => Err(format!(
+                "the display device can't be opened: {}", format_win_err(ERROR_ACCESS_DENIED)
+            )),
+            Err(_) if handle_missing => Err(
+                "no physical monitor handle, the monitor couldn't be matched to its display device".to_string()
+            ),
+            Err(e) => Err(format!("brightness can't be read: {}", e)),
+        };
+        if let Ok(mut probes) = HARDWARE_PROBES.lock() {
+            probes.insert(self.id.clone(), probe.clone());
+        }
+        probe
+    }
+
     /// check if its an internal display
     pub fn is_internal(&self) -> bool {
         match self.output_technology {
@@ -329,59 +797,393 @@ impl MonitorDeviceImpl {
         }
     }
 
+    /// connector for the ui, "Internal", "HDMI", "DisplayPort", ...
+    pub fn connection(&self) -> &'static str {
+        if self.is_internal() {
+            return "Internal";
+        }
+        match self.output_technology {
+            DISPLAYCONFIG_OUTPUT_TECHNOLOGY_HD15 => "VGA",
+            DISPLAYCONFIG_OUTPUT_TECHNOLOGY_DVI => "DVI",
+            DISPLAYCONFIG_OUTPUT_TECHNOLOGY_HDMI => "HDMI",
+            DISPLAYCONFIG_OUTPUT_TECHNOLOGY_DISPLAYPORT_EXTERNAL => "DisplayPort",
+            DISPLAYCONFIG_OUTPUT_TECHNOLOGY_UDI_EXTERNAL => "UDI",
+            DISPLAYCONFIG_OUTPUT_TECHNOLOGY_MIRACAST => "Miracast",
+            // usb docks & displaylink adapters
+            DISPLAYCONFIG_OUTPUT_TECHNOLOGY_INDIRECT_WIRED => "USB",
+            DISPLAYCONFIG_OUTPUT_TECHNOLOGY_INDIRECT_VIRTUAL => "Virtual",
+            _ => "Other",
+        }
+    }
+
+    /// native brightness backend of the monitor
+    pub fn backend(&self) -> &'static dyn BrightnessBackend {
+        if mock::is_mock(&self.id) {
+            &mock::MockBackend
+        } else if self.is_internal() {
+            &brightness::Ioctl
+        } else {
+            &brightness::Ddcci
+        }
+    }
+
     /// returns the corresponding monitor's brightness value
     pub fn get(&self) -> anyhow::Result<u32> {
-        Ok(if self.is_internal() {
-            brightness::ioctl_query_display_brightness(self)?
-        } else {
-            brightness::ddcci_get_monitor_brightness(self)?.get_current_percentage()
-        })
+        let result = match self.backend().get(self) {
+            #[cfg(feature = "hid")]
+            Err(e) => hid::HidBackend.get(self).map_err(|_| e),
+            result => result,
+        };
+        match result {
+            Err(e) if self.is_internal() && wmi::available() => wmi::WmiBackend.get(self).map_err(|_| e),
+            result => result,
+        }
+        .map_err(|e| self.failed("read the brightness", e))
+    }
+
+    /// `err` with the monitor & what was attempted, commands hand the message to the ui as is
+    fn failed(&self, attempt: &str, err: impl fmt::Display) -> anyhow::Error {
+        anyhow!("failed to {} of '{}': {:#}", attempt, self.friendly_name, err)
+    }
+
+    /// brightness read within `ttl` if there's one, otherwise a fresh `get`
+    pub fn get_cached(&self, ttl: Duration) -> anyhow::Result<u32> {
+        if let Ok(cache) = BRIGHTNESS_CACHE.lock() {
+            if let Some(&(value, read_at)) = cache.get(&self.id) {
+                if read_at.elapsed() < ttl {
+                    return Ok(value);
+                }
+            }
+        }
+        let value = self.get()?;
+        if let Ok(mut cache) = BRIGHTNESS_CACHE.lock() {
+            cache.insert(self.id.clone(), (value, Instant::now()));
+        }
+        Ok(value)
+    }
+
+    /// generation for a `set_smooth`, taken before the ramp is spawned so a write
+    /// landing before the ramp's first step still cancels it
+    pub fn start_ramp(&self) -> u64 {
+        next_ramp(&self.id)
     }
 
-    /// set brightness percentage
+    /// set brightness percentage, stops a running `set_smooth`
     pub fn set(&self, percentage: u32) -> anyhow::Result<()> {
-        if self.is_internal() {
+        next_ramp(&self.id);
+        self.write(percentage)
+    }
+
+    /// `set` for async callers, on the blocking pool. a ddc/ci write may wait on the
+    /// write rate limit & the read back, which mustn't stall a runtime worker
+    pub async fn set_async(&self, percentage: u32) -> anyhow::Result<()> {
+        let dev = self.clone();
+        tauri::async_runtime::spawn_blocking(move || dev.set(percentage)).await?
+    }
+
+    /// `get` on the blocking pool, a ddc/ci read retries with a backoff
+    pub async fn get_async(&self) -> anyhow::Result<u32> {
+        let dev = self.clone();
+        tauri::async_runtime::spawn_blocking(move || dev.get()).await?
+    }
+
+    /// `write` on the blocking pool, see `set_async`
+    async fn write_async(&self, percentage: u32) -> anyhow::Result<()> {
+        let dev = self.clone();
+        tauri::async_runtime::spawn_blocking(move || dev.write(percentage)).await?
+    }
+
+    /// steps from the current brightness to `target` over `duration`, a newer `set` or
+    /// `set_smooth` of the monitor stops it where it is. internal displays step through
+    /// their supported levels, the others in even percentages per `RAMP_STEP`.
+    /// `generation` comes from `start_ramp`
+    pub async fn set_smooth(&self, target: u32, duration: Duration, generation: u64) -> anyhow::Result<()> {
+        let target = target.min(100);
+        let from = self.get_async().await?;
+        if from == target {
+            return Ok(());
+        }
+
+        let between = |level: u32| level != from && (from.min(target)..=from.max(target)).contains(&level);
+        let mut levels: Vec<u32> = if self.is_internal() && !mock::is_mock(&self.id) {
             let supported = brightness::ioctl_query_supported_brightness(self)?;
-            let new_value = supported.get_nearest(percentage);
-            brightness::ioctl_set_display_brightness(self, new_value)?;
+            let mut levels: Vec<u32> = supported.levels().iter().map(|&l| l as u32).filter(|&l| between(l)).collect();
+            levels.sort_unstable();
+            levels.dedup();
+            levels
         } else {
-            let current = brightness::ddcci_get_monitor_brightness(self)?;
-            tracing::debug!("current ddcci monitor brightness: {:?}", current);
-            let new_value = current.percentage_to_current(percentage);
-            brightness::ddcci_set_monitor_brightness(self, new_value)?;
+            let steps = (duration.as_millis() / RAMP_STEP.as_millis()).max(1) as i64;
+            let delta = target as i64 - from as i64;
+            let mut levels: Vec<u32> = (1..=steps).map(|i| (from as i64 + delta * i / steps) as u32).collect();
+            levels.sort_unstable();
+            levels.dedup();
+            levels
+        };
+        if target < from {
+            levels.reverse();
+        }
+        if levels.last() != Some(&target) {
+            levels.push(target);
+        }
+
+        let pause = duration / levels.len() as u32;
+        for (i, &level) in levels.iter().enumerate() {
+            if !is_current_ramp(&self.id, generation) {
+                tracing::debug!("brightness ramp of '{}' cancelled at {}", self.friendly_name, level);
+                return Ok(());
+            }
+            self.write_async(level).await?;
+            if i + 1 < levels.len() {
+                sleep(pause).await;
+            }
         }
         Ok(())
     }
 
+    fn write(&self, percentage: u32) -> anyhow::Result<()> {
+        // past 100 `percentage_to_current` would go beyond the monitor's max
+        let percentage = clamp_brightness(&self.id, percentage);
+        let result = match self.backend().set(self, percentage) {
+            // last resort, only when the native backend failed
+            #[cfg(feature = "hid")]
+            Err(e) => hid::HidBackend.set(self, percentage).map_err(|hid_err| {
+                tracing::debug!("hid fallback unavailable for '{}': {}", self.friendly_name, hid_err);
+                e
+            }),
+            result => result,
+        };
+        // the os slider path, slow but works on laptops nothing else does
+        let result = match result {
+            Err(e) if self.is_internal() && wmi::available() => wmi::WmiBackend.set(self, percentage).map_err(|wmi_err| {
+                tracing::debug!("wmi fallback failed for '{}': {}", self.friendly_name, wmi_err);
+                e
+            }),
+            result => result,
+        }
+        .map_err(|e| self.failed("set the brightness", e));
+        self.remember_write(&result, percentage);
+        result
+    }
+
+    /// a written value is as good as a read, so the broadcast right after it doesn't hit ddc/ci.
+    /// a failed write makes the cached read stale, it may have landed anyway
+    fn remember_write<T>(&self, result: &anyhow::Result<T>, percentage: u32) {
+        if let Ok(mut cache) = BRIGHTNESS_CACHE.lock() {
+            match result {
+                Ok(_) => cache.insert(self.id.clone(), (percentage, Instant::now())),
+                Err(_) => cache.remove(&self.id),
+            };
+        }
+        if result.is_ok() {
+            self.last_written.set(percentage);
+        }
+    }
+
+    /// the ddc/ci capabilities string, requested once per monitor
+    pub fn capabilities_string(&self) -> anyhow::Result<String> {
+        self.ensure_ddcci(VCP_LUMINANCE)?;
+        if let Some(caps) = CAPABILITIES.lock().ok().and_then(|c| c.get(&self.id).cloned()) {
+            return Ok(caps);
+        }
+        let caps = brightness::ddcci_get_capabilities(self)
+            .map_err(|e| self.failed("read the ddc/ci capabilities", e))?;
+        tracing::debug!("capabilities of '{}': {}", self.friendly_name, caps);
+        if let Ok(mut cache) = CAPABILITIES.lock() {
+            cache.insert(self.id.clone(), caps.clone());
+        }
+        Ok(caps)
+    }
+
+    pub fn capabilities(&self) -> anyhow::Result<brightness::Capabilities> {
+        Ok(brightness::Capabilities::parse(&self.capabilities_string()?))
+    }
+
+    /// luminance target through vcp `0x10`, for monitors advertising it in their capabilities.
+    /// `max_nits` is what the top of the range shows, returns the raw vcp value written
+    pub fn set_nits(&self, nits: u32, max_nits: f64) -> anyhow::Result<u32> {
+        if !self.capabilities()?.supports(VCP_LUMINANCE) {
+            bail!(
+                "'{}' doesn't advertise luminance (vcp {:#04x}) in its ddc/ci capabilities",
+                self.friendly_name, VCP_LUMINANCE
+            );
+        }
+        let (_, max) = brightness::ddcci_get_vcp(self, VCP_LUMINANCE)
+            .map_err(|e| self.failed("read the luminance range", e))?;
+        if max == 0 {
+            bail!("'{}' reports an empty luminance range", self.friendly_name);
+        }
+        let floor = (max as f64 * min_brightness(&self.id) as f64 / 100.0).round();
+        let value = (nits as f64 / max_nits * max as f64).round().clamp(floor, max as f64) as u32;
+        tracing::debug!("{} nits of {} max -> vcp {}/{} for '{}'", nits, max_nits, value, max, self.friendly_name);
+
+        next_ramp(&self.id);
+        let result = brightness::ddcci_set_monitor_brightness(self, value)
+            .map_err(|e| self.failed("set the luminance", e));
+        self.remember_write(&result, (value as f64 / max as f64 * 100.0).round() as u32);
+        result.map(|_| value)
+    }
+
+    /// contrast percentage, only external monitors have it over ddc/ci
+    pub fn contrast(&self) -> Option<u32> {
+        if self.is_internal() || mock::is_mock(&self.id) {
+            return None;
+        }
+        brightness::ddcci_get_monitor_contrast(self)
+            .map_err(|e| tracing::debug!("no contrast on '{}': {:?}", self.friendly_name, e))
+            .ok()
+    }
+
+    /// contrast read within `ttl` if there's one, otherwise a fresh `contrast`
+    pub fn contrast_cached(&self, ttl: Duration) -> Option<u32> {
+        if let Ok(cache) = CONTRAST_CACHE.lock() {
+            if let Some(&(value, read_at)) = cache.get(&self.id) {
+                if read_at.elapsed() < ttl {
+                    return value;
+                }
+            }
+        }
+        let value = self.contrast();
+        if let Ok(mut cache) = CONTRAST_CACHE.lock() {
+            cache.insert(self.id.clone(), (value, Instant::now()));
+        }
+        value
+    }
+
+    /// set contrast percentage, fails on internal displays
+    pub fn set_contrast(&self, percentage: u32) -> anyhow::Result<()> {
+        if self.is_internal() || mock::is_mock(&self.id) {
+            return Err(anyhow!("'{}' has no ddc/ci contrast control", self.friendly_name));
+        }
+        let result = brightness::ddcci_set_monitor_contrast(self, percentage.min(100));
+        if let Ok(mut cache) = CONTRAST_CACHE.lock() {
+            cache.remove(&self.id);
+        }
+        result.map_err(|e| self.failed("set the contrast", e))
+    }
+
+    /// raw ddc/ci feature read, `(current, max)`
+    pub fn get_vcp(&self, code: u8) -> anyhow::Result<(u32, u32)> {
+        self.ensure_ddcci(code)?;
+        brightness::ddcci_get_vcp(self, code).map_err(|e| self.failed(&format!("read vcp {:#04x}", code), e))
+    }
+
+    /// raw ddc/ci feature write, nothing is validated beyond the monitor accepting it
+    pub fn set_vcp(&self, code: u8, value: u32) -> anyhow::Result<()> {
+        self.ensure_ddcci(code)?;
+        brightness::ddcci_set_vcp(self, code, value).map_err(|e| self.failed(&format!("write vcp {:#04x}", code), e))
+    }
+
+    /// switches the monitor to another input, see `INPUT_HDMI1` & friends.
+    /// the monitor usually drops off this machine right after
+    pub fn switch_input(&self, input: u8) -> anyhow::Result<()> {
+        tracing::info!(
+            "switching '{}' to input {:#04x} ({})",
+            self.friendly_name, input, input_source_name(input).unwrap_or("unknown")
+        );
+        self.set_vcp(VCP_INPUT_SOURCE, input as u32)
+    }
+
+    fn ensure_ddcci(&self, code: u8) -> anyhow::Result<()> {
+        if self.is_internal() || mock::is_mock(&self.id) {
+            return Err(anyhow!(
+                "vcp {:#04x} unavailable, '{}' isn't a ddc/ci monitor",
+                code, self.friendly_name
+            ));
+        }
+        Ok(())
+    }
+
+    /// darken the monitor beyond its hardware minimum with the given backend,
+    /// `level` is an alpha value: 0 is untouched, 255 is fully dark
+    pub async fn dim(
+        &self, level: u8,
+        backend: DimBackend,
+        overlay_tx: &Sender<Overlay>
+    ) -> anyhow::Result<()> {
+        match backend {
+            DimBackend::Overlay => {
+                // overlay windows are keyed by the gdi name
+                overlay_tx.send(Overlay {
+                    level,
+                    device_name: gdi_device_name(&self.device_name).to_string(),
+                }).await?;
+            }
+            DimBackend::Gamma => gamma::dim_brightness(level, &self.device_name)?,
+            DimBackend::Both => {
+                let (alpha, multiplier) = split_dim(level);
+                gamma::dim_multiplier(multiplier, &self.device_name)?;
+                overlay_tx.send(Overlay {
+                    level: alpha,
+                    device_name: gdi_device_name(&self.device_name).to_string(),
+                }).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// undo whatever the backend did to the monitor
+    pub async fn clear_dim(
+        &self,
+        backend: DimBackend,
+        overlay_tx: &Sender<Overlay>
+    ) -> anyhow::Result<()> {
+        match backend {
+            DimBackend::Overlay => self.dim(0, backend, overlay_tx).await,
+            DimBackend::Gamma => gamma::reset_gamma(&self.device_name),
+            DimBackend::Both => {
+                gamma::reset_gamma(&self.device_name)?;
+                self.dim(0, DimBackend::Overlay, overlay_tx).await
+            }
+        }
+    }
+
     /// especially for the frontend
     pub async fn slider(
         &self, value: i32,
+        backend: DimBackend,
         overlay_tx: &Sender<Overlay>
     ) -> anyhow::Result<()> { // handle to manage [-100..100]
-        if value >= 0 {
-            self.set(value as u32)?
-        } else {
-            let alpha = ((-value) as f32 * 2.55) as u8;
-            overlay_tx.send(Overlay {
-                level: alpha,
-                device_name: self.device_name.clone(),
-            }).await?;
+        match slider_target(value)? {
+            SliderTarget::Brightness(percentage) => self.set_async(percentage).await,
+            SliderTarget::Dim(alpha) => self.dim(alpha, backend, overlay_tx).await,
+        }
+    }
+
+    /// returns what was actually applied, presets & the gamma range snap the kelvin
+    pub fn set_color_temperature(&self, temp: ColorTemp) -> anyhow::Result<ColorTemp> {
+        let kelvin = match temp.mechanism {
+            ColorTempMechanism::Ddcci => brightness::ddcci_set_color_preset(self, temp.kelvin)?,
+            ColorTempMechanism::Gamma => gamma::set_color_temperature(temp.kelvin, &self.device_name)?,
+        };
+        Ok(ColorTemp { kelvin, ..temp })
+    }
+
+    /// like `set_color_temperature` but the gamma tint eases over `duration` in the background,
+    /// ddc/ci presets aren't continuous so they snap
+    pub fn transition_color_temperature(&self, temp: ColorTemp, duration: Duration) -> anyhow::Result<ColorTemp> {
+        match temp.mechanism {
+            ColorTempMechanism::Gamma if !duration.is_zero() => {
+                let kelvin = gamma::spawn_color_transition(temp.kelvin, self.device_name.clone(), duration);
+                Ok(ColorTemp { kelvin, ..temp })
+            }
+            _ => self.set_color_temperature(temp),
         }
-        Ok(())
     }
 }
 
 
 /// it consumes `monitorDevicePath` for both ddc/ci and ioctl devices
-pub fn get_monitors() -> anyhow::Result<Vec<MonitorDeviceImpl>> {
+pub fn get_monitors() -> Result<Vec<MonitorDeviceImpl>, MonitorError> {
+    if mock::enabled() {
+        return Ok(mock::get_monitors());
+    }
     unsafe {
         let mut path_count: u32 = 0;
         let mut mode_count: u32 = 0;
 
-        // errors are in win error code, todo: format error
         let err = GetDisplayConfigBufferSizes(QDC_ONLY_ACTIVE_PATHS, &mut path_count, &mut mode_count);
         if err != ERROR_SUCCESS {
-            return Err(anyhow!("`GetDisplayConfigBufferSizes` failed: {:?}", err));
+            return Err(err.into());
         }
 
         let mut paths = vec![DISPLAYCONFIG_PATH_INFO::default(); path_count as usize];
@@ -397,7 +1199,7 @@ pub fn get_monitors() -> anyhow::Result<Vec<MonitorDeviceImpl>> {
         );
 
         if err != ERROR_SUCCESS {
-            return Err(anyhow!("`QueryDisplayConfig` failed: {:?}", err));
+            return Err(err.into());
         }
 
         let mut monitors = Vec::new();
@@ -452,12 +1254,18 @@ pub fn get_monitors() -> anyhow::Result<Vec<MonitorDeviceImpl>> {
                         | DISPLAYCONFIG_OUTPUT_TECHNOLOGY_LVDS
                         | DISPLAYCONFIG_OUTPUT_TECHNOLOGY_DISPLAYPORT_EMBEDDED
                     ) {
-                        let mut adapter = DISPLAY_DEVICEW {
-                            cb: size_of::<DISPLAY_DEVICEW>() as u32,
-                            ..Default::default()
-                        };
-                        if EnumDisplayDevicesW(PCWSTR::null(), 0, &mut adapter, 0).as_bool() {
-                            device_name = wchar_to_string(&adapter.DeviceName);
+                        // the first adapter isn't always the one driving the internal panel
+                        match device_name_for_path(&device_path) {
+                            Some(name) => device_name = name,
+                            None => {
+                                let mut adapter = DISPLAY_DEVICEW {
+                                    cb: size_of::<DISPLAY_DEVICEW>() as u32,
+                                    ..Default::default()
+                                };
+                                if EnumDisplayDevicesW(PCWSTR::null(), 0, &mut adapter, 0).as_bool() {
+                                    device_name = wchar_to_string(&adapter.DeviceName);
+                                }
+                            }
                         }
                         get_handler_from_device_path(&device_path)?
                             .unwrap_or(SafeDisplayHandle(HANDLE(ptr::null_mut())))
@@ -470,28 +1278,40 @@ pub fn get_monitors() -> anyhow::Result<Vec<MonitorDeviceImpl>> {
                         let mut found: Option<SafePhysicalMonitor> = None;
                         for hm in enum_display_monitors()? {
                             let devices = get_display_devices_from_hmonitor(hm)?;
-                            let pms = get_physical_monitors_from_hmonitor(hm)?;
-                            if devices.len() != pms.len() {
-                                // there doesn't seem to be any way to directly associate a physical monitor
-                                // handle with the equivalent display device, other than by array indexing
-                                // https://stackoverflow.com/questions/63095216/how-to-associate-physical-monitor-with-monitor-deviceid
-                                return Err(
-                                    anyhow!(
-                                    "the length of `get_display_devices_from_hmonitor()` and `get_physical_monitors_from_hmonitor()` results did not \n
-                                    match, this could be because monitors were connected/disconnected while loading devices"
-                                ));
-                            }
-                            for (dev, pm) in devices.into_iter().zip(pms.into_iter()) {
-                                let path = wchar_to_string(&dev.DeviceID);
-                                if path == device_path {
-                                    device_name = wchar_to_string(&dev.DeviceName);
-                                    found = Some(pm);
-                                    break;
-                                }
+                            let mut pms = get_physical_monitors_from_hmonitor(hm)?;
+                            let Some(idx) = devices.iter()
+                                .position(|dev| wchar_to_string(&dev.DeviceID) == device_path)
+                            else {
+                                continue;
+                            };
+                            device_name = wchar_to_string(&devices[idx].DeviceName);
+
+                            // there doesn't seem to be any way to directly associate a physical monitor
+                            // handle with the equivalent display device, other than by array indexing
+                            // https://stackoverflow.com/questions/63095216/how-to-associate-physical-monitor-with-monitor-deviceid
+                            if devices.len() > 1 {
+                                tracing::debug!(
+                                    "'{}' is one of {} monitors in duplicate mode, grouped as clones of {}",
+                                    name, devices.len(), gdi_device_name(&device_name)
+                                );
                             }
-                            if found.is_some() {
-                                break;
+                            if devices.len() == pms.len() {
+                                found = Some(pms.swap_remove(idx));
+                            } else if pms.len() == 1 {
+                                // a single physical monitor can only be the matched device's
+                                tracing::warn!(
+                                    "{} display devices but 1 physical monitor for '{}', assuming it's the matched one",
+                                    devices.len(), name
+                                );
+                                found = pms.pop();
+                            } else {
+                                // indexes can't be trusted, still usable through the overlay
+                                tracing::warn!(
+                                    "{} display devices but {} physical monitors for '{}', falling back to overlay only",
+                                    devices.len(), pms.len(), name
+                                );
                             }
+                            break;
                         }
                         found.unwrap_or(SafePhysicalMonitor(HANDLE(ptr::null_mut())))
                     } else {
@@ -510,6 +1330,169 @@ pub fn get_monitors() -> anyhow::Result<Vec<MonitorDeviceImpl>> {
             }
         }
 
+        // one enumeration for every monitor, it's read on each rescan
+        if let Ok(rects) = monitor_rects() {
+            for dev in monitors.iter_mut() {
+                dev.dpi = dev.dpi_in(&rects);
+            }
+        }
         Ok(monitors)
     }
 }
+
+/// a monitor known to windows, active or not
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct DisplayEntry {
+    /// `monitorDevicePath`, same as `MonitorInfo::id` for active monitors
+    pub id: String,
+    pub name: String,
+    /// inactive monitors are connected but disabled, brightness can't be controlled
+    pub active: bool,
+}
+
+/// every connected monitor, including the ones disabled in the display settings.
+/// `QDC_ALL_PATHS` lists each source/target combination, so targets are deduplicated by path
+pub fn list_all_monitors() -> anyhow::Result<Vec<DisplayEntry>> {
+    unsafe {
+        let mut path_count: u32 = 0;
+        let mut mode_count: u32 = 0;
+
+        let err = GetDisplayConfigBufferSizes(QDC_ALL_PATHS, &mut path_count, &mut mode_count);
+        if err != ERROR_SUCCESS {
+            return Err(anyhow!("`GetDisplayConfigBufferSizes` failed: {:?}", err));
+        }
+
+        let mut paths = vec![DISPLAYCONFIG_PATH_INFO::default(); path_count as usize];
+        let mut modes = vec![DISPLAYCONFIG_MODE_INFO::default(); mode_count as usize];
+
+        let err = QueryDisplayConfig(
+            QDC_ALL_PATHS,
+            &mut path_count,
+            paths.as_mut_ptr(),
+            &mut mode_count,
+            modes.as_mut_ptr(),
+            None,
+        );
+        if err != ERROR_SUCCESS {
+            return Err(anyhow!("`QueryDisplayConfig` failed: {:?}", err));
+        }
+        paths.truncate(path_count as usize);
+
+        let mut entries: Vec<DisplayEntry> = Vec::new();
+        for path in paths.iter().filter(|p| p.targetInfo.targetAvailable.as_bool()) {
+            let mut target: DISPLAYCONFIG_TARGET_DEVICE_NAME = std::mem::zeroed();
+            target.header = DISPLAYCONFIG_DEVICE_INFO_HEADER {
+                r#type: DISPLAYCONFIG_DEVICE_INFO_GET_TARGET_NAME,
+                size: size_of::<DISPLAYCONFIG_TARGET_DEVICE_NAME>() as u32,
+                adapterId: path.targetInfo.adapterId,
+                id: path.targetInfo.id,
+            };
+            if DisplayConfigGetDeviceInfo(&mut target as *mut _ as *mut _) != ERROR_SUCCESS.0 as i32 {
+                continue;
+            }
+
+            let id = wchar_to_string(&target.monitorDevicePath);
+            if id.is_empty() {
+                continue;
+            }
+            let active = flag_set(path.flags, DISPLAYCONFIG_PATH_ACTIVE);
+
+            match entries.iter_mut().find(|e| e.id == id) {
+                Some(entry) => entry.active |= active,
+                None => {
+                    let friendly = wchar_to_string(&target.monitorFriendlyDeviceName);
+                    let name = if !friendly.trim().is_empty() {
+                        friendly
+                    } else if matches!(
+                        target.outputTechnology,
+                        DISPLAYCONFIG_OUTPUT_TECHNOLOGY_INTERNAL
+                        | DISPLAYCONFIG_OUTPUT_TECHNOLOGY_LVDS
+                        | DISPLAYCONFIG_OUTPUT_TECHNOLOGY_DISPLAYPORT_EMBEDDED
+                    ) {
+                        "Internal Display".to_string()
+                    } else {
+                        "Unknown Display".to_string()
+                    };
+                    entries.push(DisplayEntry { id, name, active });
+                }
+            }
+        }
+
+        Ok(entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use windows::Win32::Devices::Display::DISPLAYCONFIG_OUTPUT_TECHNOLOGY_HDMI;
+
+    fn device(id: &str, device_name: &str) -> MonitorDeviceImpl {
+        MonitorDeviceImpl::new(
+            id.to_string(),
+            device_name.to_string(),
+            id.to_string(),
+            Arc::new(SafeDisplayHandle(HANDLE(ptr::null_mut()))),
+            Arc::new(SafePhysicalMonitor(HANDLE(ptr::null_mut()))),
+            DISPLAYCONFIG_OUTPUT_TECHNOLOGY_HDMI,
+        )
+    }
+
+    fn names<'a>(devices: &[&'a MonitorDeviceImpl]) -> Vec<&'a str> {
+        devices.iter().map(|d| d.device_name.as_str()).collect()
+    }
+
+    #[test]
+    fn gdi_device_name_strips_the_monitor() {
+        assert_eq!(gdi_device_name(r"\\.\DISPLAY1\Monitor0"), r"\\.\DISPLAY1");
+        assert_eq!(gdi_device_name(r"\\.\DISPLAY1"), r"\\.\DISPLAY1");
+        assert_eq!(gdi_device_name(""), "");
+    }
+
+    #[test]
+    fn clones_share_a_gdi_device() {
+        let devices = [
+            device("a", r"\\.\MOCK1\Monitor0"),
+            device("b", r"\\.\MOCK1\Monitor1"),
+            device("c", r"\\.\MOCK3"),
+        ];
+        assert_eq!(names(&clone_peers(&devices, &devices[0])), [r"\\.\MOCK1\Monitor1"]);
+        assert_eq!(names(&clone_peers(&devices, &devices[1])), [r"\\.\MOCK1\Monitor0"]);
+        assert!(clone_peers(&devices, &devices[2]).is_empty());
+    }
+
+    #[test]
+    fn overlay_windows_are_matched_by_gdi_device() {
+        let devices = [
+            device("a", r"\\.\DISPLAY1\Monitor0"),
+            device("b", r"\\.\DISPLAY1\Monitor1"),
+            device("c", r"\\.\DISPLAY2\Monitor0"),
+        ];
+        let overlays = [r"\\.\DISPLAY1".to_string()];
+        assert_eq!(names(&devices_without_overlay(&devices, &overlays)), [r"\\.\DISPLAY2\Monitor0"]);
+        assert_eq!(devices_without_overlay(&devices, &[]).len(), 3);
+        let overlays = [r"\\.\DISPLAY1".to_string(), r"\\.\DISPLAY2".to_string()];
+        assert!(devices_without_overlay(&devices, &overlays).is_empty());
+    }
+
+    #[test]
+    fn slider_target_bounds() {
+        assert!(slider_target(-101).is_err());
+        assert_eq!(slider_target(-100).unwrap(), SliderTarget::Dim(255));
+        assert_eq!(slider_target(-1).unwrap(), SliderTarget::Dim(2));
+        assert_eq!(slider_target(0).unwrap(), SliderTarget::Brightness(0));
+        assert_eq!(slider_target(100).unwrap(), SliderTarget::Brightness(100));
+        assert!(slider_target(101).is_err());
+    }
+
+    #[test]
+    fn brightness_is_clamped_to_the_floor() {
+        set_min_brightness(HashMap::from([(r"\\?\FLOORED".to_string(), 20)]));
+        assert_eq!(clamp_brightness(r"\\?\ANY", 0), DEFAULT_MIN_BRIGHTNESS);
+        assert_eq!(clamp_brightness(r"\\?\ANY", 5), 5);
+        assert_eq!(clamp_brightness(r"\\?\ANY", 150), 100);
+        assert_eq!(clamp_brightness(r"\\?\FLOORED", 0), 20);
+        assert_eq!(clamp_brightness(r"\\?\FLOORED", 60), 60);
+        set_min_brightness(HashMap::new());
+    }
+}
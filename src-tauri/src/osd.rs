@@ -0,0 +1,153 @@
+/*
+ * small brightness osd, like the native volume/brightness flyout
+ * lives on the overlay thread, so it shares its message loop
+*/
+use std::{
+    iter,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+use tracing::warn;
+use windows::{
+    core::w,
+    Win32::{
+        Foundation::{HWND, HINSTANCE, LPARAM, LRESULT, RECT, WPARAM, COLORREF},
+        Graphics::Gdi::{
+            BeginPaint, EndPaint, FillRect, CreateSolidBrush, DeleteObject, DrawTextW, InvalidateRect,
+            SetBkMode, SetTextColor, PAINTSTRUCT, DT_CENTER, DT_SINGLELINE, DT_VCENTER, TRANSPARENT,
+        },
+        UI::WindowsAndMessaging::{
+            CreateWindowExW, DefWindowProcW, DestroyWindow, RegisterClassExW, SetLayeredWindowAttributes, SetWindowPos,
+            ShowWindow, HWND_TOPMOST, LWA_ALPHA, SWP_NOACTIVATE, SW_HIDE, SW_SHOWNOACTIVATE, WM_PAINT,
+            WNDCLASSEXW, WS_EX_LAYERED, WS_EX_NOACTIVATE, WS_EX_TOOLWINDOW, WS_EX_TOPMOST, WS_EX_TRANSPARENT,
+            WS_POPUP,
+        },
+    }
+};
+use crate::monitors::{gdi_device_name, monitor_rects};
+
+const OSD_WIDTH: i32 = 280;
+const OSD_HEIGHT: i32 = 64;
+/// distance from the bottom edge of the monitor
+const OSD_MARGIN: i32 = 96;
+const OSD_ALPHA: u8 = 230;
+const OSD_TIMEOUT: Duration = Duration::from_millis(1500);
+
+/// what the osd currently shows, read by `wnd_proc` on paint
+static CONTENT: Mutex<Option<(String, u32)>> = Mutex::new(None);
+
+pub struct Osd {
+    hwnd: HWND,
+    hide_at: Option<Instant>,
+}
+
+impl Osd {
+    pub fn new(instance: HINSTANCE) -> anyhow::Result<Self> {
+        unsafe {
+            let class_name = w!("FadeOsd");
+            let wc = WNDCLASSEXW {
+                cbSize: size_of::<WNDCLASSEXW>() as u32,
+                lpfnWndProc: Some(wnd_proc),
+                hInstance: instance,
+                lpszClassName: class_name,
+                ..Default::default()
+            };
+            RegisterClassExW(&wc);
+
+            // click through & never activated, so it can't steal focus
+            let hwnd = CreateWindowExW(
+                WS_EX_LAYERED | WS_EX_TRANSPARENT | WS_EX_TOPMOST | WS_EX_TOOLWINDOW | WS_EX_NOACTIVATE,
+                class_name,
+                w!(""),
+                WS_POPUP,
+                0, 0, OSD_WIDTH, OSD_HEIGHT,
+                None,
+                None,
+                Some(instance),
+                None
+            )?;
+            SetLayeredWindowAttributes(hwnd, COLORREF(0), OSD_ALPHA, LWA_ALPHA)?;
+
+            Ok(Self { hwnd, hide_at: None })
+        }
+    }
+
+    /// shows `label` & a brightness bar on the monitor of `device_name`
+    pub fn show(&mut self, device_name: &str, label: String, level: u32) -> anyhow::Result<()> {
+        let rects = monitor_rects()?;
+        let Some(rect) = rects.get(gdi_device_name(device_name)) else {
+            warn!("no monitor rect for osd, device: {}", device_name);
+            return Ok(());
+        };
+
+        if let Ok(mut content) = CONTENT.lock() {
+            *content = Some((label, level.min(100)));
+        }
+
+        let x = rect.left + (rect.right - rect.left - OSD_WIDTH) / 2;
+        let y = rect.bottom - OSD_HEIGHT - OSD_MARGIN;
+        unsafe {
+            SetWindowPos(self.hwnd, Some(HWND_TOPMOST), x, y, OSD_WIDTH, OSD_HEIGHT, SWP_NOACTIVATE)?;
+            let _ = InvalidateRect(Some(self.hwnd), None, true);
+            let _ = ShowWindow(self.hwnd, SW_SHOWNOACTIVATE);
+        }
+        self.hide_at = Some(Instant::now() + OSD_TIMEOUT);
+        Ok(())
+    }
+
+    /// called every tick of the overlay loop
+    pub fn tick(&mut self) {
+        if self.hide_at.is_some_and(|at| Instant::now() >= at) {
+            self.hide_at = None;
+            unsafe { let _ = ShowWindow(self.hwnd, SW_HIDE); }
+        }
+    }
+
+    pub fn destroy(self) {
+        unsafe { let _ = DestroyWindow(self.hwnd); }
+    }
+}
+
+extern "system" fn wnd_proc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    unsafe {
+        match msg {
+            WM_PAINT => {
+                let mut ps = PAINTSTRUCT::default();
+                let hdc = BeginPaint(hwnd, &mut ps);
+
+                let background = CreateSolidBrush(COLORREF(0x0020_2020));
+                let track = CreateSolidBrush(COLORREF(0x0050_5050));
+                let fill = CreateSolidBrush(COLORREF(0x00ff_ffff));
+                let full = RECT { left: 0, top: 0, right: OSD_WIDTH, bottom: OSD_HEIGHT };
+                FillRect(hdc, &full, background);
+
+                if let Some((label, level)) = CONTENT.lock().ok().and_then(|c| c.clone()) {
+                    let mut text: Vec<u16> = format!("{}  {}%", label, level)
+                        .encode_utf16()
+                        .chain(iter::once(0))
+                        .collect();
+                    let len = text.len() - 1;
+                    let mut text_rect = RECT { left: 12, top: 6, right: OSD_WIDTH - 12, bottom: 36 };
+                    SetBkMode(hdc, TRANSPARENT);
+                    SetTextColor(hdc, COLORREF(0x00ff_ffff));
+                    DrawTextW(hdc, &mut text[..len], &mut text_rect, DT_CENTER | DT_VCENTER | DT_SINGLELINE);
+
+                    let bar = RECT { left: 16, top: 44, right: OSD_WIDTH - 16, bottom: 52 };
+                    FillRect(hdc, &bar, track);
+                    let filled = RECT {
+                        right: bar.left + (bar.right - bar.left) * level as i32 / 100,
+                        ..bar
+                    };
+                    FillRect(hdc, &filled, fill);
+                }
+
+                for brush in [background, track, fill] {
+                    let _ = DeleteObject(brush.into());
+                }
+                let _ = EndPaint(hwnd, &ps);
+                LRESULT(0)
+            }
+            _ => DefWindowProcW(hwnd, msg, wparam, lparam),
+        }
+    }
+}
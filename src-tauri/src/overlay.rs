@@ -1,31 +1,44 @@
 use anyhow::{anyhow, bail};
-use std::collections::HashMap;
+use std::{
+    time::Instant,
+    collections::{HashMap, HashSet, VecDeque},
+    sync::{Mutex, LazyLock, atomic::{AtomicBool, Ordering}},
+};
+use serde::Serialize;
 use tracing::{warn, debug, info, error};
 use tokio::{
-    sync::mpsc::Receiver,
+    sync::{Notify, mpsc::Receiver},
     time::{sleep, Duration}
 };
 use windows::{
-    core::{w, BOOL},
+    core::{w, BOOL, PCWSTR},
     Win32::{
         Foundation::{
             HWND, LPARAM, LRESULT, POINT, RECT, WPARAM, COLORREF, HINSTANCE, GetLastError, ERROR_CLASS_ALREADY_EXISTS,
+            HANDLE,
         },
         Graphics::Gdi::{
             HDC, HMONITOR, BeginPaint, EndPaint, EnumDisplayMonitors, FillRect, GetMonitorInfoW, GetStockObject, 
-            MonitorFromPoint, BLACK_BRUSH, MONITORINFO, MONITOR_DEFAULTTOPRIMARY, PAINTSTRUCT, HBRUSH, MONITORINFOEXW
+            MonitorFromPoint, BLACK_BRUSH, MONITORINFO, MONITOR_DEFAULTTOPRIMARY, PAINTSTRUCT, HBRUSH, MONITORINFOEXW,
+            CreateSolidBrush, DeleteObject, InvalidateRect,
         },
         UI::WindowsAndMessaging::{
-            CreateWindowExW, DefWindowProcW, DispatchMessageW, GetMessageW, RegisterClassW,
+            CreateWindowExW, DefWindowProcW, DestroyWindow, DispatchMessageW, GetMessageW, RegisterClassW,
             SetLayeredWindowAttributes, ShowWindow, TranslateMessage, LWA_ALPHA, MSG, SW_SHOW,
             WNDCLASSW, WS_EX_LAYERED, WS_EX_TOPMOST, WS_EX_TOOLWINDOW, WS_EX_NOACTIVATE, PeekMessageW,
             RegisterClassExW, GetClassInfoExW, WM_QUIT, WS_POPUP, PM_REMOVE, WS_VISIBLE, PostQuitMessage,
-            WS_EX_TRANSPARENT, WNDCLASSEXW, WM_PAINT, 
+            WS_EX_TRANSPARENT, WNDCLASSEXW, WM_PAINT, WM_DISPLAYCHANGE, WM_HOTKEY, SetWindowPos, HWND_TOPMOST,
+            SWP_NOACTIVATE, SWP_NOREDRAW, WM_POWERBROADCAST, PBT_POWERSETTINGCHANGE, DEVICE_NOTIFY_WINDOW_HANDLE,
+            PBT_APMRESUMEAUTOMATIC, PBT_APMRESUMESUSPEND, GetWindowLongPtrW, SetWindowLongPtrW, GWL_EXSTYLE,
+        },
+        System::{
+            LibraryLoader::GetModuleHandleW,
+            Power::{RegisterPowerSettingNotification, UnregisterPowerSettingNotification, HPOWERNOTIFY, POWERBROADCAST_SETTING},
+            SystemServices::GUID_CONSOLE_DISPLAY_STATE,
         },
-        System::LibraryLoader::GetModuleHandleW
     }
 };
-use crate::{utils::format_win_err, monitors::{enum_display_monitors, get_monitors}};
+use crate::{osd::Osd, privacy, privacy::PrivacyScreen, utils::format_win_err, monitors::{enum_display_monitors, get_monitors, gdi_device_name, monitor_rects}};
 
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -34,11 +47,299 @@ pub struct Overlay {
     pub device_name: String,
 }
 
+/// control messages for the overlay thread, separate from the dim levels
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OverlayControl {
+    /// clear the dim of a single device without forgetting its level,
+    /// the previously revealed device gets its level back. `None` reveals nothing
+    Reveal(Option<String>),
+    /// briefly show the brightness osd on a device
+    Osd { device_name: String, label: String, level: u32 },
+    /// fill color of a device's overlay as `[r, g, b]`, the dim level is its opacity
+    SetColor { device_name: String, color: [u8; 3] },
+    /// opaque, input blocking windows over every monitor, see `privacy.rs`
+    Privacy(bool),
+    /// `false` makes a device's overlay take mouse input instead of passing it through, `None` is
+    /// every device. on a fully opaque overlay that locks the screen, the tray's "Release input"
+    /// puts every overlay back to click-through. new windows always start click-through
+    ClickThrough { device_name: Option<String>, enabled: bool },
+    /// destroys every window & ends the overlay loop, `STOPPED` is notified once done
+    Shutdown,
+}
+
+/// fill brush per overlay window (keyed by `HWND`), read by `wnd_proc` on paint.
+/// created once per color change instead of every paint, missing windows are painted black
+static BRUSHES: LazyLock<Mutex<HashMap<usize, Brush>>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// `HBRUSH` kept as an integer, raw handles aren't `Send`
+struct Brush {
+    color: COLORREF,
+    handle: usize,
+}
+
+fn set_color(windows: &HashMap<String, HWND>, device_name: &str, [r, g, b]: [u8; 3]) {
+    let Some(&hwnd) = windows.get(gdi_device_name(device_name)) else {
+        warn!("Received overlay color for unknown device: {}", device_name);
+        return;
+    };
+    if let Ok(mut brushes) = BRUSHES.lock() {
+        let color = COLORREF(r as u32 | ((g as u32) << 8) | ((b as u32) << 16));
+        if brushes.get(&(hwnd.0 as usize)).is_some_and(|b| b.color == color) {
+            return;
+        }
+        let handle = unsafe { CreateSolidBrush(color) }.0 as usize;
+        if let Some(old) = brushes.insert(hwnd.0 as usize, Brush { color, handle }) {
+            unsafe { let _ = DeleteObject(HBRUSH(old.handle as *mut _).into()); }
+        }
+    }
+    unsafe { let _ = InvalidateRect(Some(hwnd), None, true); }
+}
+
+/// set by `wnd_proc` on `WM_DISPLAYCHANGE`, the overlay loop relayouts the windows
+static DISPLAY_CHANGED: AtomicBool = AtomicBool::new(false);
+
+/// moves every overlay onto its monitor's current rect & reasserts its alpha right away,
+/// `SWP_NOREDRAW` keeps the stale frame until the alpha is back so nothing flashes opaque
+fn relayout(
+    windows: &HashMap<String, HWND>,
+    levels: &HashMap<String, u8>,
+    revealed: Option<&str>,
+) -> anyhow::Result<()> {
+    let rects = monitor_rects()?;
+    for (device_name, &hwnd) in windows {
+        let Some(rect) = rects.get(device_name) else {
+            debug!("overlay device '{}' is gone, leaving its window as is", device_name);
+            continue;
+        };
+        unsafe {
+            SetWindowPos(
+                hwnd,
+                Some(HWND_TOPMOST),
+                rect.left,
+                rect.top,
+                rect.right - rect.left,
+                rect.bottom - rect.top,
+                SWP_NOACTIVATE | SWP_NOREDRAW,
+            )?;
+        }
+        let level = if revealed == Some(device_name.as_str()) {
+            0
+        } else {
+            levels.get(device_name).copied().unwrap_or(0)
+        };
+        set_alpha(windows, device_name, level)?;
+        unsafe { let _ = InvalidateRect(Some(hwnd), None, true); }
+    }
+    Ok(())
+}
+
+/// `GUID_CONSOLE_DISPLAY_STATE` data values
+const DISPLAY_STATE_OFF: u8 = 0;
+const DISPLAY_STATE_ON: u8 = 1;
+
+/// set by `wnd_proc` when the displays turn back on after being off
+static DISPLAY_WOKE: AtomicBool = AtomicBool::new(false);
+/// set by `wnd_proc` when the system resumed from sleep, along with `DISPLAY_WOKE`
+static RESUMED: AtomicBool = AtomicBool::new(false);
+/// last display state seen, the registration itself reports the current one
+static DISPLAY_WAS_OFF: AtomicBool = AtomicBool::new(false);
+
+/// notified once the displays wake up, see `Settings::wake_behavior`
+pub static WAKE: LazyLock<Notify> = LazyLock::new(Notify::new);
+/// whether the last `WAKE` came from a resume from sleep, cleared by reading it.
+/// monitor handles may have gone stale across it, unlike a display only turning off
+pub fn take_resumed() -> bool {
+    RESUMED.swap(false, Ordering::Relaxed)
+}
+
+/// notified once `OverlayControl::Shutdown` took the windows down
+pub static STOPPED: LazyLock<Notify> = LazyLock::new(Notify::new);
+
+/// notified once the startup overlay windows exist
+pub static CREATED: LazyLock<Notify> = LazyLock::new(Notify::new);
+
+/// gdi device names with an overlay window, for checking them against the managed devices
+static OVERLAY_DEVICES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+pub fn overlay_devices() -> Vec<String> {
+    OVERLAY_DEVICES.lock().map(|d| d.clone()).unwrap_or_default()
+}
+
+/// interval of the overlay loop
+const TICK: Duration = Duration::from_millis(16);
+/// samples kept per measurement, ~4s of ticks at 16ms
+const STATS_WINDOW: usize = 256;
+
+/// rolling timings of the overlay loop, written by it & read by `overlay_stats`
+#[derive(Debug, Default)]
+struct Timings {
+    /// time between the starts of consecutive iterations, in µs
+    ticks: VecDeque<u64>,
+    /// time spent in `SetLayeredWindowAttributes`, in µs
+    alpha: VecDeque<u64>,
+}
+
+static TIMINGS: LazyLock<Mutex<Timings>> = LazyLock::new(|| Mutex::new(Timings::default()));
+
+fn push_sample(samples: &mut VecDeque<u64>, micros: u64) {
+    if samples.len() >= STATS_WINDOW {
+        samples.pop_front();
+    }
+    samples.push_back(micros);
+}
+
+/// average & max over the last `samples` measurements, in ms
+#[derive(Debug, Serialize, Clone, Copy, Default)]
+pub struct TimingSummary {
+    pub samples: usize,
+    pub avg_ms: f64,
+    pub max_ms: f64,
+}
+
+impl TimingSummary {
+    fn of(samples: &VecDeque<u64>) -> Self {
+        if samples.is_empty() {
+            return Self::default();
+        }
+        let total: u64 = samples.iter().sum();
+        Self {
+            samples: samples.len(),
+            avg_ms: total as f64 / samples.len() as f64 / 1000.0,
+            max_ms: samples.iter().copied().max().unwrap_or_default() as f64 / 1000.0,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone, Copy)]
+pub struct OverlayStats {
+    /// the interval the loop aims for
+    pub target_tick_ms: u64,
+    pub tick: TimingSummary,
+    /// only sampled when an alpha is applied, empty while the dim is idle
+    pub set_alpha: TimingSummary,
+}
+
+pub fn overlay_stats() -> OverlayStats {
+    let timings = TIMINGS.lock().unwrap_or_else(|e| e.into_inner());
+    OverlayStats {
+        target_tick_ms: TICK.as_millis() as u64,
+        tick: TimingSummary::of(&timings.ticks),
+        set_alpha: TimingSummary::of(&timings.alpha),
+    }
+}
+
+fn set_alpha(windows: &HashMap<String, HWND>, device_name: &str, level: u8) -> anyhow::Result<()> {
+    if let Some(&hwnd) = windows.get(gdi_device_name(device_name)) {
+        let start = Instant::now();
+        unsafe { SetLayeredWindowAttributes(hwnd, COLORREF(0), level, LWA_ALPHA)? };
+        if let Ok(mut timings) = TIMINGS.lock() {
+            push_sample(&mut timings.alpha, start.elapsed().as_micros() as u64);
+        }
+    } else {
+        warn!("Received overlay update for unknown device: {}", device_name);
+    }
+    Ok(())
+}
+
+const CLASS_NAME: PCWSTR = w!("FadeOverlay");
+
+/// hidden, click-through layered window covering `rect`, fully transparent until a level is set
+unsafe fn create_window(instance: HINSTANCE, rect: RECT) -> anyhow::Result<HWND> {
+    let hwnd = CreateWindowExW(
+        WS_EX_LAYERED | WS_EX_TRANSPARENT | WS_EX_TOPMOST | WS_EX_TOOLWINDOW | WS_EX_NOACTIVATE,
+        CLASS_NAME,
+        w!(""),                             // keep window name empty
+        WS_POPUP,
+        rect.left,
+        rect.top,
+        rect.right - rect.left,
+        rect.bottom - rect.top,
+        None,
+        None,
+        Some(instance),
+        None
+    )?;
+    Ok(hwnd)
+}
+
+/// adds or removes `WS_EX_TRANSPARENT`, without it clicks land on the overlay
+unsafe fn set_click_through(hwnd: HWND, enabled: bool) {
+    let style = GetWindowLongPtrW(hwnd, GWL_EXSTYLE);
+    let transparent = WS_EX_TRANSPARENT.0 as isize;
+    let style = if enabled { style | transparent } else { style & !transparent };
+    SetWindowLongPtrW(hwnd, GWL_EXSTYLE, style);
+}
+
+/// destroys the window & its brush
+unsafe fn destroy_window(hwnd: HWND) {
+    if let Some(brush) = BRUSHES.lock().ok().and_then(|mut b| b.remove(&(hwnd.0 as usize))) {
+        let _ = DeleteObject(HBRUSH(brush.handle as *mut _).into());
+    }
+    if let Err(e) = DestroyWindow(hwnd) {
+        warn!("failed to destroy overlay window: {:?}", e);
+    }
+}
+
+/// creates windows for monitors that showed up & destroys the ones of monitors that are gone,
+/// returns the removed device names. new windows start transparent, the relayout after it
+/// puts their level on
+unsafe fn sync_windows(windows: &mut HashMap<String, HWND>, instance: HINSTANCE) -> anyhow::Result<Vec<String>> {
+    let rects = monitor_rects()?;
+
+    let removed: Vec<String> = windows.keys().filter(|d| !rects.contains_key(*d)).cloned().collect();
+    for device_name in &removed {
+        if let Some(hwnd) = windows.remove(device_name) {
+            destroy_window(hwnd);
+            info!("removed dim overlay of disconnected device: {}", device_name);
+        }
+    }
+
+    for (device_name, rect) in rects {
+        if windows.contains_key(&device_name) {
+            continue;
+        }
+        let hwnd = create_window(instance, rect)?;
+        SetLayeredWindowAttributes(hwnd, COLORREF(0), 0, LWA_ALPHA)?;
+        let _ = ShowWindow(hwnd, SW_SHOW);
+        info!("created dim overlay for new device: {}", device_name);
+        windows.insert(device_name, hwnd);
+    }
+
+    if let Ok(mut overlay_devices) = OVERLAY_DEVICES.lock() {
+        *overlay_devices = windows.keys().cloned().collect();
+    }
+    Ok(removed)
+}
+
+/// registers one of the windows for display on/off changes, returns the registered window & its handle
+unsafe fn register_power_notification(windows: &HashMap<String, HWND>) -> Option<(HWND, HPOWERNOTIFY)> {
+    let &hwnd = windows.values().next()?;
+    match RegisterPowerSettingNotification(HANDLE(hwnd.0), &GUID_CONSOLE_DISPLAY_STATE, DEVICE_NOTIFY_WINDOW_HANDLE) {
+        Ok(handle) => Some((hwnd, handle)),
+        Err(e) => {
+            warn!("failed to register for display power changes, wake handling disabled: {:?}", e);
+            None
+        }
+    }
+}
+
+/// the registration outlives its window, it has to be undone even after the window is gone
+unsafe fn unregister_power_notification(power: Option<(HWND, HPOWERNOTIFY)>) {
+    if let Some((_, handle)) = power {
+        if let Err(e) = UnregisterPowerSettingNotification(handle) {
+            warn!("failed to unregister display power changes: {:?}", e);
+        }
+    }
+}
+
 /// message overlay thread will listen for.
 /// it's an alpha value: 0 is transparent, 255 is fully opaque.
-pub async fn init_overlay(mut rx: Receiver<Overlay>) -> anyhow::Result<()> {
+pub async fn init_overlay(
+    mut rx: Receiver<Overlay>,
+    mut ctl_rx: Receiver<OverlayControl>,
+) -> anyhow::Result<()> {
     unsafe {
-        let class_name = w!("FadeOverlay");
+        let class_name = CLASS_NAME;
         let instance = GetModuleHandleW(None)?;
 
         let wc = WNDCLASSEXW {
@@ -79,21 +380,7 @@ pub async fn init_overlay(mut rx: Receiver<Overlay>) -> anyhow::Result<()> {
                 let device_name = String::from_utf16_lossy(&info_ex.szDevice)
                     .trim_end_matches('\0')
                     .to_string();
-                let info = info_ex.monitorInfo;
-                let hwnd = CreateWindowExW(
-                    WS_EX_LAYERED | WS_EX_TRANSPARENT | WS_EX_TOPMOST | WS_EX_TOOLWINDOW | WS_EX_NOACTIVATE,
-                    class_name,
-                    w!(""),                             // keep window name empty
-                    WS_POPUP,
-                    info.rcMonitor.left,
-                    info.rcMonitor.top,
-                    info.rcMonitor.right - info.rcMonitor.left,
-                    info.rcMonitor.bottom - info.rcMonitor.top,
-                    None,
-                    None,
-                    Some(instance.into()),
-                    None
-                )?;
+                let hwnd = create_window(instance.into(), info_ex.monitorInfo.rcMonitor)?;
 
                 windows.insert(device_name.clone(), hwnd);
                 debug!("created dim overlay for device: {}", device_name);
@@ -104,56 +391,221 @@ pub async fn init_overlay(mut rx: Receiver<Overlay>) -> anyhow::Result<()> {
         }
 
         debug!("overlay windows created: {:?}, {:?}", windows.keys(), windows);
+        if let Ok(mut overlay_devices) = OVERLAY_DEVICES.lock() {
+            *overlay_devices = windows.keys().cloned().collect();
+        }
+        CREATED.notify_one();
 
         for &hwnd in windows.values() {
             SetLayeredWindowAttributes(hwnd, COLORREF(0), 0, LWA_ALPHA)?;
             ShowWindow(hwnd, SW_SHOW);
         }
+
+        // display on/off is system wide, one window is enough to hear about it
+        let mut power = register_power_notification(&windows);
         
         // for &hwnd in &windows {
         //     SetLayeredWindowAttributes(hwnd, COLORREF(0), 0, LWA_ALPHA)?;
         //     ShowWindow(hwnd, SW_SHOW);
         // }
 
+        // requested level per device & the one currently revealed
+        let mut levels: HashMap<String, u8> = HashMap::new();
+        let mut revealed: Option<String> = None;
+        // requested color per device, a monitor's window may only show up after its color was sent
+        let mut colors: HashMap<String, [u8; 3]> = HashMap::new();
+        // devices whose overlay takes input, only for the log & to forget disconnected ones
+        let mut input_blocking: HashSet<String> = HashSet::new();
+
+        let mut privacy = PrivacyScreen::default();
+        let mut osd = Osd::new(instance.into())
+            .map_err(|e| error!("failed to create osd window: {:?}", e))
+            .ok();
+
         let mut msg = MSG::default();
+        let mut last_tick: Option<Instant> = None;
         loop {
+            let now = Instant::now();
+            if let Some(last) = last_tick.replace(now) {
+                if let Ok(mut timings) = TIMINGS.lock() {
+                    push_sample(&mut timings.ticks, now.duration_since(last).as_micros() as u64);
+                }
+            }
+
             if let Ok(overlay) = rx.try_recv() {
                 // debug!("alpha value received: {:#?}", overlay);
                 info!("alpha value received for device '{}': {}", &overlay.device_name, overlay.level);
-                if let Some(&hwnd) = windows.get(&overlay.device_name) {
-                    SetLayeredWindowAttributes(hwnd, COLORREF(0), overlay.level, LWA_ALPHA)?;
-                } else {
-                    warn!("Received overlay update for unknown device: {}", &overlay.device_name);
+                levels.insert(overlay.device_name.clone(), overlay.level);
+                if revealed.as_deref() != Some(overlay.device_name.as_str()) {
+                    set_alpha(&windows, &overlay.device_name, overlay.level)?;
                 }
                 // for &hwnd in &windows {
                 //     SetLayeredWindowAttributes(hwnd, COLORREF(0), overlay.level, LWA_ALPHA)?;
                 // }
             }
 
+            if let Ok(control) = ctl_rx.try_recv() {
+                match control {
+                    OverlayControl::Reveal(device_name) => {
+                        if let Some(previous) = std::mem::replace(&mut revealed, device_name) {
+                            set_alpha(&windows, &previous, levels.get(&previous).copied().unwrap_or(0))?;
+                        }
+                        if let Some(current) = &revealed {
+                            debug!("revealing device: {}", current);
+                            set_alpha(&windows, current, 0)?;
+                        }
+                    }
+                    OverlayControl::Privacy(true) => {
+                        if let Err(e) = privacy.enable(instance.into()) {
+                            error!("failed to enable privacy screen: {:?}", e);
+                        }
+                    }
+                    OverlayControl::Privacy(false) => privacy.disable(),
+                    OverlayControl::Shutdown => {
+                        info!("shutting down overlays");
+                        privacy.disable();
+                        unregister_power_notification(power.take());
+                        if let Some(osd) = osd.take() {
+                            osd.destroy();
+                        }
+                        for (_, hwnd) in windows.drain() {
+                            destroy_window(hwnd);
+                        }
+                        if let Ok(mut overlay_devices) = OVERLAY_DEVICES.lock() {
+                            overlay_devices.clear();
+                        }
+                        STOPPED.notify_one();
+                        return Ok(());
+                    }
+                    OverlayControl::ClickThrough { device_name, enabled } => {
+                        let targets: Vec<String> = match device_name {
+                            Some(device_name) => vec![gdi_device_name(&device_name).to_string()],
+                            None => windows.keys().cloned().collect(),
+                        };
+                        for device_name in targets {
+                            let Some(&hwnd) = windows.get(&device_name) else {
+                                warn!("Received click-through change for unknown device: {}", device_name);
+                                continue;
+                            };
+                            set_click_through(hwnd, enabled);
+                            if enabled {
+                                input_blocking.remove(&device_name);
+                            } else {
+                                input_blocking.insert(device_name);
+                            }
+                        }
+                        info!("overlays taking input: {:?}", input_blocking);
+                    }
+                    OverlayControl::SetColor { device_name, color } => {
+                        set_color(&windows, &device_name, color);
+                        colors.insert(gdi_device_name(&device_name).to_string(), color);
+                    }
+                    OverlayControl::Osd { device_name, label, level } => {
+                        if let Some(osd) = osd.as_mut() {
+                            if let Err(e) = osd.show(&device_name, label, level) {
+                                warn!("failed to show osd for device '{}': {:?}", device_name, e);
+                            }
+                        }
+                    }
+                }
+            }
+
+            if let Some(osd) = osd.as_mut() {
+                osd.tick();
+            }
+
             while PeekMessageW(&mut msg, None, 0, 0, PM_REMOVE).as_bool() {
                 if msg.message == WM_QUIT {
+                    unregister_power_notification(power.take());
                     return Ok(());
                 }
+                // thread hotkey, it has no window to dispatch to
+                if msg.message == WM_HOTKEY && msg.wParam.0 == privacy::DISMISS_HOTKEY_ID as usize {
+                    privacy.disable();
+                    continue;
+                }
                 TranslateMessage(&msg);
                 DispatchMessageW(&msg);
             }
 
-            sleep(Duration::from_millis(16)).await;
+            if DISPLAY_CHANGED.swap(false, Ordering::Relaxed) {
+                info!("display configuration changed, relayouting overlays");
+                match sync_windows(&mut windows, instance.into()) {
+                    Ok(removed) => {
+                        for device_name in removed {
+                            if revealed.as_deref() == Some(device_name.as_str()) {
+                                revealed = None;
+                            }
+                            input_blocking.remove(&device_name);
+                        }
+                        // unchanged colors are skipped by `set_color`, only new windows get painted
+                        for (device_name, &color) in colors.iter().filter(|(d, _)| windows.contains_key(*d)) {
+                            set_color(&windows, device_name, color);
+                        }
+                    }
+                    Err(e) => error!("failed to sync overlay windows: {:?}", e),
+                }
+                if !power.is_some_and(|(hwnd, _)| windows.values().any(|&w| w == hwnd)) {
+                    unregister_power_notification(power.take());
+                    power = register_power_notification(&windows);
+                }
+                if let Err(e) = relayout(&windows, &levels, revealed.as_deref()) {
+                    error!("failed to relayout overlays: {:?}", e);
+                }
+            }
+
+            if DISPLAY_WOKE.swap(false, Ordering::Relaxed) {
+                info!("displays woke up");
+                WAKE.notify_one();
+            }
+
+            sleep(TICK).await;
         }
     }
 }
 
-/// window procedure for our overlay windows. it just paints itself black.
+/// window procedure for our overlay windows. it just paints itself with its color, black by default.
 extern "system" fn wnd_proc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
     unsafe {
         match msg {
             WM_PAINT => {
                 let mut ps = PAINTSTRUCT::default();
                 let hdc = BeginPaint(hwnd, &mut ps);
-                FillRect(hdc, &ps.rcPaint, HBRUSH(GetStockObject(BLACK_BRUSH).0));
+                let brush = BRUSHES.lock().ok()
+                    .and_then(|brushes| brushes.get(&(hwnd.0 as usize)).map(|b| b.handle));
+                match brush {
+                    Some(handle) => {
+                        FillRect(hdc, &ps.rcPaint, HBRUSH(handle as *mut _));
+                    }
+                    None => {
+                        FillRect(hdc, &ps.rcPaint, HBRUSH(GetStockObject(BLACK_BRUSH).0));
+                    }
+                }
                 let _end_paint = EndPaint(hwnd, &ps);
                 LRESULT(0)
             }
+            WM_DISPLAYCHANGE => {
+                DISPLAY_CHANGED.store(true, Ordering::Relaxed);
+                DefWindowProcW(hwnd, msg, wparam, lparam)
+            }
+            WM_POWERBROADCAST if wparam.0 as u32 == PBT_POWERSETTINGCHANGE => {
+                let setting = &*(lparam.0 as *const POWERBROADCAST_SETTING);
+                if setting.PowerSetting == GUID_CONSOLE_DISPLAY_STATE {
+                    match setting.Data[0] {
+                        DISPLAY_STATE_OFF => DISPLAY_WAS_OFF.store(true, Ordering::Relaxed),
+                        DISPLAY_STATE_ON if DISPLAY_WAS_OFF.swap(false, Ordering::Relaxed) => {
+                            DISPLAY_WOKE.store(true, Ordering::Relaxed);
+                        }
+                        _ => {}
+                    }
+                }
+                LRESULT(1)
+            }
+            WM_POWERBROADCAST if matches!(wparam.0 as u32, PBT_APMRESUMEAUTOMATIC | PBT_APMRESUMESUSPEND) => {
+                RESUMED.store(true, Ordering::Relaxed);
+                DISPLAY_WOKE.store(true, Ordering::Relaxed);
+                LRESULT(1)
+            }
             // fuck it, just drop the thread
             // WM_DESTROY => {
             //     PostQuitMessage(0);
@@ -0,0 +1,141 @@
+/*
+ * privacy screen: opaque windows over every monitor which, unlike the dim overlays,
+ * aren't click-through. the ex-styles are fixed at creation, so they're separate windows
+ * created on enable & destroyed on dismiss, owned by the overlay thread
+*/
+use anyhow::anyhow;
+use std::iter;
+use tracing::{info, warn};
+use windows::{
+    core::w,
+    Win32::{
+        Foundation::{HWND, HINSTANCE, LPARAM, LRESULT, RECT, WPARAM, COLORREF},
+        Graphics::Gdi::{
+            BeginPaint, EndPaint, FillRect, GetStockObject, DrawTextW, SetBkMode, SetTextColor,
+            BLACK_BRUSH, HBRUSH, PAINTSTRUCT, DT_CENTER, DT_SINGLELINE, DT_VCENTER, TRANSPARENT,
+        },
+        UI::{
+            Input::KeyboardAndMouse::{RegisterHotKey, UnregisterHotKey, MOD_ALT, MOD_CONTROL, MOD_NOREPEAT},
+            WindowsAndMessaging::{
+                CreateWindowExW, DefWindowProcW, DestroyWindow, GetClientRect, RegisterClassExW,
+                SetLayeredWindowAttributes, ShowWindow, LWA_ALPHA, SW_SHOW, WM_PAINT, WNDCLASSEXW,
+                WS_EX_LAYERED, WS_EX_TOOLWINDOW, WS_EX_TOPMOST, WS_POPUP,
+            },
+        },
+    }
+};
+use crate::monitors::monitor_rects;
+
+/// `WM_HOTKEY` id, posted to the overlay thread's queue
+pub const DISMISS_HOTKEY_ID: i32 = 0xfade;
+/// ctrl + alt + p
+const DISMISS_VK: u32 = b'P' as u32;
+const DISMISS_PROMPT: &str = "privacy screen, press ctrl + alt + p to dismiss";
+/// not fully opaque, so it's obvious the screen is covered rather than off
+const PRIVACY_ALPHA: u8 = 250;
+
+#[derive(Default)]
+pub struct PrivacyScreen {
+    windows: Vec<HWND>,
+}
+
+impl PrivacyScreen {
+    pub fn is_on(&self) -> bool {
+        !self.windows.is_empty()
+    }
+
+    /// the dismiss hotkey is registered first, without it there'd be no way out
+    pub fn enable(&mut self, instance: HINSTANCE) -> anyhow::Result<()> {
+        if self.is_on() {
+            return Ok(());
+        }
+
+        unsafe {
+            RegisterHotKey(None, DISMISS_HOTKEY_ID, MOD_CONTROL | MOD_ALT | MOD_NOREPEAT, DISMISS_VK)
+                .map_err(|e| anyhow!("dismiss hotkey unavailable, refusing privacy screen: {:?}", e))?;
+
+            let class_name = w!("FadePrivacy");
+            let wc = WNDCLASSEXW {
+                cbSize: size_of::<WNDCLASSEXW>() as u32,
+                lpfnWndProc: Some(wnd_proc),
+                hInstance: instance,
+                lpszClassName: class_name,
+                ..Default::default()
+            };
+            RegisterClassExW(&wc);
+
+            let rects = match monitor_rects() {
+                Ok(rects) => rects,
+                Err(e) => {
+                    let _ = UnregisterHotKey(None, DISMISS_HOTKEY_ID);
+                    return Err(e);
+                }
+            };
+
+            // no `WS_EX_TRANSPARENT` & `WS_EX_NOACTIVATE`, input lands here instead of below
+            for rect in rects.values() {
+                match CreateWindowExW(
+                    WS_EX_LAYERED | WS_EX_TOPMOST | WS_EX_TOOLWINDOW,
+                    class_name,
+                    w!(""),
+                    WS_POPUP,
+                    rect.left,
+                    rect.top,
+                    rect.right - rect.left,
+                    rect.bottom - rect.top,
+                    None,
+                    None,
+                    Some(instance),
+                    None
+                ) {
+                    Ok(hwnd) => {
+                        let _ = SetLayeredWindowAttributes(hwnd, COLORREF(0), PRIVACY_ALPHA, LWA_ALPHA);
+                        let _ = ShowWindow(hwnd, SW_SHOW);
+                        self.windows.push(hwnd);
+                    }
+                    Err(e) => warn!("failed to create privacy window: {:?}", e),
+                }
+            }
+        }
+
+        info!("privacy screen on, {} windows", self.windows.len());
+        Ok(())
+    }
+
+    pub fn disable(&mut self) {
+        if !self.is_on() {
+            return;
+        }
+        unsafe {
+            for hwnd in self.windows.drain(..) {
+                let _ = DestroyWindow(hwnd);
+            }
+            let _ = UnregisterHotKey(None, DISMISS_HOTKEY_ID);
+        }
+        info!("privacy screen dismissed");
+    }
+}
+
+extern "system" fn wnd_proc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    unsafe {
+        match msg {
+            WM_PAINT => {
+                let mut ps = PAINTSTRUCT::default();
+                let hdc = BeginPaint(hwnd, &mut ps);
+                FillRect(hdc, &ps.rcPaint, HBRUSH(GetStockObject(BLACK_BRUSH).0));
+
+                let mut rect = RECT::default();
+                let _ = GetClientRect(hwnd, &mut rect);
+                let mut text: Vec<u16> = DISMISS_PROMPT.encode_utf16().chain(iter::once(0)).collect();
+                let len = text.len() - 1;
+                SetBkMode(hdc, TRANSPARENT);
+                SetTextColor(hdc, COLORREF(0x0080_8080));
+                DrawTextW(hdc, &mut text[..len], &mut rect, DT_CENTER | DT_VCENTER | DT_SINGLELINE);
+
+                let _ = EndPaint(hwnd, &ps);
+                LRESULT(0)
+            }
+            _ => DefWindowProcW(hwnd, msg, wparam, lparam),
+        }
+    }
+}
@@ -0,0 +1,146 @@
+/*
+ * named brightness setups ("day", "night", "movie"): a snapshot of every monitor's brightness,
+ * dim & color temperature, kept per monitor `id` so they survive reconnects & port changes
+*/
+use std::collections::HashMap;
+use anyhow::{anyhow, bail};
+use tracing::{debug, info, warn};
+use tokio::{sync::mpsc::Sender, time::Duration};
+use crate::{
+    app::AppState,
+    events,
+    overlay::Overlay,
+    monitors::{MonitorDeviceImpl, DimBackend},
+    settings::ProfileEntry,
+};
+
+/// saves what every connected monitor is on as `name`, replacing a profile of the same name.
+/// returns the number of monitors saved, a monitor whose brightness can't be read is left out
+pub async fn save(state: &AppState, name: &str) -> anyhow::Result<usize> {
+    let name = name.trim();
+    if name.is_empty() {
+        bail!("profile name can't be empty");
+    }
+
+    let devices = state.monitor_device.lock().await.clone();
+    let dim_levels: HashMap<String, u8> = state.dim_state.lock().await
+        .iter()
+        .map(|(device_name, dim)| (device_name.clone(), dim.level))
+        .collect();
+
+    let mut entries = HashMap::new();
+    for dev in devices {
+        let brightness = match tauri::async_runtime::spawn_blocking({
+            let dev = dev.clone();
+            move || dev.get()
+        }).await? {
+            Ok(brightness) => brightness,
+            Err(e) => {
+                warn!("profile '{}': leaving out '{}', brightness unreadable: {:?}", name, dev.friendly_name, e);
+                continue;
+            }
+        };
+        let dim_level = dim_levels.get(&dev.device_name).copied().unwrap_or(0);
+        let serial = dev.edid_serial();
+        entries.insert(dev.id, ProfileEntry { serial, brightness, dim_level, color_temp: None });
+    }
+
+    let mut settings = state.settings.lock().await;
+    for (id, entry) in entries.iter_mut() {
+        entry.color_temp = settings.color_temps.get(id).copied();
+    }
+    let saved = entries.len();
+    settings.profiles.insert(name.to_string(), entries);
+    settings.save()?;
+    info!("profile '{}' saved with {} monitors", name, saved);
+    Ok(saved)
+}
+
+/// puts every connected monitor of profile `name` on its entry, the monitors concurrently.
+/// disconnected ones are skipped, returns one message per monitor that failed
+pub async fn apply(state: &AppState, name: &str) -> anyhow::Result<Vec<String>> {
+    // the watcher stays quiet until every monitor is on its entry
+    let freeze = state.broadcast_freeze.hold();
+    let (entries, transition) = {
+        let settings = state.settings.lock().await;
+        let entries = settings.profiles.get(name.trim())
+            .cloned()
+            .ok_or_else(|| anyhow!("no profile named '{}'", name))?;
+        (entries, Duration::from_millis(settings.color_temp_transition_ms))
+    };
+    let devices = state.monitor_device.lock().await.clone();
+    let tx = state.overlay_tx.lock().await.clone()
+        .ok_or_else(|| anyhow!("overlay channel not initialized"))?;
+
+    let tasks = entries.iter().filter_map(|(id, entry)| {
+        // by `id`, or by serial when the monitor moved to another port
+        let dev = devices.iter().find(|d| &d.id == id).or_else(|| {
+            let serial = entry.serial.as_ref()?;
+            devices.iter().find(|d| d.edid_serial().as_ref() == Some(serial))
+        });
+        let Some(dev) = dev else {
+            debug!("profile '{}': '{}' isn't connected, skipping", name, id);
+            return None;
+        };
+        let tx = &tx;
+        Some(async move {
+            let result = apply_entry(state, dev.clone(), entry.clone(), transition, tx).await;
+            result.map_err(|e| format!("{}: {}", dev.device_name, e))
+        })
+    });
+    let errors: Vec<String> = futures::future::join_all(tasks).await
+        .into_iter()
+        .filter_map(Result::err)
+        .collect();
+
+    if let Err(e) = state.settings.lock().await.save() {
+        warn!("failed to save settings: {:?}", e);
+    }
+    drop(freeze);
+    events::broadcast_devices(state, &devices).await;
+    info!("profile '{}' applied, {} monitors failed", name, errors.len());
+    Ok(errors)
+}
+
+async fn apply_entry(
+    state: &AppState,
+    dev: MonitorDeviceImpl,
+    entry: ProfileEntry,
+    transition: Duration,
+    tx: &Sender<Overlay>,
+) -> anyhow::Result<()> {
+    if state.settings.lock().await.locked.contains(&dev.id) {
+        bail!("monitor is locked");
+    }
+
+    // ddc/ci writes block, off the runtime so the monitors are written in parallel
+    tauri::async_runtime::spawn_blocking({
+        let dev = dev.clone();
+        move || dev.set(entry.brightness.min(100))
+    }).await??;
+
+    let (backend, dimmed) = state.dim_state.lock().await
+        .get(&dev.device_name)
+        .map_or((DimBackend::default(), false), |d| (d.backend, d.level > 0));
+    if entry.dim_level > 0 {
+        dev.dim(entry.dim_level, backend, tx).await?;
+    } else if dimmed {
+        dev.clear_dim(backend, tx).await?;
+    }
+    state.dim_state.lock().await.entry(dev.device_name.clone()).or_default().level = entry.dim_level;
+
+    if let Some(temp) = entry.color_temp {
+        let applied = dev.transition_color_temperature(temp, transition)?;
+        state.settings.lock().await.color_temps.insert(dev.id.clone(), applied);
+    }
+
+    events::remember_levels(state, &dev.id, Some(entry.brightness.min(100)), entry.dim_level).await;
+    Ok(())
+}
+
+/// profile names, sorted
+pub async fn list(state: &AppState) -> Vec<String> {
+    let mut names: Vec<String> = state.settings.lock().await.profiles.keys().cloned().collect();
+    names.sort();
+    names
+}
@@ -0,0 +1,202 @@
+/*
+ * typed payloads accepted over the websocket & tauri commands,
+ * everything gets validated here instead of clamping silently
+*/
+use std::{fmt, ops::RangeInclusive};
+use schemars::{JsonSchema, schema_for};
+use serde::{
+    Serialize,
+    Deserialize
+};
+use crate::monitors::MonitorInfo;
+
+/// same range as the frontend slider, negative values dim
+pub const SLIDER_RANGE: RangeInclusive<i64> = -100..=100;
+/// hardware brightness percentage
+pub const BRIGHTNESS_RANGE: RangeInclusive<i64> = 0..=100;
+/// dim alpha: 0 is transparent, 255 is fully opaque
+pub const DIM_RANGE: RangeInclusive<i64> = 0..=255;
+
+/// numbers are kept wide so out of range values reach `validate`
+/// instead of failing with an obscure deserialization error
+#[derive(Debug, Deserialize, JsonSchema, Clone, PartialEq, Eq)]
+#[serde(tag = "command", rename_all = "snake_case", deny_unknown_fields)]
+pub enum CommandEnvelope {
+    Slider { device_name: String, value: i64 },
+    SetBrightness { device_name: String, brightness: i64 },
+    SetDim { device_name: String, level: i64 },
+}
+
+#[derive(Debug, Serialize, JsonSchema, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CommandErrorKind {
+    /// not json or not a known command
+    Malformed,
+    OutOfRange,
+    NotFound,
+    /// the monitor is locked by `lock_monitor`
+    Locked,
+    /// valid command but the device refused it
+    Failed,
+}
+
+#[derive(Debug, Serialize, JsonSchema, Clone, PartialEq, Eq)]
+pub struct CommandError {
+    pub kind: CommandErrorKind,
+    pub message: String,
+}
+
+impl CommandError {
+    pub fn new(kind: CommandErrorKind, message: impl Into<String>) -> Self {
+        Self { kind, message: message.into() }
+    }
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}: {}", self.kind, self.message)
+    }
+}
+
+impl std::error::Error for CommandError {}
+
+/// reply sent back to websocket clients for every inbound message
+#[derive(Debug, Serialize, JsonSchema, Clone, PartialEq, Eq)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum CommandReply {
+    Ok,
+    Error { error: CommandError },
+}
+
+impl From<Result<(), CommandError>> for CommandReply {
+    fn from(result: Result<(), CommandError>) -> Self {
+        match result {
+            Ok(()) => CommandReply::Ok,
+            Err(error) => CommandReply::Error { error },
+        }
+    }
+}
+
+fn check_range(field: &str, value: i64, range: RangeInclusive<i64>) -> Result<(), CommandError> {
+    if range.contains(&value) {
+        Ok(())
+    } else {
+        Err(CommandError::new(
+            CommandErrorKind::OutOfRange,
+            format!("{} must be within {}..={}, got {}", field, range.start(), range.end(), value),
+        ))
+    }
+}
+
+impl CommandEnvelope {
+    /// deserialize & validate a raw json payload
+    /// a bare `{ "device_name", "value" }` without `command` is taken as a `slider`,
+    /// it's what simple clients like stream deck plugins send
+    pub fn parse(raw: &str) -> Result<Self, CommandError> {
+        let malformed = |e: serde_json::Error| CommandError::new(CommandErrorKind::Malformed, e.to_string());
+        let mut value: serde_json::Value = serde_json::from_str(raw).map_err(malformed)?;
+        if let Some(object) = value.as_object_mut() {
+            if !object.contains_key("command") && object.contains_key("value") {
+                object.insert("command".into(), "slider".into());
+            }
+        }
+        let command: Self = serde_json::from_value(value).map_err(malformed)?;
+        command.validate()?;
+        Ok(command)
+    }
+
+    pub fn validate(&self) -> Result<(), CommandError> {
+        match self {
+            CommandEnvelope::Slider { value, .. } => check_range("value", *value, SLIDER_RANGE),
+            CommandEnvelope::SetBrightness { brightness, .. } => check_range("brightness", *brightness, BRIGHTNESS_RANGE),
+            CommandEnvelope::SetDim { level, .. } => check_range("level", *level, DIM_RANGE),
+        }
+    }
+
+    pub fn device_name(&self) -> &str {
+        match self {
+            CommandEnvelope::Slider { device_name, .. }
+            | CommandEnvelope::SetBrightness { device_name, .. }
+            | CommandEnvelope::SetDim { device_name, .. } => device_name,
+        }
+    }
+}
+
+/// json schemas of what goes over `/ws/monitors`: inbound `command`s, the `reply` to each,
+/// & the `monitors` list pushed on every change
+pub fn schema() -> serde_json::Value {
+    serde_json::json!({
+        "command": schema_for!(CommandEnvelope),
+        "reply": schema_for!(CommandReply),
+        "monitors": schema_for!(Vec<MonitorInfo>),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kind(raw: &str) -> CommandErrorKind {
+        CommandEnvelope::parse(raw).unwrap_err().kind
+    }
+
+    #[test]
+    fn parses_every_command() {
+        assert_eq!(
+            CommandEnvelope::parse(r#"{ "command": "set_brightness", "device_name": "a", "brightness": 40 }"#).unwrap(),
+            CommandEnvelope::SetBrightness { device_name: "a".into(), brightness: 40 },
+        );
+        assert_eq!(
+            CommandEnvelope::parse(r#"{ "command": "set_dim", "device_name": "a", "level": 255 }"#).unwrap(),
+            CommandEnvelope::SetDim { device_name: "a".into(), level: 255 },
+        );
+        assert_eq!(
+            CommandEnvelope::parse(r#"{ "command": "slider", "device_name": "a", "value": -100 }"#).unwrap(),
+            CommandEnvelope::Slider { device_name: "a".into(), value: -100 },
+        );
+    }
+
+    #[test]
+    fn bare_value_is_a_slider() {
+        assert_eq!(
+            CommandEnvelope::parse(r#"{ "device_name": "a", "value": 30 }"#).unwrap(),
+            CommandEnvelope::Slider { device_name: "a".into(), value: 30 },
+        );
+        assert_eq!(kind(r#"{ "device_name": "a", "value": 101 }"#), CommandErrorKind::OutOfRange);
+    }
+
+    #[test]
+    fn malformed_payloads() {
+        assert_eq!(kind("not json"), CommandErrorKind::Malformed);
+        assert_eq!(kind("[1, 2]"), CommandErrorKind::Malformed);
+        assert_eq!(kind(r#"{ "device_name": "a" }"#), CommandErrorKind::Malformed);
+        assert_eq!(kind(r#"{ "command": "explode", "device_name": "a" }"#), CommandErrorKind::Malformed);
+        assert_eq!(kind(r#"{ "command": "set_brightness", "device_name": "a" }"#), CommandErrorKind::Malformed);
+        assert_eq!(kind(r#"{ "command": "set_brightness", "device_name": "a", "brightness": "50" }"#), CommandErrorKind::Malformed);
+    }
+
+    #[test]
+    fn unknown_fields_are_rejected() {
+        assert_eq!(
+            kind(r#"{ "command": "set_brightness", "device_name": "a", "brightness": 50, "extra": 1 }"#),
+            CommandErrorKind::Malformed,
+        );
+        assert_eq!(kind(r#"{ "device_name": "a", "value": 50, "level": 3 }"#), CommandErrorKind::Malformed);
+    }
+
+    #[test]
+    fn out_of_range_values() {
+        for raw in [
+            r#"{ "command": "slider", "device_name": "a", "value": -101 }"#,
+            r#"{ "command": "slider", "device_name": "a", "value": 101 }"#,
+            r#"{ "command": "set_brightness", "device_name": "a", "brightness": -1 }"#,
+            r#"{ "command": "set_brightness", "device_name": "a", "brightness": 101 }"#,
+            r#"{ "command": "set_dim", "device_name": "a", "level": -1 }"#,
+            r#"{ "command": "set_dim", "device_name": "a", "level": 256 }"#,
+        ] {
+            assert_eq!(kind(raw), CommandErrorKind::OutOfRange, "{}", raw);
+        }
+        assert!(CommandEnvelope::parse(r#"{ "command": "set_brightness", "device_name": "a", "brightness": 0 }"#).is_ok());
+        assert!(CommandEnvelope::parse(r#"{ "command": "set_dim", "device_name": "a", "level": 0 }"#).is_ok());
+    }
+}
@@ -0,0 +1,105 @@
+/*
+ * reading mode: lower brightness, warmer color & less contrast in one go,
+ * the previous values are captured per monitor & restored when it's turned off
+*/
+use tracing::{debug, warn};
+use tokio::time::Duration;
+use crate::{
+    app::AppState,
+    brightness, gamma,
+    monitors::{MonitorDeviceImpl, ColorTempMechanism},
+    settings::ReadingModeRestore,
+};
+
+/// applies reading mode to `devices`, capturing what they had unless it's already captured
+/// (reapplied after a restart or reconnect). monitors apply what they support, locked ones are skipped
+pub async fn apply(state: &AppState, devices: &[&MonitorDeviceImpl]) {
+    // the settings aren't held across the ddc/ci round trips below
+    let (mode, transition, targets) = {
+        let settings = state.settings.lock().await;
+        let targets: Vec<(&MonitorDeviceImpl, bool)> = devices.iter()
+            .filter(|d| !settings.locked.contains(&d.id))
+            .map(|d| (*d, settings.reading_mode_restore.contains_key(&d.id)))
+            .collect();
+        (settings.reading_mode, Duration::from_millis(settings.color_temp_transition_ms), targets)
+    };
+
+    let mut captured = Vec::new();
+    for (dev, already_captured) in targets {
+        if !already_captured {
+            let contrast = if dev.is_internal() {
+                None
+            } else {
+                brightness::ddcci_get_monitor_contrast(dev).ok()
+            };
+            let restore = ReadingModeRestore { brightness: dev.get_async().await.ok(), contrast };
+            captured.push((dev.id.clone(), restore));
+        }
+
+        if let Err(e) = dev.set_async(mode.brightness).await {
+            warn!("reading mode: failed to set brightness of '{}': {:?}", dev.friendly_name, e);
+        }
+        gamma::spawn_color_transition(mode.kelvin, dev.device_name.clone(), transition);
+        if !dev.is_internal() {
+            if let Err(e) = brightness::ddcci_set_monitor_contrast(dev, mode.contrast) {
+                debug!("reading mode: no contrast control on '{}': {:?}", dev.friendly_name, e);
+            }
+        }
+    }
+
+    let mut settings = state.settings.lock().await;
+    for (id, restore) in captured {
+        settings.reading_mode_restore.entry(id).or_insert(restore);
+    }
+    if let Err(e) = settings.save() {
+        warn!("failed to save settings: {:?}", e);
+    }
+}
+
+/// puts back what `apply` captured, the saved color temperature included.
+/// locked monitors keep their capture until a later restore
+pub async fn restore(state: &AppState, devices: &[&MonitorDeviceImpl]) {
+    let (transition, targets) = {
+        let mut settings = state.settings.lock().await;
+        let mut targets = Vec::new();
+        for dev in devices {
+            if settings.locked.contains(&dev.id) {
+                continue;
+            }
+            if let Some(restore) = settings.reading_mode_restore.remove(&dev.id) {
+                targets.push((*dev, restore, settings.color_temps.get(&dev.id).copied()));
+            }
+        }
+        if let Err(e) = settings.save() {
+            warn!("failed to save settings: {:?}", e);
+        }
+        (Duration::from_millis(settings.color_temp_transition_ms), targets)
+    };
+
+    for (dev, restore, temp) in targets {
+
+        if let Some(level) = restore.brightness {
+            if let Err(e) = dev.set_async(level).await {
+                warn!("reading mode: failed to restore brightness of '{}': {:?}", dev.friendly_name, e);
+            }
+        }
+        if let Some(contrast) = restore.contrast {
+            if let Err(e) = brightness::ddcci_set_monitor_contrast(dev, contrast) {
+                warn!("reading mode: failed to restore contrast of '{}': {:?}", dev.friendly_name, e);
+            }
+        }
+
+        // the warmth came from the gamma ramp, ease it back to the user's own gamma
+        // color temperature, or to neutral before their ddc/ci preset
+        let gamma_kelvin = match temp {
+            Some(temp) if temp.mechanism == ColorTempMechanism::Gamma => temp.kelvin,
+            _ => gamma::NEUTRAL_KELVIN,
+        };
+        gamma::spawn_color_transition(gamma_kelvin, dev.device_name.clone(), transition);
+        if let Some(temp) = temp.filter(|t| t.mechanism == ColorTempMechanism::Ddcci) {
+            if let Err(e) = dev.set_color_temperature(temp) {
+                warn!("reading mode: failed to restore color temperature of '{}': {:?}", dev.friendly_name, e);
+            }
+        }
+    }
+}
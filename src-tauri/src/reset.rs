@@ -0,0 +1,105 @@
+/*
+ * factory reset: every monitor back to full brightness, no dims, overlay colors or gamma changes,
+ * & the settings file moved aside so the next start behaves like a first run.
+ * every step is logged, support reads the log to see what a reset did
+*/
+use std::path::PathBuf;
+use tracing::{info, warn};
+use crate::{
+    app::AppState,
+    autostart, brightness, events, gamma, monitors, wmi,
+    settings::Settings,
+    overlay::OverlayControl,
+    monitors::{ColorTemp, ColorTempMechanism},
+};
+
+/// has to be passed to `factory_reset` as is
+pub const CONFIRMATION: &str = "reset";
+/// color preset ddc/ci monitors are put back on, the usual factory default
+const DEFAULT_PRESET_KELVIN: u32 = 6500;
+
+/// returns the settings backup, `None` when there was no settings file
+pub async fn factory_reset(state: &AppState) -> anyhow::Result<Option<PathBuf>> {
+    info!("factory reset requested");
+
+    // reading mode holds the contrast it replaced, only it can put that back
+    if state.settings.lock().await.reading_mode_enabled {
+        info!("factory reset: turning reading mode off");
+        events::set_reading_mode(state, false).await;
+    }
+
+    for (device_name, pending) in state.pending_confirms.lock().await.drain() {
+        info!("factory reset: dropping unconfirmed brightness change of '{}'", device_name);
+        pending.cancel();
+    }
+
+    // before anything is forgotten, a failed backup leaves everything as it was
+    let backup = Settings::backup_and_remove()?;
+    match &backup {
+        Some(path) => info!("factory reset: settings backed up to {:?}", path),
+        None => info!("factory reset: no settings file to back up"),
+    }
+
+    let previous = std::mem::take(&mut *state.settings.lock().await);
+    info!("factory reset: settings reset to defaults");
+    wmi::init(false);
+    brightness::set_verify_writes(false);
+    brightness::set_max_writes_per_sec(Settings::default().ddcci_max_writes_per_sec);
+    monitors::set_dim_split(Default::default());
+    monitors::set_min_brightness(Default::default());
+    match autostart::reconcile(false) {
+        Ok(()) => info!("factory reset: autostart turned off"),
+        Err(e) => warn!("factory reset: failed to turn off autostart: {:?}", e),
+    }
+    *state.history.lock().await = Default::default();
+    info!("factory reset: brightness history cleared");
+
+    let devices = state.monitor_device.lock().await;
+    let overlay_tx = state.overlay_tx.lock().await;
+    let overlay_ctl_tx = state.overlay_ctl_tx.lock().await;
+    let mut dim_state = state.dim_state.lock().await;
+
+    if let Some(tx) = overlay_ctl_tx.as_ref() {
+        let _ = tx.send(OverlayControl::Privacy(false)).await;
+    }
+
+    for dev in devices.iter() {
+        let backend = dim_state.remove(&dev.device_name).unwrap_or_default().backend;
+        if let Some(tx) = overlay_tx.as_ref() {
+            match dev.clear_dim(backend, tx).await {
+                Ok(()) => info!("factory reset: cleared dim of '{}'", dev.friendly_name),
+                Err(e) => warn!("factory reset: failed to clear dim of '{}': {:?}", dev.friendly_name, e),
+            }
+        }
+        if let Some(tx) = overlay_ctl_tx.as_ref() {
+            let color = Settings::default().overlay_color;
+            let _ = tx.send(OverlayControl::SetColor { device_name: dev.device_name.clone(), color }).await;
+            info!("factory reset: reset overlay color of '{}'", dev.friendly_name);
+        }
+
+        let gamma = gamma::reset_gamma(&dev.device_name)
+            .and_then(|_| gamma::set_color_temperature(gamma::NEUTRAL_KELVIN, &dev.device_name));
+        match gamma {
+            Ok(_) => info!("factory reset: reset gamma of '{}'", dev.friendly_name),
+            Err(e) => warn!("factory reset: failed to reset gamma of '{}': {:?}", dev.friendly_name, e),
+        }
+
+        if previous.color_temps.get(&dev.id).is_some_and(|t| t.mechanism == ColorTempMechanism::Ddcci) {
+            let temp = ColorTemp { mechanism: ColorTempMechanism::Ddcci, kelvin: DEFAULT_PRESET_KELVIN };
+            match dev.set_color_temperature(temp) {
+                Ok(_) => info!("factory reset: reset color preset of '{}'", dev.friendly_name),
+                Err(e) => warn!("factory reset: failed to reset color preset of '{}': {:?}", dev.friendly_name, e),
+            }
+        }
+
+        match dev.set_async(100).await {
+            Ok(()) => info!("factory reset: set brightness of '{}' to 100%", dev.friendly_name),
+            Err(e) => warn!("factory reset: failed to set brightness of '{}': {:?}", dev.friendly_name, e),
+        }
+    }
+    drop(dim_state);
+
+    events::broadcast_devices(state, &devices).await;
+    info!("factory reset done");
+    Ok(backup)
+}
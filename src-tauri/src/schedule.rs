@@ -0,0 +1,295 @@
+/*
+ * brightness schedule, either fixed points through the day or following the sun.
+ * evaluation is pure (time in, brightness out), the clock is only read by `local_clock`
+*/
+use anyhow::{anyhow, bail};
+use std::f64::consts::PI;
+use serde::{
+    Serialize,
+    Deserialize
+};
+use windows::Win32::System::SystemInformation::{GetLocalTime, GetSystemTime};
+
+const MINUTES_PER_DAY: u32 = 24 * 60;
+/// sun's center 0.833° below the horizon, refraction & the solar disk included
+const SUNRISE_ZENITH_DEG: f64 = 90.833;
+
+/// brightness to reach at a local time of day
+#[derive(Debug, Serialize, Deserialize, Clone, Eq, PartialEq)]
+pub struct SchedulePoint {
+    /// `HH:MM`, 24h
+    pub time: String,
+    pub brightness: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SunSchedule {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub day_brightness: u32,
+    pub night_brightness: u32,
+    /// minutes the change is spread over, centered on sunrise & sunset
+    pub transition_mins: u32,
+    /// overlay alpha reached at night, 0 leaves the dim alone
+    #[serde(default)]
+    pub night_dim: u8,
+    /// gamma color temperature reached at night, from the monitor's own one during the day
+    #[serde(default)]
+    pub night_kelvin: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "mode", rename_all = "lowercase")]
+pub enum Schedule {
+    /// linear between the points, wrapping around midnight
+    Fixed { points: Vec<SchedulePoint> },
+    Sun(SunSchedule),
+}
+
+impl Default for Schedule {
+    fn default() -> Self {
+        Schedule::Fixed { points: Vec::new() }
+    }
+}
+
+/// `HH:MM` -> minutes since midnight
+pub fn parse_time(time: &str) -> anyhow::Result<u32> {
+    let (hours, minutes) = time.trim().split_once(':')
+        .ok_or_else(|| anyhow!("expected HH:MM, got '{}'", time))?;
+    let hours: u32 = hours.parse().map_err(|_| anyhow!("invalid hours in '{}'", time))?;
+    let minutes: u32 = minutes.parse().map_err(|_| anyhow!("invalid minutes in '{}'", time))?;
+    if hours > 23 || minutes > 59 {
+        bail!("time out of range: '{}'", time);
+    }
+    Ok(hours * 60 + minutes)
+}
+
+/// local minutes since midnight from the system clock
+pub fn local_minutes() -> u32 {
+    let local = unsafe { GetLocalTime() };
+    local.wHour as u32 * 60 + local.wMinute as u32
+}
+
+/// `(day of year, local - utc in minutes)` from the system clock
+pub fn local_clock() -> (u32, i32) {
+    let (local, utc) = unsafe { (GetLocalTime(), GetSystemTime()) };
+    let local_mins = local.wHour as i32 * 60 + local.wMinute as i32;
+    let utc_mins = utc.wHour as i32 * 60 + utc.wMinute as i32;
+    // both sides of midnight, offsets stay within -12h..=+14h
+    let mut offset = local_mins - utc_mins;
+    if offset > 14 * 60 {
+        offset -= MINUTES_PER_DAY as i32;
+    } else if offset < -12 * 60 {
+        offset += MINUTES_PER_DAY as i32;
+    }
+    (day_of_year(local.wYear as u32, local.wMonth as u32, local.wDay as u32), offset)
+}
+
+fn day_of_year(year: u32, month: u32, day: u32) -> u32 {
+    const CUMULATIVE: [u32; 12] = [0, 31, 59, 90, 120, 151, 181, 212, 243, 273, 304, 334];
+    let leap = (year % 4 == 0 && year % 100 != 0) || year % 400 == 0;
+    let month = month.clamp(1, 12);
+    CUMULATIVE[month as usize - 1] + day + (leap && month > 2) as u32
+}
+
+/// brightness at `at` (minutes since midnight, local), `None` when the schedule is empty
+pub fn evaluate(schedule: &Schedule, at: u32, day_of_year: u32, utc_offset_mins: i32) -> anyhow::Result<Option<u32>> {
+    match schedule {
+        Schedule::Fixed { points } => {
+            let mut parsed = points.iter()
+                .map(|p| Ok((parse_time(&p.time)?, p.brightness.min(100))))
+                .collect::<anyhow::Result<Vec<(u32, u32)>>>()?;
+            parsed.sort_by_key(|(time, _)| *time);
+            Ok(evaluate_fixed(&parsed, at))
+        }
+        Schedule::Sun(sun) => Ok(Some(evaluate_sun(sun, at, day_of_year, utc_offset_mins))),
+    }
+}
+
+/// `points` sorted by time
+fn evaluate_fixed(points: &[(u32, u32)], at: u32) -> Option<u32> {
+    let (&first, &last) = (points.first()?, points.last()?);
+    // the segment `at` falls in, the one across midnight is last -> first
+    let (from, to) = match points.iter().position(|&(time, _)| time > at) {
+        Some(0) | None => (last, (first.0 + MINUTES_PER_DAY, first.1)),
+        Some(i) => (points[i - 1], points[i]),
+    };
+    let at = if at < from.0 { at + MINUTES_PER_DAY } else { at };
+    let span = to.0.saturating_sub(from.0);
+    if span == 0 {
+        return Some(to.1);
+    }
+    let t = (at - from.0) as f64 / span as f64;
+    Some((from.1 as f64 + (to.1 as f64 - from.1 as f64) * t).round() as u32)
+}
+
+/// sunrise & sunset in local minutes since midnight, noaa's approximation.
+/// `Err(true)` for polar day, `Err(false)` for polar night
+fn sun_times(day_of_year: u32, latitude: f64, longitude: f64, utc_offset_mins: i32) -> Result<(f64, f64), bool> {
+    let g = 2.0 * PI / 365.0 * (day_of_year as f64 - 1.0);
+    let eqtime = 229.18 * (0.000075 + 0.001868 * g.cos() - 0.032077 * g.sin()
+        - 0.014615 * (2.0 * g).cos() - 0.040849 * (2.0 * g).sin());
+    let decl = 0.006918 - 0.399912 * g.cos() + 0.070257 * g.sin() - 0.006758 * (2.0 * g).cos()
+        + 0.000907 * (2.0 * g).sin() - 0.002697 * (3.0 * g).cos() + 0.00148 * (3.0 * g).sin();
+
+    let lat = latitude.to_radians();
+    let cos_ha = SUNRISE_ZENITH_DEG.to_radians().cos() / (lat.cos() * decl.cos()) - lat.tan() * decl.tan();
+    if cos_ha < -1.0 {
+        return Err(true);
+    }
+    if cos_ha > 1.0 {
+        return Err(false);
+    }
+    let ha = cos_ha.acos().to_degrees();
+    let noon = 720.0 - 4.0 * longitude - eqtime + utc_offset_mins as f64;
+    Ok((noon - 4.0 * ha, noon + 4.0 * ha))
+}
+
+fn evaluate_sun(sun: &SunSchedule, at: u32, day_of_year: u32, utc_offset_mins: i32) -> u32 {
+    let (day, night) = (sun.day_brightness.min(100) as f64, sun.night_brightness.min(100) as f64);
+    let daylight = daylight(sun, at, day_of_year, utc_offset_mins);
+    (night + (day - night) * daylight).round() as u32
+}
+
+/// 1.0 during the day, 0.0 at night & in between over the transitions.
+/// days without a sunrise or sunset stay where the polar day or night holds them
+pub fn daylight(sun: &SunSchedule, at: u32, day_of_year: u32, utc_offset_mins: i32) -> f64 {
+    match sun_times(day_of_year, sun.latitude, sun.longitude, utc_offset_mins) {
+        Ok((sunrise, sunset)) => {
+            let half = sun.transition_mins as f64 / 2.0;
+            // 0 before the transition, 1 after it, a step without one
+            let ramp = |center: f64| {
+                if half == 0.0 {
+                    return if at as f64 >= center { 1.0 } else { 0.0 };
+                }
+                ((at as f64 - (center - half)) / (2.0 * half)).clamp(0.0, 1.0)
+            };
+            ramp(sunrise).min(1.0 - ramp(sunset))
+        }
+        Err(polar_day) => if polar_day { 1.0 } else { 0.0 },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixed(points: &[(&str, u32)]) -> Schedule {
+        Schedule::Fixed {
+            points: points.iter()
+                .map(|&(time, brightness)| SchedulePoint { time: time.to_string(), brightness })
+                .collect(),
+        }
+    }
+
+    fn sun(transition_mins: u32) -> SunSchedule {
+        SunSchedule {
+            latitude: 0.0,
+            longitude: 0.0,
+            day_brightness: 80,
+            night_brightness: 20,
+            transition_mins,
+            night_dim: 0,
+            night_kelvin: None,
+        }
+    }
+
+    /// march equinox, the sun rises around 06:00 & sets around 18:00 on the equator
+    const EQUINOX: u32 = 80;
+
+    #[test]
+    fn parse_time_bounds() {
+        assert_eq!(parse_time("00:00").unwrap(), 0);
+        assert_eq!(parse_time("23:59").unwrap(), MINUTES_PER_DAY - 1);
+        assert_eq!(parse_time(" 7:05 ").unwrap(), 7 * 60 + 5);
+        assert!(parse_time("24:00").is_err());
+        assert!(parse_time("12:60").is_err());
+        assert!(parse_time("1200").is_err());
+    }
+
+    #[test]
+    fn fixed_hits_points_at_their_exact_minute() {
+        let schedule = fixed(&[("22:00", 20), ("07:00", 80)]);
+        assert_eq!(evaluate(&schedule, parse_time("07:00").unwrap(), 1, 0).unwrap(), Some(80));
+        assert_eq!(evaluate(&schedule, parse_time("22:00").unwrap(), 1, 0).unwrap(), Some(20));
+        // halfway between 07:00 & 22:00
+        assert_eq!(evaluate(&schedule, parse_time("14:30").unwrap(), 1, 0).unwrap(), Some(50));
+    }
+
+    #[test]
+    fn fixed_wraps_around_midnight() {
+        let schedule = fixed(&[("07:00", 80), ("22:00", 20)]);
+        // 22:00 -> 07:00 spans 9h, midnight is 2h into it
+        assert_eq!(evaluate(&schedule, 0, 1, 0).unwrap(), Some(33));
+        assert_eq!(evaluate(&schedule, parse_time("02:30").unwrap(), 1, 0).unwrap(), Some(50));
+        // either side of midnight is on the same segment
+        assert_eq!(evaluate(&schedule, parse_time("23:59").unwrap(), 1, 0).unwrap(), Some(33));
+        assert_eq!(evaluate(&schedule, parse_time("22:09").unwrap(), 1, 0).unwrap(), Some(21));
+        assert_eq!(evaluate(&schedule, parse_time("06:59").unwrap(), 1, 0).unwrap(), Some(80));
+    }
+
+    #[test]
+    fn fixed_edge_cases() {
+        assert_eq!(evaluate(&fixed(&[]), 600, 1, 0).unwrap(), None);
+        assert_eq!(evaluate(&fixed(&[("12:00", 40)]), 0, 1, 0).unwrap(), Some(40));
+        assert_eq!(evaluate(&fixed(&[("12:00", 140)]), 720, 1, 0).unwrap(), Some(100));
+        assert!(evaluate(&fixed(&[("25:00", 40)]), 0, 1, 0).is_err());
+    }
+
+    #[test]
+    fn sun_day_night_and_transition_midpoints() {
+        let sun = sun(60);
+        let (sunrise, sunset) = sun_times(EQUINOX, sun.latitude, sun.longitude, 0).unwrap();
+        assert!((340.0..380.0).contains(&sunrise), "sunrise at {}", sunrise);
+        assert!((1060.0..1100.0).contains(&sunset), "sunset at {}", sunset);
+
+        let schedule = Schedule::Sun(sun.clone());
+        assert_eq!(evaluate(&schedule, 12 * 60, EQUINOX, 0).unwrap(), Some(80));
+        assert_eq!(evaluate(&schedule, 0, EQUINOX, 0).unwrap(), Some(20));
+        assert_eq!(evaluate(&schedule, MINUTES_PER_DAY - 1, EQUINOX, 0).unwrap(), Some(20));
+
+        // the transitions are centered on sunrise & sunset
+        let at_sunrise = daylight(&sun, sunrise.round() as u32, EQUINOX, 0);
+        let at_sunset = daylight(&sun, sunset.round() as u32, EQUINOX, 0);
+        assert!((at_sunrise - 0.5).abs() < 0.02, "daylight at sunrise {}", at_sunrise);
+        assert!((at_sunset - 0.5).abs() < 0.02, "daylight at sunset {}", at_sunset);
+        assert_eq!(daylight(&sun, (sunrise - 31.0) as u32, EQUINOX, 0), 0.0);
+        assert_eq!(daylight(&sun, (sunrise + 31.0) as u32, EQUINOX, 0), 1.0);
+    }
+
+    #[test]
+    fn sun_without_transition_steps_at_sunrise() {
+        let sun = sun(0);
+        let (sunrise, _) = sun_times(EQUINOX, sun.latitude, sun.longitude, 0).unwrap();
+        assert_eq!(daylight(&sun, sunrise.floor() as u32, EQUINOX, 0), 0.0);
+        assert_eq!(daylight(&sun, sunrise.ceil() as u32, EQUINOX, 0), 1.0);
+    }
+
+    #[test]
+    fn sun_follows_the_utc_offset() {
+        let sun = sun(60);
+        let (utc_sunrise, _) = sun_times(EQUINOX, 0.0, 0.0, 0).unwrap();
+        let (local_sunrise, _) = sun_times(EQUINOX, 0.0, 0.0, 120).unwrap();
+        assert!((local_sunrise - utc_sunrise - 120.0).abs() < 1e-9);
+        // 07:00 local with +2h is before sunrise
+        assert_eq!(evaluate(&Schedule::Sun(sun), 7 * 60, EQUINOX, 120).unwrap(), Some(20));
+    }
+
+    #[test]
+    fn sun_polar_day_and_night() {
+        let arctic = SunSchedule { latitude: 80.0, ..sun(60) };
+        // late june & late december
+        assert_eq!(sun_times(172, 80.0, 0.0, 0), Err(true));
+        assert_eq!(sun_times(355, 80.0, 0.0, 0), Err(false));
+        assert_eq!(daylight(&arctic, 0, 172, 0), 1.0);
+        assert_eq!(daylight(&arctic, 12 * 60, 355, 0), 0.0);
+    }
+
+    #[test]
+    fn day_of_year_counts_leap_days() {
+        assert_eq!(day_of_year(2025, 1, 1), 1);
+        assert_eq!(day_of_year(2025, 3, 1), 60);
+        assert_eq!(day_of_year(2024, 3, 1), 61);
+        assert_eq!(day_of_year(2024, 12, 31), 366);
+    }
+}
@@ -0,0 +1,94 @@
+/*
+ * one round trip per backend & monitor, every write puts back what's already there:
+ * brightness is set to its current value, the overlay gets its current level again
+ * & the gamma ramp is rewritten as is
+*/
+use serde::Serialize;
+use tracing::{info, warn};
+use crate::{
+    app::AppState,
+    gamma, overlay,
+    monitors::{self, MonitorDeviceImpl, DimBackend},
+    overlay::Overlay,
+};
+
+#[derive(Debug, Serialize, Clone, Eq, PartialEq)]
+pub struct BackendCheck {
+    pub works: bool,
+    pub error: Option<String>,
+}
+
+impl From<anyhow::Result<()>> for BackendCheck {
+    fn from(result: anyhow::Result<()>) -> Self {
+        Self { works: result.is_ok(), error: result.err().map(|e| e.to_string()) }
+    }
+}
+
+#[derive(Debug, Serialize, Clone, Eq, PartialEq)]
+pub struct SelfTestReport {
+    pub id: String,
+    pub device_name: String,
+    pub hardware: BackendCheck,
+    pub overlay: BackendCheck,
+    pub gamma: BackendCheck,
+}
+
+fn check_hardware(dev: &MonitorDeviceImpl) -> anyhow::Result<()> {
+    let current = dev.get()?;
+    dev.set(current)
+}
+
+async fn check_overlay(state: &AppState, dev: &MonitorDeviceImpl, level: u8) -> anyhow::Result<()> {
+    let device_name = monitors::gdi_device_name(&dev.device_name).to_string();
+    if !overlay::overlay_devices().contains(&device_name) {
+        anyhow::bail!("no overlay window for {}", device_name);
+    }
+    let tx = state.overlay_tx.lock().await.clone()
+        .ok_or_else(|| anyhow::anyhow!("overlay channel not initialized"))?;
+    tx.send(Overlay { level, device_name }).await?;
+    Ok(())
+}
+
+/// checks every managed monitor & caches the reports in `AppState::self_test`.
+/// monitors whose overlay doesn't work but gamma does are switched to gamma dimming
+pub async fn run(state: &AppState) -> Vec<SelfTestReport> {
+    let devices = state.monitor_device.lock().await.clone();
+    let mut reports = Vec::with_capacity(devices.len());
+
+    for dev in devices {
+        let dim = state.dim_state.lock().await.get(&dev.device_name).copied().unwrap_or_default();
+        // the overlay only holds a level while it's the active backend
+        let overlay_level = match dim.backend {
+            DimBackend::Overlay => dim.level,
+            DimBackend::Both => monitors::split_dim(dim.level).0,
+            DimBackend::Gamma => 0,
+        };
+
+        let hardware = tauri::async_runtime::spawn_blocking({
+            let dev = dev.clone();
+            move || check_hardware(&dev)
+        })
+            .await
+            .unwrap_or_else(|e| Err(e.into()));
+        let report = SelfTestReport {
+            id: dev.id.clone(),
+            device_name: dev.device_name.clone(),
+            hardware: hardware.into(),
+            overlay: check_overlay(state, &dev, overlay_level).await.into(),
+            gamma: gamma::rewrite(&dev.device_name).into(),
+        };
+
+        if !report.overlay.works && report.gamma.works && dim.level == 0 {
+            info!("overlay unavailable on '{}', dimming it through gamma", dev.friendly_name);
+            state.dim_state.lock().await.entry(dev.device_name.clone()).or_default().backend = DimBackend::Gamma;
+        }
+        if !report.hardware.works && !report.overlay.works && !report.gamma.works {
+            warn!("no working backend for '{}'", dev.friendly_name);
+        }
+        reports.push(report);
+    }
+
+    info!("self test finished: {:?}", reports);
+    *state.self_test.lock().await = reports.clone();
+    reports
+}
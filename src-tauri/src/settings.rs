@@ -0,0 +1,402 @@
+/*
+ * user settings, persisted as json in the same dir as `fade.log`
+*/
+use std::{
+    fs,
+    path::PathBuf,
+    collections::{HashMap, HashSet},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use anyhow::anyhow;
+use tauri::Manager;
+use serde::{
+    Serialize,
+    Deserialize
+};
+use crate::{app, conflicts::ConflictRule, monitors::{ColorTemp, DimSplit}, schedule::Schedule};
+
+const SETTINGS_FILE: &str = "settings.json";
+
+/// report layout of a vendor hid brightness control
+#[derive(Debug, Serialize, Deserialize, Clone, Eq, PartialEq)]
+pub struct HidReportFormat {
+    /// first byte of the feature report
+    pub report_id: u8,
+    /// total report length in bytes, including the id
+    pub length: usize,
+    /// byte offset of the brightness value
+    pub value_offset: usize,
+    /// 1 or 2 bytes (little endian)
+    pub value_width: u8,
+    /// raw value for 0%
+    pub min: u16,
+    /// raw value for 100%
+    pub max: u16,
+}
+
+/// maps a monitor model to its usb hid brightness control
+#[derive(Debug, Serialize, Deserialize, Clone, Eq, PartialEq)]
+pub struct HidDeviceConfig {
+    /// matched against the monitor's friendly name
+    pub model: String,
+    pub vendor_id: u16,
+    pub product_id: u16,
+    pub report: HidReportFormat,
+}
+
+/// monitors changed by scrolling over the tray icon
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ScrollTarget {
+    #[default]
+    Primary,
+    All,
+    /// monitor under the mouse cursor
+    Cursor,
+}
+
+/// what happens to a monitor's brightness & dim once the displays wake up
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum WakeBehavior {
+    /// put back the brightness fade last set & the dim level, monitors often reset them while asleep
+    #[default]
+    Reapply,
+    /// drop the dim, so a forgotten dim doesn't greet the user with a dark screen
+    Clear,
+}
+
+/// targets of the reading mode preset
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Eq, PartialEq)]
+#[serde(default)]
+pub struct ReadingMode {
+    pub brightness: u32,
+    /// applied through the gamma ramp, works on every monitor
+    pub kelvin: u32,
+    /// ddc/ci only, skipped on monitors without contrast control
+    pub contrast: u32,
+}
+
+impl Default for ReadingMode {
+    fn default() -> Self {
+        Self { brightness: 40, kelvin: 4500, contrast: 60 }
+    }
+}
+
+/// what a monitor had before reading mode, `None` where it couldn't be read
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, Eq, PartialEq)]
+pub struct ReadingModeRestore {
+    pub brightness: Option<u32>,
+    pub contrast: Option<u32>,
+}
+
+/// what a click on the tray icon does
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TrayAction {
+    None,
+    ToggleWindow,
+    /// clears every dim & brings it back on the next toggle
+    ToggleDim,
+    /// steps the scroll target through 25/50/75/100%
+    CycleBrightness,
+    ShowMenu,
+    OpenLogs,
+}
+
+/// what a monitor's own button does, see `buttons.rs`
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ButtonAction {
+    ToggleDim,
+    CycleBrightness,
+    ToggleReadingMode,
+}
+
+/// the action run when the monitor reports a change of `vcp` from its osd,
+/// e.g. `0x60` for the input button
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Eq, PartialEq)]
+pub struct ButtonMapping {
+    pub vcp: u8,
+    pub action: ButtonAction,
+}
+
+/// last brightness & dim a user set on a monitor, restored when it shows up
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, Eq, PartialEq)]
+pub struct MonitorLevels {
+    /// `None` until the brightness itself was changed, only the dim was
+    pub brightness: Option<u32>,
+    pub dim_level: u8,
+}
+
+/// edid manufacturer & serial number, identifies a monitor whichever port it's on
+#[derive(Debug, Serialize, Deserialize, Clone, Eq, PartialEq)]
+pub struct EdidSerial {
+    /// pnp manufacturer id, e.g. `DEL`
+    pub manufacturer: String,
+    pub serial: String,
+}
+
+/// what a profile puts a monitor on, see `profiles.rs`
+#[derive(Debug, Serialize, Deserialize, Clone, Eq, PartialEq)]
+pub struct ProfileEntry {
+    /// matches the monitor again after its `id` changed
+    #[serde(default)]
+    pub serial: Option<EdidSerial>,
+    pub brightness: u32,
+    #[serde(default)]
+    pub dim_level: u8,
+    /// left as is when `None`
+    #[serde(default)]
+    pub color_temp: Option<ColorTemp>,
+}
+
+/// global shortcuts acting on the monitor under the cursor, `ctrl+alt+up` style.
+/// `None` leaves one unregistered, see `hotkeys.rs`
+#[derive(Debug, Serialize, Deserialize, Clone, Eq, PartialEq)]
+#[serde(default)]
+pub struct Hotkeys {
+    pub brightness_up: Option<String>,
+    pub brightness_down: Option<String>,
+    /// between no dim & `dim_level`
+    pub toggle_dim: Option<String>,
+    /// brightness percentage per press
+    pub step: u32,
+    /// dim alpha the toggle goes to
+    pub dim_level: u8,
+}
+
+impl Default for Hotkeys {
+    fn default() -> Self {
+        Self {
+            brightness_up: Some("ctrl+alt+up".to_string()),
+            brightness_down: Some("ctrl+alt+down".to_string()),
+            toggle_dim: Some("ctrl+alt+d".to_string()),
+            step: 10,
+            dim_level: 128,
+        }
+    }
+}
+
+/// tray click mapping, a double click also sends the two single left clicks before it
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Eq, PartialEq)]
+#[serde(default)]
+pub struct TrayActions {
+    pub left: TrayAction,
+    pub right: TrayAction,
+    pub middle: TrayAction,
+    pub double_click: TrayAction,
+}
+
+impl Default for TrayActions {
+    fn default() -> Self {
+        Self {
+            left: TrayAction::ToggleWindow,
+            right: TrayAction::ShowMenu,
+            middle: TrayAction::None,
+            double_click: TrayAction::None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct Settings {
+    /// only used when built with the `hid` feature
+    pub hid_devices: Vec<HidDeviceConfig>,
+    /// brightness & dim per monitor `id`, saved from the slider & websocket commands
+    pub levels: HashMap<String, MonitorLevels>,
+    /// put `levels` back on startup & reconnect
+    pub restore_levels: bool,
+    /// named setups like "day" or "movie", name -> monitor `id` -> entry
+    pub profiles: HashMap<String, HashMap<String, ProfileEntry>>,
+    /// user given monitor labels, keyed by monitor `id`
+    pub labels: HashMap<String, String>,
+    /// stable names for scripts, alias -> monitor `id`. accepted wherever a `device_name` is
+    pub aliases: HashMap<String, String>,
+    /// warn when another app keeps changing ddc/ci brightness
+    pub detect_ddcci_contention: bool,
+    /// clear the dim of the monitor hosting the window while it's visible
+    pub reveal_window_monitor: bool,
+    /// seconds the device list must stay stable before automatic features kick in
+    pub startup_grace_secs: u64,
+    pub scroll_target: ScrollTarget,
+    /// brightness percentage changed per wheel notch
+    pub scroll_step: u32,
+    /// brief on-screen feedback for brightness changed outside the window
+    pub show_osd: bool,
+    /// extra programs reported by `check_conflicts`, on top of the built-in list
+    pub conflict_rules: Vec<ConflictRule>,
+    /// color temperature per monitor `id`, re-applied on startup & reconnect
+    pub color_temps: HashMap<String, ColorTemp>,
+    /// overlay fill color `[r, g, b]`, dimming raises its opacity
+    pub overlay_color: [u8; 3],
+    /// per monitor `id` override of `overlay_color`. e-ink & transflective panels
+    /// lose contrast toward white, so dimming them with a white overlay looks right
+    pub overlay_colors: HashMap<String, [u8; 3]>,
+    /// read once on startup, the native tray menu is attached accordingly
+    pub tray_actions: TrayActions,
+    /// read once on startup
+    pub hotkeys: Hotkeys,
+    /// measured max luminance per monitor `id`, takes precedence over the edid
+    pub max_nits: HashMap<String, f64>,
+    pub reading_mode: ReadingMode,
+    /// kept across restarts, monitors get it back on startup & reconnect
+    pub reading_mode_enabled: bool,
+    /// values to restore per monitor `id` once reading mode is turned off
+    pub reading_mode_restore: HashMap<String, ReadingModeRestore>,
+    /// monitor `id`s whose brightness & dim can't be changed until unlocked
+    pub locked: HashSet<String>,
+    /// monitor `id`s whose overlay dim moves to the gamma ramp while a fullscreen app covers them,
+    /// exclusive fullscreen can hide the overlay. see `fullscreen_changes`
+    pub fullscreen_gamma: HashSet<String>,
+    /// required by `unlock_monitor` when set, for shared/kiosk machines
+    pub lock_pin: Option<String>,
+    /// brightness reads within this many ms are reused instead of hitting ddc/ci again
+    pub brightness_cache_ms: u64,
+    /// simulated monitors instead of real ones, for ui development. `--mock-monitors N` overrides it
+    pub mock_monitors: u32,
+    pub wake_behavior: WakeBehavior,
+    /// keep brightness changes in memory for `brightness_history`
+    pub record_history: bool,
+    /// samples kept per monitor, older ones are dropped
+    pub history_capacity: usize,
+    /// also append recorded samples to `brightness_history.csv`
+    pub history_to_file: bool,
+    /// drop gamma color temperatures while windows night light is on, instead of warming twice
+    pub defer_to_night_light: bool,
+    /// internal display brightness through wmi/powershell when ioctl fails, spawns a process per call
+    pub wmi_fallback: bool,
+    /// read every ddc/ci brightness write back & fail when the monitor didn't take it, costs a read per write
+    pub verify_ddcci_writes: bool,
+    /// brightness writes a second per ddc/ci monitor, more wait their turn. 0 doesn't limit
+    pub ddcci_max_writes_per_sec: u32,
+    /// ms between brightness polls, longer saves power on battery. at least 250, read at startup
+    pub brightness_poll_ms: u64,
+    /// ms between monitor rescans for (dis)connects. at least 250, read at startup
+    pub device_poll_ms: u64,
+    /// ms the window may be unfocused before it hides, focus coming back within it cancels the hide
+    pub hide_grace_ms: u64,
+    /// launch at login, in the tray. the `Run` entry is made to match it on every start
+    pub autostart: bool,
+    /// ms a large slider jump eases over, 0 jumps at once
+    pub brightness_transition_ms: u64,
+    /// ms a gamma color temperature change eases over, 0 switches at once
+    pub color_temp_transition_ms: u64,
+    /// run `self_test` once the startup grace is over, only until it ran once
+    pub self_test_on_first_launch: bool,
+    pub self_tested: bool,
+    pub schedule: Schedule,
+    /// follow `schedule` instead of only previewing it, see `schedule_changes`
+    pub apply_schedule: bool,
+    /// how `DimBackend::Both` shares a dim between the overlay & gamma, see `DimSplit`
+    pub dim_split: DimSplit,
+    /// lowest brightness percentage per monitor `id`, `DEFAULT_MIN_BRIGHTNESS` for the rest.
+    /// a percentage of the monitor's range, not its ddc/ci raw minimum. darker is the dim's job
+    pub min_brightness: HashMap<String, u32>,
+    /// osd button mappings per pnp model (`GSM5B7F`), only these models are polled
+    pub monitor_buttons: HashMap<String, Vec<ButtonMapping>>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            hid_devices: Vec::new(),
+            levels: HashMap::new(),
+            restore_levels: true,
+            profiles: HashMap::new(),
+            labels: HashMap::new(),
+            aliases: HashMap::new(),
+            detect_ddcci_contention: false,
+            reveal_window_monitor: true,
+            startup_grace_secs: 15,
+            scroll_target: ScrollTarget::Primary,
+            scroll_step: 5,
+            show_osd: true,
+            conflict_rules: Vec::new(),
+            color_temps: HashMap::new(),
+            overlay_color: [0, 0, 0],
+            overlay_colors: HashMap::new(),
+            tray_actions: TrayActions::default(),
+            hotkeys: Hotkeys::default(),
+            max_nits: HashMap::new(),
+            reading_mode: ReadingMode::default(),
+            reading_mode_enabled: false,
+            reading_mode_restore: HashMap::new(),
+            locked: HashSet::new(),
+            fullscreen_gamma: HashSet::new(),
+            lock_pin: None,
+            brightness_cache_ms: 1500,
+            mock_monitors: 0,
+            wake_behavior: WakeBehavior::Reapply,
+            record_history: false,
+            history_capacity: 1024,
+            history_to_file: false,
+            defer_to_night_light: false,
+            wmi_fallback: false,
+            verify_ddcci_writes: false,
+            ddcci_max_writes_per_sec: 10,
+            brightness_poll_ms: 2000,
+            device_poll_ms: 10000,
+            hide_grace_ms: 200,
+            autostart: false,
+            brightness_transition_ms: 300,
+            color_temp_transition_ms: 500,
+            self_test_on_first_launch: true,
+            self_tested: false,
+            schedule: Schedule::default(),
+            apply_schedule: false,
+            dim_split: DimSplit::default(),
+            min_brightness: HashMap::new(),
+            monitor_buttons: HashMap::new(),
+        }
+    }
+}
+
+impl Settings {
+    fn path() -> anyhow::Result<PathBuf> {
+        Ok(app::app_handle()
+            .path()
+            .app_local_data_dir()?
+            .join(SETTINGS_FILE))
+    }
+
+    /// a missing file isn't an error, defaults are used instead
+    pub fn load() -> anyhow::Result<Self> {
+        let path = Self::path()?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let raw = fs::read_to_string(&path)?;
+        serde_json::from_str(&raw)
+            .map_err(|e| anyhow!("failed to parse settings file: {:?}, err: {}", path, e))
+    }
+
+    /// overlay color of a monitor, its own or the global default
+    pub fn overlay_color_for(&self, id: &str) -> [u8; 3] {
+        self.overlay_colors.get(id).copied().unwrap_or(self.overlay_color)
+    }
+
+    /// moves the settings file aside as `settings.json.<unix secs>.bak`,
+    /// the next `load` gets defaults. `None` when there was no file
+    pub fn backup_and_remove() -> anyhow::Result<Option<PathBuf>> {
+        let path = Self::path()?;
+        if !path.exists() {
+            return Ok(None);
+        }
+        let secs = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default();
+        let backup = path.with_file_name(format!("{}.{}.bak", SETTINGS_FILE, secs));
+        fs::rename(&path, &backup)?;
+        Ok(Some(backup))
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        let path = Self::path()?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,217 @@
+/*
+ * shareable settings blob, so a calibration can be handed to someone with the same monitor.
+ * monitors are matched by their `id`, then their edid serial & last by their pnp model (`GSM5B7F`),
+ * the model only when it's a single monitor on both ends so identical monitors don't collide
+*/
+use std::collections::HashMap;
+use anyhow::{anyhow, bail};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use serde::{
+    Serialize,
+    Deserialize
+};
+use crate::{
+    gamma,
+    settings::{Settings, ReadingMode, ProfileEntry, EdidSerial},
+    monitors::{pnp_model, ColorTemp, MonitorDeviceImpl},
+};
+
+/// bumped on incompatible changes, older blobs are rejected
+const SHARE_VERSION: u32 = 2;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct SharedMonitor {
+    /// `monitorDevicePath` on the exporting machine
+    pub id: String,
+    pub serial: Option<EdidSerial>,
+    /// pnp model from the `monitorDevicePath`
+    pub model: Option<String>,
+    /// friendly name, only for people reading the blob
+    pub name: String,
+    pub color_temp: Option<ColorTemp>,
+    pub overlay_color: Option<[u8; 3]>,
+    pub max_nits: Option<f64>,
+    /// `Settings::min_brightness`
+    pub min_brightness: Option<u32>,
+    /// the monitor's entry of each profile, by profile name
+    pub profiles: HashMap<String, ProfileEntry>,
+}
+
+impl SharedMonitor {
+    fn same_serial(&self, dev: &MonitorDeviceImpl) -> bool {
+        self.serial.is_some() && self.serial == dev.edid_serial()
+    }
+
+    fn same_model(&self, dev: &MonitorDeviceImpl) -> bool {
+        matches!((self.model.as_deref(), pnp_model(&dev.id)), (Some(a), Some(b)) if a.eq_ignore_ascii_case(b))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct SharedSettings {
+    pub version: u32,
+    pub overlay_color: [u8; 3],
+    pub reading_mode: ReadingMode,
+    pub monitors: Vec<SharedMonitor>,
+}
+
+impl SharedSettings {
+    /// the connected monitors' part of `settings`, monitors without anything to share are left out
+    pub fn collect(settings: &Settings, devices: &[MonitorDeviceImpl]) -> Self {
+        let monitors = devices.iter()
+            .filter_map(|d| {
+                let profiles = settings.profiles.iter()
+                    .filter_map(|(name, entries)| Some((name.clone(), entries.get(&d.id)?.clone())))
+                    .collect();
+                let shared = SharedMonitor {
+                    id: d.id.clone(),
+                    serial: d.edid_serial(),
+                    model: pnp_model(&d.id).map(str::to_string),
+                    name: d.friendly_name.clone(),
+                    color_temp: settings.color_temps.get(&d.id).copied(),
+                    overlay_color: settings.overlay_colors.get(&d.id).copied(),
+                    max_nits: settings.max_nits.get(&d.id).copied(),
+                    min_brightness: settings.min_brightness.get(&d.id).copied(),
+                    profiles,
+                };
+                let empty = shared.color_temp.is_none()
+                    && shared.overlay_color.is_none()
+                    && shared.max_nits.is_none()
+                    && shared.min_brightness.is_none()
+                    && shared.profiles.is_empty();
+                (!empty).then_some(shared)
+            })
+            .collect();
+
+        Self {
+            version: SHARE_VERSION,
+            overlay_color: settings.overlay_color,
+            reading_mode: settings.reading_mode,
+            monitors,
+        }
+    }
+
+    pub fn encode(&self) -> anyhow::Result<String> {
+        Ok(URL_SAFE_NO_PAD.encode(serde_json::to_vec(self)?))
+    }
+
+    /// decodes & validates, nothing is applied on error
+    pub fn decode(blob: &str) -> anyhow::Result<Self> {
+        let raw = URL_SAFE_NO_PAD.decode(blob.trim())
+            .map_err(|e| anyhow!("settings blob is not valid base64: {}", e))?;
+        let shared: Self = serde_json::from_slice(&raw)
+            .map_err(|e| anyhow!("settings blob is malformed: {}", e))?;
+        shared.validate()?;
+        Ok(shared)
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        if self.version != SHARE_VERSION {
+            bail!("settings blob version {} is not supported, expected {}", self.version, SHARE_VERSION);
+        }
+        for (i, monitor) in self.monitors.iter().enumerate() {
+            if self.monitors[..i].iter().any(|m| m.id == monitor.id) {
+                bail!("monitor '{}' is in the settings blob twice", monitor.name);
+            }
+            let temps = monitor.color_temp.iter()
+                .chain(monitor.profiles.values().filter_map(|e| e.color_temp.as_ref()));
+            for temp in temps {
+                if !valid_kelvin(temp.kelvin) {
+                    bail!("invalid color temperature for '{}': {}k", monitor.name, temp.kelvin);
+                }
+            }
+            if let Some(nits) = monitor.max_nits {
+                if !nits.is_finite() || nits <= 0.0 {
+                    bail!("invalid max luminance for '{}': {}", monitor.name, nits);
+                }
+            }
+            if monitor.min_brightness.is_some_and(|floor| floor > 100) {
+                bail!("invalid minimum brightness for '{}': {:?}", monitor.name, monitor.min_brightness);
+            }
+            for (profile, entry) in &monitor.profiles {
+                if profile.trim().is_empty() {
+                    bail!("profile of '{}' has an empty name", monitor.name);
+                }
+                if entry.brightness > 100 {
+                    bail!("invalid brightness for '{}' in profile '{}': {}", monitor.name, profile, entry.brightness);
+                }
+            }
+        }
+        if self.reading_mode.brightness > 100 || self.reading_mode.contrast > 100 {
+            bail!("reading mode brightness & contrast must be within 0..=100");
+        }
+        if !valid_kelvin(self.reading_mode.kelvin) {
+            bail!("reading mode color temperature must be within {}k..={}k", gamma::MIN_KELVIN, gamma::MAX_KELVIN);
+        }
+        Ok(())
+    }
+
+    /// each connected monitor with the shared monitor it gets, every shared monitor goes to one at most.
+    /// all `id` matches come first, then serials & then models, so a weaker match can't take a monitor
+    /// a stronger one would have gotten
+    fn pair<'a>(&'a self, devices: &'a [MonitorDeviceImpl]) -> Vec<(&'a MonitorDeviceImpl, &'a SharedMonitor)> {
+        let unique_model = |dev: &MonitorDeviceImpl, shared: &SharedMonitor| {
+            shared.same_model(dev)
+                && devices.iter().filter(|d| shared.same_model(d)).count() == 1
+                && self.monitors.iter().filter(|m| m.same_model(dev)).count() == 1
+        };
+        type Rule<'r> = &'r dyn Fn(&MonitorDeviceImpl, &SharedMonitor) -> bool;
+        let rules: [Rule; 3] = [
+            &|dev, shared| shared.id == dev.id,
+            &|dev, shared| shared.same_serial(dev),
+            &unique_model,
+        ];
+
+        let mut pairs: Vec<(&MonitorDeviceImpl, &SharedMonitor)> = Vec::new();
+        for rule in rules {
+            for dev in devices {
+                if pairs.iter().any(|(d, _)| d.id == dev.id) {
+                    continue;
+                }
+                let shared = self.monitors.iter()
+                    .find(|m| rule(dev, m) && !pairs.iter().any(|(_, taken)| taken.id == m.id));
+                if let Some(shared) = shared {
+                    pairs.push((dev, shared));
+                }
+            }
+        }
+        pairs
+    }
+
+    /// merges into `settings` for every connected monitor matching a shared one,
+    /// returns the names of the monitors which got something
+    pub fn apply(&self, settings: &mut Settings, devices: &[MonitorDeviceImpl]) -> Vec<String> {
+        settings.overlay_color = self.overlay_color;
+        settings.reading_mode = self.reading_mode;
+
+        let mut matched = Vec::new();
+        for (dev, shared) in self.pair(devices) {
+            if let Some(temp) = shared.color_temp {
+                settings.color_temps.insert(dev.id.clone(), temp);
+            }
+            if let Some(color) = shared.overlay_color {
+                settings.overlay_colors.insert(dev.id.clone(), color);
+            }
+            if let Some(nits) = shared.max_nits {
+                settings.max_nits.insert(dev.id.clone(), nits);
+            }
+            if let Some(floor) = shared.min_brightness {
+                settings.min_brightness.insert(dev.id.clone(), floor);
+            }
+            for (profile, entry) in &shared.profiles {
+                // the serial is the one of the monitor it lands on, not the exporter's
+                settings.profiles.entry(profile.trim().to_string())
+                    .or_default()
+                    .insert(dev.id.clone(), ProfileEntry { serial: dev.edid_serial(), ..entry.clone() });
+            }
+            matched.push(dev.friendly_name.clone());
+        }
+        matched
+    }
+}
+
+fn valid_kelvin(kelvin: u32) -> bool {
+    (gamma::MIN_KELVIN..=gamma::MAX_KELVIN).contains(&kelvin)
+}
@@ -0,0 +1,306 @@
+/*
+ * tray icon gestures: configurable click actions & scroll to change brightness,
+ * the tray api doesn't report scrolling so a low level mouse hook watches the wheel while the icon is hovered
+*/
+use tracing::{debug, error, warn};
+use std::sync::{
+    OnceLock,
+    atomic::{AtomicBool, AtomicU8, Ordering},
+};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use windows::Win32::{
+    Foundation::{HINSTANCE, LPARAM, LRESULT, WPARAM},
+    System::LibraryLoader::GetModuleHandleW,
+    UI::WindowsAndMessaging::{
+        CallNextHookEx, GetMessageW, SetWindowsHookExW, UnhookWindowsHookEx, MSG, MSLLHOOKSTRUCT,
+        WH_MOUSE_LL, WM_MOUSEWHEEL,
+    },
+};
+use tauri::{
+    AppHandle, Manager, PhysicalPosition, Wry,
+    image::Image,
+    menu::Menu,
+    tray::MouseButton,
+};
+use tauri_plugin_opener::OpenerExt;
+use crate::{app, events, log, monitors, utils, app::AppState, monitors::MonitorInfo, settings::{ScrollTarget, TrayAction}};
+
+pub const TRAY_ID: &str = "fade-tray";
+pub const TOOLTIP: &str = "fade & brightness";
+
+/// quarters filled of the glyph the icon shows, `NO_GLYPH` while it's the app icon
+static SHOWN_GLYPH: AtomicU8 = AtomicU8::new(NO_GLYPH);
+const NO_GLYPH: u8 = u8::MAX;
+const GLYPH_SIZE: u32 = 32;
+
+/// tooltip & icon follow the average brightness, the brightness watcher calls it on changes.
+/// without monitors it's back to the plain tooltip & the app icon
+pub async fn show_brightness(state: &AppState, infos: &[MonitorInfo]) {
+    let Some(tray) = state.tray.lock().await.clone() else {
+        return;
+    };
+    let average = (!infos.is_empty())
+        .then(|| infos.iter().map(|i| i.brightness).sum::<u32>() / infos.len() as u32);
+
+    let tooltip = match average {
+        Some(average) => format!("{}\nBrightness: {}%", TOOLTIP, average),
+        None => format!("{}\nno monitors", TOOLTIP),
+    };
+    if let Err(e) = tray.set_tooltip(Some(tooltip)) {
+        warn!("failed to update tray tooltip: {}", e);
+    }
+
+    let glyph = average.map_or(NO_GLYPH, |average| ((average + 12) / 25).min(4) as u8);
+    if SHOWN_GLYPH.swap(glyph, Ordering::Relaxed) == glyph {
+        return;
+    }
+    let icon = match glyph {
+        NO_GLYPH => app::app_handle().default_window_icon().cloned(),
+        quarters => Some(brightness_glyph(quarters)),
+    };
+    if let Err(e) = tray.set_icon(icon) {
+        warn!("failed to update tray icon: {}", e);
+    }
+}
+
+/// a white disc lit from the bottom up by `quarters` of 4, the rest faint
+fn brightness_glyph(quarters: u8) -> Image<'static> {
+    let size = GLYPH_SIZE as f64;
+    let center = (size - 1.0) / 2.0;
+    let radius = size / 2.0 - 2.0;
+    let lit_from = center + radius - 2.0 * radius * quarters as f64 / 4.0;
+
+    let mut rgba = Vec::with_capacity((GLYPH_SIZE * GLYPH_SIZE * 4) as usize);
+    for y in 0..GLYPH_SIZE {
+        for x in 0..GLYPH_SIZE {
+            let distance = (x as f64 - center).hypot(y as f64 - center);
+            let alpha = if distance > radius {
+                0
+            } else if y as f64 >= lit_from {
+                255
+            } else {
+                70
+            };
+            rgba.extend_from_slice(&[255, 255, 255, alpha]);
+        }
+    }
+    Image::new_owned(rgba, GLYPH_SIZE, GLYPH_SIZE)
+}
+
+/// set from the tray `Enter`/`Leave` events
+static HOVERING: AtomicBool = AtomicBool::new(false);
+/// wheel direction per message, `1` up & `-1` down
+static WHEEL_TX: OnceLock<UnboundedSender<i32>> = OnceLock::new();
+
+pub fn set_hovering(hovering: bool) {
+    HOVERING.store(hovering, Ordering::Relaxed);
+}
+
+unsafe extern "system" fn mouse_hook(code: i32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    if code >= 0 && wparam.0 as u32 == WM_MOUSEWHEEL && HOVERING.load(Ordering::Relaxed) {
+        let info = unsafe { &*(lparam.0 as *const MSLLHOOKSTRUCT) };
+        // high word is the signed wheel delta, touchpads send less than a full notch
+        let delta = (info.mouseData >> 16) as u16 as i16;
+        if let Some(tx) = WHEEL_TX.get() {
+            let _ = tx.send(delta.signum() as i32);
+        }
+    }
+    unsafe { CallNextHookEx(None, code, wparam, lparam) }
+}
+
+/// installs the hook on its own thread, low level hooks need a message loop
+pub fn init_scroll_hook() -> UnboundedReceiver<i32> {
+    let (tx, rx) = unbounded_channel();
+    if WHEEL_TX.set(tx).is_err() {
+        warn!("tray scroll hook was already initialized");
+    }
+
+    std::thread::spawn(|| unsafe {
+        let instance = GetModuleHandleW(None).ok().map(HINSTANCE::from);
+        let hook = match SetWindowsHookExW(WH_MOUSE_LL, Some(mouse_hook), instance, 0) {
+            Ok(hook) => hook,
+            Err(e) => {
+                error!("failed to install tray scroll hook: {:?}", e);
+                return;
+            }
+        };
+
+        let mut msg = MSG::default();
+        while GetMessageW(&mut msg, None, 0, 0).as_bool() {}
+        let _ = UnhookWindowsHookEx(hook);
+    });
+
+    rx
+}
+
+/// device names of the monitors a tray gesture applies to
+async fn target_devices(state: &AppState, target: ScrollTarget) -> Vec<String> {
+    let devices = state.monitor_device.lock().await;
+    match target {
+        ScrollTarget::All => devices.iter().map(|d| d.device_name.clone()).collect(),
+        ScrollTarget::Primary | ScrollTarget::Cursor => {
+            let target = match target {
+                ScrollTarget::Cursor => monitors::cursor_monitor(),
+                _ => monitors::primary_device_name(),
+            };
+            devices.iter()
+                .filter(|d| target.as_deref() == Some(monitors::gdi_device_name(&d.device_name)))
+                .map(|d| d.device_name.clone())
+                .collect()
+        }
+    }
+}
+
+/// applies a brightness step per wheel notch to the configured target
+pub async fn scroll_brightness(state: AppState, mut rx: UnboundedReceiver<i32>) {
+    while let Some(direction) = rx.recv().await {
+        let (target, step) = {
+            let settings = state.settings.lock().await;
+            (settings.scroll_target, settings.scroll_step as i32)
+        };
+
+        let device_names = target_devices(&state, target).await;
+
+        let mut levels = Vec::new();
+        for device_name in &device_names {
+            match events::step_brightness(&state, device_name, direction * step).await {
+                Ok(level) => levels.push(level),
+                Err(e) => warn!("tray scroll failed for '{}': {}", device_name, e),
+            }
+        }
+        debug!("tray scroll {:+}, new levels: {:?}", direction * step, levels);
+
+        if let (Some(tray), Some(&level)) = (app::app_handle().tray_by_id(TRAY_ID), levels.first()) {
+            let _ = tray.set_tooltip(Some(format!("brightness: {}%", level)));
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrayClick {
+    Left,
+    Right,
+    Middle,
+    Double,
+}
+
+impl From<MouseButton> for TrayClick {
+    fn from(button: MouseButton) -> Self {
+        match button {
+            MouseButton::Left => TrayClick::Left,
+            MouseButton::Right => TrayClick::Right,
+            MouseButton::Middle => TrayClick::Middle,
+        }
+    }
+}
+
+/// the tray menu, kept for `ShowMenu` on clicks the native menu doesn't handle
+pub static TRAY_MENU: OnceLock<Menu<Wry>> = OnceLock::new();
+
+/// cycle steps of `TrayAction::CycleBrightness`
+const CYCLE_STEPS: [u32; 4] = [25, 50, 75, 100];
+
+/// set while `TrayAction::ToggleDim` has the dims cleared
+static DIM_SUSPENDED: AtomicBool = AtomicBool::new(false);
+
+/// runs the action mapped to `click` in the settings
+pub fn on_click(app: &AppHandle, click: TrayClick, position: PhysicalPosition<f64>) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let state = app.state::<AppState>().inner().clone();
+        let actions = state.settings.lock().await.tray_actions;
+        let action = match click {
+            TrayClick::Left => actions.left,
+            TrayClick::Right => actions.right,
+            TrayClick::Middle => actions.middle,
+            TrayClick::Double => actions.double_click,
+        };
+
+        match action {
+            TrayAction::None => {}
+            TrayAction::ToggleWindow => toggle_window(&app, &position),
+            TrayAction::ToggleDim => toggle_dim(&state).await,
+            TrayAction::CycleBrightness => cycle_brightness(&state).await,
+            // left & right already get the native menu, see `app::run`
+            TrayAction::ShowMenu if matches!(click, TrayClick::Left | TrayClick::Right) => {}
+            TrayAction::ShowMenu => {
+                if let (Some(window), Some(menu)) = (app.get_webview_window("main"), TRAY_MENU.get()) {
+                    if let Err(e) = window.popup_menu(menu) {
+                        error!("failed to show tray menu: {}", e);
+                    }
+                }
+            }
+            TrayAction::OpenLogs => open_logs(&app),
+        }
+    });
+}
+
+fn toggle_window(app: &AppHandle, position: &PhysicalPosition<f64>) {
+    if let Some(window) = app.get_webview_window("main") {
+        let is_visible = window.is_visible().unwrap_or(false);
+        if is_visible {
+            if let Err(e) = window.hide() {
+                error!("failed to hide window: {}", e);
+            }
+            app::reveal_window_monitor(&window, false);
+        } else {
+            utils::show_tray_window(&window, position);
+            app::reveal_window_monitor(&window, true);
+        }
+    }
+}
+
+/// clears the dims without forgetting their levels, the next toggle brings them back
+pub async fn toggle_dim(state: &AppState) {
+    let devices = state.monitor_device.lock().await;
+    let overlay_tx = state.overlay_tx.lock().await;
+    // without the overlay nothing gets toggled, so the flag has to stay as it is
+    let Some(tx) = overlay_tx.as_ref() else {
+        return;
+    };
+    let suspend = !DIM_SUSPENDED.fetch_xor(true, Ordering::Relaxed);
+    let dim_state = state.dim_state.lock().await;
+
+    for dev in devices.iter() {
+        let Some(dim) = dim_state.get(&dev.device_name).filter(|d| d.level > 0) else {
+            continue;
+        };
+        let result = if suspend {
+            dev.clear_dim(dim.backend, tx).await
+        } else {
+            dev.dim(dim.level, dim.backend, tx).await
+        };
+        if let Err(e) = result {
+            warn!("failed to toggle dim for '{}': {:?}", dev.device_name, e);
+        }
+    }
+    debug!("tray dim toggle, suspended: {}", suspend);
+}
+
+/// next step above the current level of each target, wrapping to the first
+pub async fn cycle_brightness(state: &AppState) {
+    let target = state.settings.lock().await.scroll_target;
+    for device_name in target_devices(state, target).await {
+        let result = async {
+            let current = events::step_brightness(state, &device_name, 0).await?;
+            let next = CYCLE_STEPS.iter().copied().find(|&s| s > current).unwrap_or(CYCLE_STEPS[0]);
+            events::step_brightness(state, &device_name, next as i32 - current as i32).await
+        }.await;
+        if let Err(e) = result {
+            warn!("failed to cycle brightness for '{}': {}", device_name, e);
+        }
+    }
+}
+
+fn open_logs(app: &AppHandle) {
+    let path = match app.path().app_local_data_dir() {
+        Ok(dir) => dir.join(log::LOG_FILE),
+        Err(e) => {
+            error!("failed to resolve log dir: {}", e);
+            return;
+        }
+    };
+    if let Err(e) = app.opener().open_path(path.to_string_lossy(), None::<&str>) {
+        error!("failed to open log file: {}", e);
+    }
+}
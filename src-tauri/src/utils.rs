@@ -4,6 +4,7 @@ use windows::{
         Foundation::{
             WIN32_ERROR, GetLastError, LocalFree, HLOCAL
         },
+        Graphics::Gdi::{MonitorFromWindow, MONITOR_DEFAULTTONEAREST},
         System::Diagnostics::Debug::{
             FormatMessageW,
             FORMAT_MESSAGE_FROM_SYSTEM,
@@ -17,6 +18,7 @@ use tauri::{
     PhysicalPosition,
     WebviewWindow
 };
+use crate::monitors;
 
 // TODO: remove the window shadow
 pub fn show_tray_window(window: &WebviewWindow, position: &PhysicalPosition<f64>) {
@@ -54,6 +56,11 @@ pub fn show_tray_window(window: &WebviewWindow, position: &PhysicalPosition<f64>
     if let Err(e) = window.set_focus() { error!("failed to focus window: {}", e); }
 }
 
+/// gdi device name (`szDevice`) of the monitor hosting the window
+pub fn window_device_name(window: &WebviewWindow) -> Option<String> {
+    let hwnd = window.hwnd().ok()?;
+    monitors::hmonitor_device_name(unsafe { MonitorFromWindow(hwnd, MONITOR_DEFAULTTONEAREST) })
+}
 
 /// returns string by formatting win32 error
 pub fn format_win_err(err: WIN32_ERROR) -> String {
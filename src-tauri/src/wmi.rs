@@ -0,0 +1,83 @@
+/*
+ * internal display brightness through `WmiMonitorBrightness(Methods)`, by shelling out to powershell
+ * last resort: only used when `wmi_fallback` is on & the native (and hid) backends failed,
+ * for laptops where only the os brightness slider works. every call spawns a process
+*/
+use anyhow::anyhow;
+use std::{
+    process::Command,
+    os::windows::process::CommandExt,
+    sync::{OnceLock, atomic::{AtomicBool, Ordering}},
+};
+use windows::Win32::System::Threading::CREATE_NO_WINDOW;
+use crate::{
+    brightness::BrightnessBackend,
+    monitors::MonitorDeviceImpl,
+};
+
+const GET_SCRIPT: &str =
+    "(Get-CimInstance -Namespace root/WMI -ClassName WmiMonitorBrightness | Select-Object -First 1).CurrentBrightness";
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+/// probed on first use, a missing wmi class won't appear later
+static AVAILABLE: OnceLock<bool> = OnceLock::new();
+
+/// probes on the blocking pool right away, so the first fallback read doesn't pay for it
+pub fn init(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+    if enabled {
+        tauri::async_runtime::spawn_blocking(available);
+    }
+}
+
+/// enabled in the settings & the wmi class answers
+pub fn available() -> bool {
+    ENABLED.load(Ordering::Relaxed) && *AVAILABLE.get_or_init(|| {
+        let available = powershell(GET_SCRIPT).is_ok_and(|out| out.parse::<u32>().is_ok());
+        tracing::info!("wmi brightness fallback {}", if available { "available" } else { "unavailable" });
+        available
+    })
+}
+
+/// blocks until powershell exits, which takes a few hundred ms. only reached through
+/// `MonitorDeviceImpl::get` & `set`, async code calls those through `get_async` & `set_async`
+fn powershell(script: &str) -> anyhow::Result<String> {
+    let output = Command::new("powershell")
+        .args(["-NoProfile", "-NonInteractive", "-Command", script])
+        .creation_flags(CREATE_NO_WINDOW.0)
+        .output()?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "powershell exited with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// wmi only knows about the internal panel, so `device` isn't used to pick the instance
+pub struct WmiBackend;
+
+impl BrightnessBackend for WmiBackend {
+    fn get(&self, _device: &MonitorDeviceImpl) -> anyhow::Result<u32> {
+        if !available() {
+            return Err(anyhow!("wmi brightness fallback unavailable"));
+        }
+        let out = powershell(GET_SCRIPT)?;
+        out.parse::<u32>()
+            .map_err(|e| anyhow!("unexpected wmi brightness '{}': {}", out, e))
+    }
+
+    fn set(&self, _device: &MonitorDeviceImpl, percentage: u32) -> anyhow::Result<()> {
+        if !available() {
+            return Err(anyhow!("wmi brightness fallback unavailable"));
+        }
+        powershell(&format!(
+            "Get-CimInstance -Namespace root/WMI -ClassName WmiMonitorBrightnessMethods | \
+            Invoke-CimMethod -MethodName WmiSetBrightness -Arguments @{{Timeout=0; Brightness={}}} | Out-Null",
+            percentage.min(100)
+        ))?;
+        Ok(())
+    }
+}
@@ -8,12 +8,40 @@ import { LogicalSize, getCurrentWindow } from "@tauri-apps/api/window";
 const WINDOW_WIDTH = 380;
 
 type MonitorInfo = {
+  /// `monitorDevicePath`, stable identifier
+  id: string
   /// unique identifier
   device_name: string
   /// actual monitor name same as settings
   name: string
+  /// user given label
+  label: string | null
+  /// stable selector for scripts
+  alias: string | null
+  /// same for monitors in duplicate mode, they're set together
+  clone_group_id: string | null
+  /// locked by `lock_monitor`, brightness can't be changed
+  locked: boolean
+  /// `[width, height]` from the edid
+  physical_size_mm: [number, number] | null
+  dpi: number | null
+  /// edid manufacturer id & serial number, null when the edid doesn't say
+  manufacturer: string | null
+  serial: string | null
   /// brightness value
   brightness: number
+  /// ddc/ci range & value behind `brightness`, null on internal displays
+  raw_min: number | null
+  raw_current: number | null
+  raw_max: number | null
+  /// ddc/ci contrast, null on internal displays & monitors without it
+  contrast: number | null
+  /// `set_warmth` percentage, null without a gamma color temperature
+  warmth: number | null
+  /// laptop panel, ddc/ci only features don't apply
+  is_internal: boolean
+  /// "Internal", "HDMI", "DisplayPort", ...
+  connection: string
 }
 
 function App() {
@@ -22,7 +50,26 @@ function App() {
   const containerRef = useRef<HTMLDivElement>(null);
 
   useEffect(() => {
-    const socket = new WebSocket('ws://127.0.0.1:8956/ws/monitors');
+    let socket: WebSocket | null = null;
+    let closed = false;
+
+    // the address is configurable through `FADE_WS_ADDR`
+    invoke<string>("ws_url")
+      .catch(() => "ws://127.0.0.1:8956/ws/monitors")
+      .then(url => {
+        if (!closed) {
+          socket = connect(url);
+        }
+      });
+
+    return () => {
+      closed = true;
+      socket?.close();
+    };
+  }, []); 
+
+  const connect = (url: string) => {
+    const socket = new WebSocket(url);
 
     socket.onopen = () => {
       console.log("connected to websocket");
@@ -42,10 +89,8 @@ function App() {
     socket.onerror = (err: Event | Error) =>
       setErrors(prev => [...prev, (err as Error)?.message || String(err)]);
 
-    return () => {
-      socket.close();
-    };
-  }, []); 
+    return socket;
+  };
 
   useEffect(() => {
     if (containerRef.current) {
@@ -70,6 +115,16 @@ function App() {
     }
   };
 
+  /// overlay fill, a warm tint instead of black dims & cuts blue light together
+  const handleTint = async (hex: string, deviceName: string) => {
+    const color = [1, 3, 5].map(i => parseInt(hex.slice(i, i + 2), 16));
+    try {
+      await invoke("set_overlay_color", { deviceName: deviceName, color: color });
+    } catch (e) {
+      console.error("failed to set overlay color:", e);
+    }
+  };
+
   return (
     <main
       id="root"
@@ -86,18 +141,30 @@ function App() {
         className="container"
         ref={containerRef}
       >
-        {monitors.map(m => (
-          <Slider
-            displayName={m.name.toLowerCase()}
-            onChange={(val: number) => handleSlider(val, m.device_name)}
-            onDoubleClick={() => handleSlider(0, m.device_name)}
-            key={m.device_name}
-            minValue={-100}
-            maxValue={100}
-            centerValue={0}
-            brightnessValue={m.brightness}
-          />
-        ))}
+        {monitors
+          // duplicated monitors are set together, one slider per clone group
+          .filter((m, i) => !m.clone_group_id
+            || monitors.findIndex(o => o.clone_group_id === m.clone_group_id) === i)
+          .map(m => (
+            <div key={m.device_name} className="flex items-center gap-2">
+              <Slider
+                displayName={`${m.locked ? "🔒 " : ""}${(m.label ?? m.name).toLowerCase()}`}
+                onChange={(val: number) => handleSlider(val, m.device_name)}
+                onDoubleClick={() => handleSlider(0, m.device_name)}
+                minValue={-100}
+                maxValue={100}
+                centerValue={0}
+                brightnessValue={m.brightness}
+              />
+              <input
+                type="color"
+                title="dim tint"
+                className="h-5 w-5 cursor-pointer rounded bg-transparent"
+                defaultValue="#000000"
+                onChange={(e) => handleTint(e.target.value, m.device_name)}
+              />
+            </div>
+          ))}
       </div>
     </main>
   );